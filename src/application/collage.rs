@@ -0,0 +1,52 @@
+//! Provides settings of collage wallpapers composed from other wallpapers.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How cells of a collage are arranged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollageLayout {
+    /// Rows of equally sized cells. The last row is stretched if not filled.
+    #[default]
+    Grid,
+
+    /// Columns of cells sized by the aspect ratio of each image.
+    Masonry,
+}
+
+impl CollageLayout {
+    /// All layouts, for choices in UI.
+    pub const ALL: &'static [CollageLayout] = &[CollageLayout::Grid, CollageLayout::Masonry];
+
+    /// Gets display name.
+    pub fn label(self) -> &'static str {
+        match self {
+            CollageLayout::Grid => "Grid",
+            CollageLayout::Masonry => "Masonry",
+        }
+    }
+}
+
+/// Wallpapers composed onto a monitor and how they are arranged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Collage {
+    /// UUIDs of composed wallpapers, in placement order.
+    pub members: Vec<Uuid>,
+    pub layout: CollageLayout,
+
+    /// Space between and around cells, in percent of the monitor height.
+    pub gutter: f32,
+    pub background: [u8; 3],
+}
+
+impl Default for Collage {
+    fn default() -> Collage {
+        Collage {
+            members: vec![],
+            layout: CollageLayout::default(),
+            gutter: 1.0,
+            background: [0, 0, 0],
+        }
+    }
+}
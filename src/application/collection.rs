@@ -0,0 +1,161 @@
+//! Provides named collections of wallpapers which monitors can rotate through.
+
+use crate::application::{SmartQuery, Wallpaper};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Named and ordered set of wallpapers. A wallpaper may belong to several collections.
+/// Smart playlists have a query instead, and their members are computed from it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Collection {
+    uuid: Uuid,
+    pub name: String,
+
+    /// UUIDs of wallpapers, in rotation order.
+    #[serde(default)]
+    members: Vec<Uuid>,
+
+    /// Defines members of smart playlists.
+    #[serde(default)]
+    query: Option<SmartQuery>,
+}
+
+impl Collection {
+    /// Constructs new empty collection with generated UUID.
+    pub fn new(name: impl Into<String>) -> Collection {
+        Collection {
+            uuid: Uuid::new_v4(),
+            name: name.into(),
+            members: vec![],
+            query: None,
+        }
+    }
+
+    /// Constructs new smart playlist with generated UUID.
+    pub fn new_smart(name: impl Into<String>, query: SmartQuery) -> Collection {
+        Collection {
+            query: Some(query),
+            ..Collection::new(name)
+        }
+    }
+
+    /// Gets assigned UUID.
+    pub fn id(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// Gets the query if this is a smart playlist.
+    pub fn query(&self) -> Option<&SmartQuery> {
+        self.query.as_ref()
+    }
+
+    /// Replaces the query. Ignored unless this is a smart playlist.
+    pub fn set_query(&mut self, query: SmartQuery) {
+        if self.query.is_some() {
+            self.query = Some(query);
+        }
+    }
+
+    /// Checks whether the wallpaper belongs to this.
+    pub fn contains(&self, wallpaper_id: Uuid) -> bool {
+        self.members.contains(&wallpaper_id)
+    }
+
+    /// Appends the wallpaper if it is not a member yet. Ignored for smart playlists.
+    pub fn add(&mut self, wallpaper_id: Uuid) {
+        if self.query.is_none() && !self.contains(wallpaper_id) {
+            self.members.push(wallpaper_id);
+        }
+    }
+
+    /// Removes the wallpaper.
+    pub fn remove(&mut self, wallpaper_id: Uuid) {
+        self.members.retain(|id| *id != wallpaper_id);
+    }
+
+    /// Swaps the wallpaper with the previous one, or the next one if `forward`.
+    pub fn move_member(&mut self, wallpaper_id: Uuid, forward: bool) {
+        let position = match self.members.iter().position(|id| *id == wallpaper_id) {
+            Some(p) => p,
+            None => return,
+        };
+        match forward {
+            false if position > 0 => self.members.swap(position, position - 1),
+            true if position + 1 < self.members.len() => self.members.swap(position, position + 1),
+            _ => (),
+        }
+    }
+
+    /// Gets indices of member wallpapers in collection order, skipping missing ones.
+    /// Smart playlists select matching ones in library order. `now` is UNIX time in seconds.
+    pub fn select_wallpapers(&self, wallpapers: &[Wallpaper], now: i64) -> Vec<usize> {
+        if let Some(query) = &self.query {
+            return (0..wallpapers.len())
+                .filter(|&i| query.matches(&wallpapers[i], now))
+                .collect();
+        }
+        self.members
+            .iter()
+            .filter_map(|id| wallpapers.iter().position(|w| w.id() == *id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::application::Fitting;
+
+    #[test]
+    fn members_keep_order() {
+        let wallpapers: Vec<_> = ["a.png", "b.png", "c.png"]
+            .into_iter()
+            .map(|f| Wallpaper::new(f, Fitting::Cover))
+            .collect();
+        let mut collection = Collection::new("collection");
+        for index in [2, 0, 1, 0] {
+            collection.add(wallpapers[index].id());
+        }
+        assert_eq!(collection.select_wallpapers(&wallpapers, 0), [2, 0, 1]);
+
+        collection.move_member(wallpapers[0].id(), false);
+        assert_eq!(collection.select_wallpapers(&wallpapers, 0), [0, 2, 1]);
+        collection.move_member(wallpapers[0].id(), false);
+        collection.move_member(wallpapers[1].id(), true);
+        assert_eq!(collection.select_wallpapers(&wallpapers, 0), [0, 2, 1]);
+        collection.move_member(wallpapers[2].id(), true);
+        assert_eq!(collection.select_wallpapers(&wallpapers, 0), [0, 1, 2]);
+
+        // Missing ones are skipped
+        assert_eq!(collection.select_wallpapers(&wallpapers[1..], 0), [0, 1]);
+    }
+
+    #[test]
+    fn smart_playlist_follows_query() {
+        let mut wallpapers: Vec<_> = ["a.png", "b.png", "c.png"]
+            .into_iter()
+            .map(|f| Wallpaper::new(f, Fitting::Cover))
+            .collect();
+        wallpapers[0].set_rating(3);
+        wallpapers[2].set_rating(5);
+        let query = SmartQuery {
+            min_rating: 3,
+            ..Default::default()
+        };
+        let mut playlist = Collection::new_smart("playlist", query);
+
+        playlist.add(wallpapers[1].id());
+        assert!(!playlist.contains(wallpapers[1].id()));
+        assert_eq!(playlist.select_wallpapers(&wallpapers, 0), [0, 2]);
+
+        // Library order, not member order
+        wallpapers.swap(0, 2);
+        assert_eq!(playlist.select_wallpapers(&wallpapers, 0), [0, 2]);
+
+        let mut collection = Collection::new("collection");
+        collection.set_query(SmartQuery::default());
+        assert_eq!(collection.query(), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! Provides dynamic wallpapers, which change frames along the day.
+
+use crate::application::{
+    schedule::ClockTime,
+    solar::{sun_crossings, Crossing},
+};
+
+use std::{
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Direction of the sun for elevation keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SunDirection {
+    #[default]
+    Rising,
+    Setting,
+}
+
+/// A frame of dynamic wallpaper.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicFrame {
+    /// Image path, relative to the manifest.
+    pub image: String,
+
+    /// Time of day when this frame starts.
+    #[serde(default)]
+    pub time: Option<ClockTime>,
+
+    /// Sun elevation in degrees when this frame starts.
+    #[serde(default)]
+    pub elevation: Option<f64>,
+
+    /// Whether `elevation` is for morning or evening.
+    #[serde(default)]
+    pub direction: SunDirection,
+}
+
+impl DynamicFrame {
+    /// Calculates minutes since midnight when this frame starts at the date of `now`.
+    fn start_minutes(&self, now: OffsetDateTime, location: Option<(f64, f64)>) -> Option<u16> {
+        if let Some(time) = self.time {
+            return Some(time.minutes());
+        }
+
+        let (latitude, longitude) = location?;
+        let crossing = sun_crossings(
+            now.date(),
+            now.offset(),
+            latitude,
+            longitude,
+            self.elevation?,
+        );
+        let start = match (crossing, self.direction) {
+            (Crossing::Times(rise, _), SunDirection::Rising) => rise,
+            (Crossing::Times(_, set), SunDirection::Setting) => set,
+            _ => return None,
+        };
+        Some(start.hour() as u16 * 60 + start.minute() as u16)
+    }
+}
+
+/// Manifest of dynamic wallpaper, written in TOML or JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicManifest {
+    /// Latitude for elevation keys.
+    #[serde(default)]
+    pub latitude: Option<f64>,
+
+    /// Longitude for elevation keys.
+    #[serde(default)]
+    pub longitude: Option<f64>,
+
+    /// Frames in any order.
+    pub frames: Vec<DynamicFrame>,
+}
+
+impl DynamicManifest {
+    /// Loads and validates manifest.
+    pub fn load(path: &Path) -> Result<DynamicManifest> {
+        let source = read_to_string(path)?;
+        let manifest: DynamicManifest = match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("json") => serde_json::from_str(&source)?,
+            _ => toml::from_str(&source)?,
+        };
+
+        if manifest.frames.is_empty() {
+            bail!("Manifest has no frame");
+        }
+        for (i, frame) in manifest.frames.iter().enumerate() {
+            if frame.time.is_some() == frame.elevation.is_some() {
+                bail!("Frame #{i} must have either time or elevation");
+            }
+            let image_path = manifest.frame_path(path, i);
+            image::image_dimensions(&image_path)
+                .with_context(|| format!("Invalid frame image: {}", image_path.display()))?;
+        }
+
+        Ok(manifest)
+    }
+
+    /// Checks whether the file looks like a manifest.
+    pub fn is_manifest(path: &Path) -> bool {
+        matches!(
+            path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()),
+            Some(e) if e == "toml" || e == "json"
+        )
+    }
+
+    /// Gets the path of the frame image.
+    pub fn frame_path(&self, manifest_path: &Path, index: usize) -> PathBuf {
+        let base = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+        base.join(&self.frames[index].image)
+    }
+
+    /// Selects the frame for the time.
+    /// `location` is used when the manifest has none.
+    pub fn frame_at(&self, now: OffsetDateTime, location: Option<(f64, f64)>) -> Option<usize> {
+        let location = match (self.latitude, self.longitude) {
+            (Some(latitude), Some(longitude)) => Some((latitude, longitude)),
+            _ => location,
+        };
+
+        let mut starts: Vec<_> = self
+            .frames
+            .iter()
+            .enumerate()
+            .filter_map(|(i, f)| Some((f.start_minutes(now, location)?, i)))
+            .collect();
+        starts.sort_unstable();
+
+        // Before the first frame of the day, the last one continues
+        let minutes = now.hour() as u16 * 60 + now.minute() as u16;
+        starts
+            .iter()
+            .rev()
+            .find(|(start, _)| *start <= minutes)
+            .or_else(|| starts.last())
+            .map(|(_, i)| *i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::RgbaImage;
+    use tempfile::TempDir;
+    use time::{Date, Month};
+
+    fn at(hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2022, Month::December, 21)
+            .and_then(|d| d.with_hms(hour, minute, 0))
+            .expect("Should be valid")
+            .assume_utc()
+    }
+
+    #[test]
+    fn load_toml_and_json() {
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        for name in ["morning.png", "night.png"] {
+            RgbaImage::new(4, 4)
+                .save(directory.path().join(name))
+                .expect("Failed to save");
+        }
+
+        let toml_path = directory.path().join("scene.toml");
+        std::fs::write(
+            &toml_path,
+            r#"
+                [[frames]]
+                image = "morning.png"
+                time = "06:00"
+
+                [[frames]]
+                image = "night.png"
+                elevation = -6.0
+                direction = "Setting"
+            "#,
+        )
+        .expect("Failed to write");
+        let manifest = DynamicManifest::load(&toml_path).expect("Should load");
+        assert_eq!(manifest.frames.len(), 2);
+        assert_eq!(
+            manifest.frame_path(&toml_path, 1),
+            directory.path().join("night.png")
+        );
+
+        let json_path = directory.path().join("scene.json");
+        std::fs::write(
+            &json_path,
+            r#"{ "frames": [{ "image": "missing.png", "time": "06:00" }] }"#,
+        )
+        .expect("Failed to write");
+        assert!(DynamicManifest::load(&json_path).is_err());
+    }
+
+    #[test]
+    fn frame_by_time_and_elevation() {
+        let frame = |time: Option<&str>, elevation, direction| DynamicFrame {
+            image: String::new(),
+            time: time.map(|t| t.parse().expect("Should parse")),
+            elevation,
+            direction,
+        };
+        let manifest = DynamicManifest {
+            latitude: Some(51.5),
+            longitude: Some(0.0),
+            frames: vec![
+                frame(Some("12:00"), None, SunDirection::Rising),
+                frame(None, Some(-0.833), SunDirection::Rising),
+                frame(None, Some(-0.833), SunDirection::Setting),
+            ],
+        };
+
+        // Sunrise 08:04, sunset 15:53 at London
+        assert_eq!(manifest.frame_at(at(3, 0), None), Some(2));
+        assert_eq!(manifest.frame_at(at(9, 0), None), Some(1));
+        assert_eq!(manifest.frame_at(at(12, 0), None), Some(0));
+        assert_eq!(manifest.frame_at(at(16, 0), None), Some(2));
+
+        // Elevation keys are ignored without location
+        let manifest = DynamicManifest {
+            latitude: None,
+            longitude: None,
+            ..manifest
+        };
+        assert_eq!(manifest.frame_at(at(3, 0), None), Some(0));
+        assert_eq!(manifest.frame_at(at(9, 0), Some((51.5, 0.0))), Some(1));
+    }
+}
@@ -0,0 +1,213 @@
+//! Provides persistence of wallpaper library.
+
+use crate::application::{
+    rotation::RotationSettings, schedule::ScheduleRule, selection::History, solar::SolarSettings,
+    span::MonitorLayout, AspectMatching, Collection, FocalPoint, FolderSource, Overlay, Wallpaper,
+};
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Configuration name passed to `confy`.
+const CONFIG_NAME: &str = "adwapach";
+
+/// Current version of library format.
+pub const LIBRARY_VERSION: u32 = 1;
+
+/// Persisted form of wallpaper library.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+    /// Format version.
+    pub version: u32,
+
+    /// Wallpapers in list order.
+    pub wallpapers: Vec<Wallpaper>,
+
+    /// Folders scanned for wallpapers.
+    #[serde(default)]
+    pub sources: Vec<FolderSource>,
+
+    /// Named collections of wallpapers.
+    #[serde(default)]
+    pub collections: Vec<Collection>,
+
+    /// Last applied wallpaper for each monitor, keyed by monitor ID.
+    pub assignments: BTreeMap<String, Uuid>,
+
+    /// Rotation settings for each monitor, keyed by monitor ID.
+    #[serde(default)]
+    pub rotations: BTreeMap<String, RotationSettings>,
+
+    /// Shown wallpapers history for each monitor, keyed by monitor ID.
+    #[serde(default)]
+    pub histories: BTreeMap<String, History>,
+
+    /// Schedule rules in priority order.
+    #[serde(default)]
+    pub schedules: Vec<ScheduleRule>,
+
+    /// Sun-driven switching settings.
+    #[serde(default)]
+    pub solar: SolarSettings,
+
+    /// Span group name for each monitor, keyed by monitor ID.
+    #[serde(default)]
+    pub span_groups: BTreeMap<String, String>,
+
+    /// Bezel and physical size for each monitor, keyed by monitor ID.
+    #[serde(default)]
+    pub layouts: BTreeMap<String, MonitorLayout>,
+
+    /// Detected focal points, keyed by SHA-256 of image file content.
+    #[serde(default)]
+    pub detected_focal_points: BTreeMap<String, FocalPoint>,
+
+    /// Text overlays rendered into wallpapers.
+    #[serde(default)]
+    pub overlays: Vec<Overlay>,
+
+    /// Aspect ratio matching in rotation.
+    #[serde(default)]
+    pub aspect_matching: AspectMatching,
+}
+
+impl Default for Library {
+    fn default() -> Library {
+        Library {
+            version: LIBRARY_VERSION,
+            wallpapers: vec![],
+            sources: vec![],
+            collections: vec![],
+            assignments: BTreeMap::new(),
+            rotations: BTreeMap::new(),
+            histories: BTreeMap::new(),
+            schedules: vec![],
+            solar: SolarSettings::default(),
+            span_groups: BTreeMap::new(),
+            layouts: BTreeMap::new(),
+            detected_focal_points: BTreeMap::new(),
+            overlays: vec![],
+            aspect_matching: AspectMatching::default(),
+        }
+    }
+}
+
+impl Library {
+    /// Loads library from configuration directory.
+    /// Returns empty one if it does not exist yet.
+    pub fn load() -> Result<Library> {
+        let library: Library = confy::load(CONFIG_NAME)?;
+        if library.version > LIBRARY_VERSION {
+            bail!(
+                "Library version {} is newer than supported {}",
+                library.version,
+                LIBRARY_VERSION
+            );
+        }
+
+        Ok(library)
+    }
+
+    /// Stores library into configuration directory.
+    pub fn store(&self) -> Result<()> {
+        confy::store(CONFIG_NAME, self.to_toml()?)?;
+        Ok(())
+    }
+
+    /// Converts into TOML value.
+    /// TOML requires plain values before tables, and `Value` reorders them so.
+    fn to_toml(&self) -> Result<toml::Value> {
+        Ok(toml::Value::try_from(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::{
+        Adjustments, AspectMode, Background, Collage, CollageLayout, Fitting, FocalPoint,
+        ImageVariant, OverlayContent, SmartQuery,
+    };
+
+    use vek::Vec2;
+
+    #[test]
+    fn toml_roundtrip() {
+        let mut library = Library::default();
+        library
+            .wallpapers
+            .push(Wallpaper::new("a.png", Fitting::Cover));
+        library.wallpapers[0].set_background(Background::Solid([1, 2, 250]));
+        library.wallpapers[0].set_focal_point(Some(FocalPoint { x: 0.25, y: 0.75 }));
+        library.wallpapers[0].set_image_size(Vec2::new(1920, 1080));
+        library.wallpapers[0].add_variant(ImageVariant {
+            filename: "a-4k.png".into(),
+            size: [3840, 2160],
+        });
+        library.wallpapers[0].set_adjustments(Adjustments {
+            rotation: 1,
+            dim: 0.25,
+            ..Default::default()
+        });
+        library.wallpapers[0].set_tags(["Space", "dark"]);
+        library.wallpapers.push(Wallpaper::new_collage(
+            "Collage",
+            Collage {
+                members: vec![library.wallpapers[0].id()],
+                layout: CollageLayout::Masonry,
+                ..Default::default()
+            },
+        ));
+        let mut collection = Collection::new("Work");
+        collection.add(library.wallpapers[1].id());
+        collection.add(library.wallpapers[0].id());
+        library.collections.push(collection);
+        library.collections.push(Collection::new_smart(
+            "Wide",
+            SmartQuery {
+                aspect_ratio: Some([2.0, 4.0]),
+                taken_from: Some("2021".into()),
+                ..Default::default()
+            },
+        ));
+        library.sources.push(FolderSource::new(
+            "C:\\Wallpapers",
+            vec!["**/*.png".into()],
+            vec![],
+        ));
+        library.wallpapers.push(Wallpaper::new_from_source(
+            "C:\\Wallpapers\\b.png",
+            Fitting::Cover,
+            library.sources[0].id(),
+        ));
+        library
+            .assignments
+            .insert("primary".into(), library.wallpapers[0].id());
+        library
+            .detected_focal_points
+            .insert("0123abcd".into(), FocalPoint { x: 0.5, y: 0.125 });
+        library.overlays.push(Overlay {
+            content: OverlayContent::Clock,
+            ..Default::default()
+        });
+        library.aspect_matching.mode = AspectMode::Require;
+        library.rotations.insert(
+            "primary".into(),
+            RotationSettings {
+                interval: 60,
+                paused: false,
+                order: Default::default(),
+                query: "#space -#dark".parse().expect("Should parse"),
+                collection: Some(library.collections[0].id()),
+            },
+        );
+
+        let text =
+            toml::to_string(&library.to_toml().expect("Should convert")).expect("Should serialize");
+        let restored: Library = toml::from_str(&text).expect("Should deserialize");
+        assert_eq!(restored, library);
+    }
+}
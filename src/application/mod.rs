@@ -1,12 +1,46 @@
+mod collage;
+mod collection;
+mod dynamic;
+mod library;
 mod model;
+mod overlay;
+mod query;
+mod rendering;
+mod rotation;
+mod schedule;
+mod selection;
+mod smart;
+mod solar;
+mod source;
+mod span;
+mod variant;
 mod view;
 mod viewmodel;
 
-use serde::{Serialize, Deserialize};
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, Error as AnyhowError};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
 use uuid::Uuid;
+use vek::Vec2;
+
+use self::variant::select_rendition;
 
-pub use self::model::Application;
-pub use self::view::ApplicationView;
+pub use self::collage::{Collage, CollageLayout};
+pub use self::collection::Collection;
+pub use self::model::{Application, ScheduleSource, WallpaperListOperation};
+pub use self::overlay::{Caption, Overlay, OverlayAnchor, OverlayContent};
+pub use self::query::{normalize_tag, TagQuery};
+pub use self::rotation::RotationStatus;
+pub use self::schedule::{ClockTime, Day, ScheduleRule};
+pub use self::selection::{AspectMatching, AspectMode, RotationOrder};
+pub use self::smart::SmartQuery;
+pub use self::solar::{Crossing, SolarSettings, Twilight};
+pub use self::source::FolderSource;
+pub use self::span::{BezelUnit, MonitorLayout, PhysicalSize};
+pub use self::variant::ImageVariant;
+pub use self::view::{ApplicationView, ApplicationWindowEvent};
 pub use self::viewmodel::ApplicationViewModel;
 
 /// Represents the positioning of wallpaper.
@@ -26,14 +60,213 @@ pub enum Fitting {
 
     /// Scaled to be covered the whole desktop.
     Cover,
+
+    /// Scaled to cover the monitors in the span group, and sliced for each.
+    Span,
+}
+
+impl FromStr for Fitting {
+    type Err = AnyhowError;
+
+    fn from_str(s: &str) -> Result<Fitting, AnyhowError> {
+        let fitting = match s.to_ascii_lowercase().as_str() {
+            "center" => Fitting::Center,
+            "tile" => Fitting::Tile,
+            "stretch" => Fitting::Stretch,
+            "contain" => Fitting::Contain,
+            "cover" => Fitting::Cover,
+            "span" => Fitting::Span,
+            _ => bail!("Unknown fitting: {s}"),
+        };
+        Ok(fitting)
+    }
+}
+
+/// Represents how the area not covered by image is filled.
+/// Serialized as `#RRGGBB` for solid color, otherwise its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Background {
+    /// Fixed RGB color.
+    Solid([u8; 3]),
+
+    /// The most frequent color of the image.
+    Dominant,
+
+    /// Average color of the image edges.
+    Edge,
+
+    /// Blurred and darkened copy of the image, stretched to fill.
+    Blurred,
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::Solid([0, 0, 0])
+    }
+}
+
+impl fmt::Display for Background {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Background::Solid([r, g, b]) => write!(f, "#{r:02X}{g:02X}{b:02X}"),
+            Background::Dominant => write!(f, "Dominant"),
+            Background::Edge => write!(f, "Edge"),
+            Background::Blurred => write!(f, "Blurred"),
+        }
+    }
+}
+
+impl FromStr for Background {
+    type Err = AnyhowError;
+
+    fn from_str(s: &str) -> Result<Background, AnyhowError> {
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 6 {
+                bail!("Color must be #RRGGBB: {s}");
+            }
+            let rgb = u32::from_str_radix(hex, 16)?;
+            return Ok(Background::Solid([
+                (rgb >> 16) as u8,
+                (rgb >> 8) as u8,
+                rgb as u8,
+            ]));
+        }
+
+        let background = match s.to_ascii_lowercase().as_str() {
+            "dominant" => Background::Dominant,
+            "edge" => Background::Edge,
+            "blurred" => Background::Blurred,
+            _ => bail!("Unknown background: {s}"),
+        };
+        Ok(background)
+    }
+}
+
+impl TryFrom<String> for Background {
+    type Error = AnyhowError;
+
+    fn try_from(s: String) -> Result<Background, AnyhowError> {
+        s.parse()
+    }
+}
+
+impl From<Background> for String {
+    fn from(background: Background) -> String {
+        background.to_string()
+    }
+}
+
+/// Point of interest kept visible when the image is cropped.
+/// Normalized into 0.0 to 1.0 from top-left of the image.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FocalPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Default for FocalPoint {
+    fn default() -> FocalPoint {
+        FocalPoint { x: 0.5, y: 0.5 }
+    }
+}
+
+/// Non-destructive adjustments of the source image.
+/// Orientation is changed before composition, and colors after.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Adjustments {
+    /// Clockwise quarter turns, 0 to 3.
+    pub rotation: u8,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+
+    /// -1.0 to 1.0.
+    pub brightness: f32,
+
+    /// -1.0 to 1.0.
+    pub contrast: f32,
+
+    /// Darkening amount, 0.0 to 1.0.
+    pub dim: f32,
+    pub grayscale: bool,
+
+    /// Blur radius in percent of the shorter side.
+    pub blur: f32,
+}
+
+impl Adjustments {
+    /// Maps a point on the source image into the oriented image.
+    pub fn orient_point(&self, point: FocalPoint) -> FocalPoint {
+        let (mut x, mut y) = (point.x, point.y);
+        for _ in 0..self.rotation % 4 {
+            (x, y) = (1.0 - y, x);
+        }
+        if self.flip_horizontal {
+            x = 1.0 - x;
+        }
+        if self.flip_vertical {
+            y = 1.0 - y;
+        }
+        FocalPoint { x, y }
+    }
+}
+
+/// Represents what the file of wallpaper is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum WallpaperKind {
+    /// Single image.
+    #[default]
+    Image,
+
+    /// Manifest of images changing along the day.
+    Dynamic,
+
+    /// Other wallpapers composed onto a monitor.
+    Collage,
 }
 
 /// Represents an item of wallpaper.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Wallpaper {
     uuid: Uuid,
     filename: String,
     fitting: Fitting,
+    #[serde(default)]
+    kind: WallpaperKind,
+    #[serde(default)]
+    background: Background,
+    /// Manually set focal point. Detected one is used if not set.
+    #[serde(default)]
+    focal_point: Option<FocalPoint>,
+    #[serde(default)]
+    adjustments: Adjustments,
+    #[serde(default)]
+    collage: Option<Collage>,
+    /// Size of the image, or the first frame for dynamic wallpapers. Recorded when read.
+    #[serde(default)]
+    image_size: Option<[u32; 2]>,
+    /// Renditions in other resolutions, for image wallpapers.
+    #[serde(default)]
+    variants: Vec<ImageVariant>,
+    /// UUID of `FolderSource` which this is found in.
+    #[serde(default)]
+    source: Option<Uuid>,
+    /// Normalized by `normalize_tag`, sorted.
+    #[serde(default)]
+    tags: Vec<String>,
+    /// From 0 to 5, 0 means unrated.
+    #[serde(default)]
+    rating: u8,
+    /// UNIX time when this is added to the library.
+    #[serde(default)]
+    added_at: Option<i64>,
+    /// Date taken from EXIF, as `YYYY-MM-DD`.
+    #[serde(default)]
+    taken_at: Option<String>,
+    /// UNIX time when this is applied last.
+    #[serde(default)]
+    shown_at: Option<i64>,
 }
 
 impl Wallpaper {
@@ -43,15 +276,68 @@ impl Wallpaper {
             uuid: Uuid::new_v4(),
             filename: filename.into(),
             fitting,
+            kind: WallpaperKind::Image,
+            background: Background::default(),
+            focal_point: None,
+            adjustments: Adjustments::default(),
+            collage: None,
+            image_size: None,
+            variants: vec![],
+            source: None,
+            tags: vec![],
+            rating: 0,
+            added_at: Some(OffsetDateTime::now_utc().unix_timestamp()),
+            taken_at: None,
+            shown_at: None,
+        }
+    }
+
+    /// Constructs new dynamic wallpaper from manifest filename.
+    pub fn new_dynamic(manifest: impl Into<String>, fitting: Fitting) -> Wallpaper {
+        Wallpaper {
+            kind: WallpaperKind::Dynamic,
+            ..Wallpaper::new(manifest, fitting)
+        }
+    }
+
+    /// Constructs new collage wallpaper with display name.
+    /// Collages are always rendered into monitor size, so `Fitting` is `Cover`.
+    pub fn new_collage(name: impl Into<String>, collage: Collage) -> Wallpaper {
+        Wallpaper {
+            kind: WallpaperKind::Collage,
+            collage: Some(collage),
+            ..Wallpaper::new(name, Fitting::Cover)
         }
     }
 
+    /// Constructs new image wallpaper found in the folder source.
+    pub fn new_from_source(
+        filename: impl Into<String>,
+        fitting: Fitting,
+        source: Uuid,
+    ) -> Wallpaper {
+        Wallpaper {
+            source: Some(source),
+            ..Wallpaper::new(filename, fitting)
+        }
+    }
+
+    /// Gets kind.
+    pub fn kind(&self) -> WallpaperKind {
+        self.kind
+    }
+
     /// Gets assigned UUID.
     pub fn id(&self) -> Uuid {
         self.uuid
     }
 
-    /// Gets filename.
+    /// Gets UUID of the folder source, `None` if added individually.
+    pub fn source(&self) -> Option<Uuid> {
+        self.source
+    }
+
+    /// Gets filename, or display name for collages.
     pub fn filename(&self) -> &str {
         &self.filename
     }
@@ -65,4 +351,162 @@ impl Wallpaper {
     pub fn set_fitting(&mut self, fitting: Fitting) {
         self.fitting = fitting;
     }
+
+    /// Gets background for letterboxing.
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
+    /// Sets new background for letterboxing.
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    /// Gets manually set focal point for cropping.
+    pub fn focal_point(&self) -> Option<FocalPoint> {
+        self.focal_point
+    }
+
+    /// Sets new focal point for cropping. `None` uses detected one.
+    pub fn set_focal_point(&mut self, focal_point: Option<FocalPoint>) {
+        self.focal_point = focal_point;
+    }
+
+    /// Gets image adjustments.
+    pub fn adjustments(&self) -> Adjustments {
+        self.adjustments
+    }
+
+    /// Sets new image adjustments.
+    pub fn set_adjustments(&mut self, adjustments: Adjustments) {
+        self.adjustments = adjustments;
+    }
+
+    /// Gets recorded size of the source image.
+    pub fn image_size(&self) -> Option<Vec2<u32>> {
+        self.image_size.map(Vec2::from)
+    }
+
+    /// Records size of the source image.
+    pub fn set_image_size(&mut self, size: Vec2<u32>) {
+        self.image_size = Some(size.into_array());
+    }
+
+    /// Gets aspect ratio of the oriented source image, if its size is recorded.
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        let size = self.orient_size(self.image_size()?).as_::<f32>();
+        Some(size.x / size.y.max(1.0))
+    }
+
+    /// Gets tags.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Sets tags, normalizing them and dropping empty ones and duplicates.
+    pub fn set_tags<S: AsRef<str>>(&mut self, tags: impl IntoIterator<Item = S>) {
+        let mut tags: Vec<_> = tags
+            .into_iter()
+            .filter_map(|t| normalize_tag(t.as_ref()))
+            .collect();
+        tags.sort();
+        tags.dedup();
+        self.tags = tags;
+    }
+
+    /// Checks whether this matches the query.
+    pub fn matches(&self, query: &TagQuery) -> bool {
+        query.matches(&self.filename, &self.tags)
+    }
+
+    /// Gets rating.
+    pub fn rating(&self) -> u8 {
+        self.rating
+    }
+
+    /// Sets rating, clamped to 5.
+    pub fn set_rating(&mut self, rating: u8) {
+        self.rating = rating.min(5);
+    }
+
+    /// Gets UNIX time when this is added, if recorded.
+    pub fn added_at(&self) -> Option<i64> {
+        self.added_at
+    }
+
+    /// Gets date taken, as `YYYY-MM-DD`.
+    pub fn taken_at(&self) -> Option<&str> {
+        self.taken_at.as_deref()
+    }
+
+    /// Records date taken read from EXIF.
+    pub fn set_taken_at(&mut self, taken_at: Option<String>) {
+        self.taken_at = taken_at;
+    }
+
+    /// Gets UNIX time when this is applied last, `None` if never.
+    pub fn shown_at(&self) -> Option<i64> {
+        self.shown_at
+    }
+
+    /// Records that this is applied.
+    pub fn set_shown_at(&mut self, shown_at: i64) {
+        self.shown_at = Some(shown_at);
+    }
+
+    /// Gets additional renditions.
+    pub fn variants(&self) -> &[ImageVariant] {
+        &self.variants
+    }
+
+    /// Adds a rendition unless it has the same file. Ignored unless this is an image.
+    pub fn add_variant(&mut self, variant: ImageVariant) {
+        let exists = self.filename == variant.filename
+            || self.variants.iter().any(|v| v.filename == variant.filename);
+        if self.kind == WallpaperKind::Image && !exists {
+            self.variants.push(variant);
+        }
+    }
+
+    /// Removes a rendition.
+    pub fn remove_variant(&mut self, index: usize) {
+        if index < self.variants.len() {
+            self.variants.remove(index);
+        }
+    }
+
+    /// Selects the image file for the target size in pixels, from the primary one and variants.
+    /// Returns its index, 0 for the primary, with the filename.
+    pub fn rendition_for(&self, target: Vec2<u32>) -> (usize, &str) {
+        let sizes: Vec<_> = [self.image_size()]
+            .into_iter()
+            .chain(self.variants.iter().map(|v| Some(v.size())))
+            .map(|s| s.map(|s| self.orient_size(s)))
+            .collect();
+        match select_rendition(&sizes, target) {
+            0 => (0, &self.filename),
+            i => (i, &self.variants[i - 1].filename),
+        }
+    }
+
+    /// Swaps width and height if rotated by a quarter turn.
+    fn orient_size(&self, size: Vec2<u32>) -> Vec2<u32> {
+        match self.adjustments.rotation % 2 {
+            0 => size,
+            _ => size.yx(),
+        }
+    }
+
+    /// Gets collage settings. `None` unless this is a collage.
+    pub fn collage(&self) -> Option<&Collage> {
+        self.collage.as_ref()
+    }
+
+    /// Sets new collage settings and display name. Ignored unless this is a collage.
+    pub fn set_collage(&mut self, name: impl Into<String>, collage: Collage) {
+        if self.kind == WallpaperKind::Collage {
+            self.filename = name.into();
+            self.collage = Some(collage);
+        }
+    }
 }
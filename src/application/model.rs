@@ -1,31 +1,130 @@
 use crate::{
-    application::{Fitting, Wallpaper},
+    application::{
+        dynamic::DynamicManifest,
+        library::Library,
+        normalize_tag,
+        overlay::read_taken_at,
+        rendering::{
+            Assignment, RenderJob, RenderSource, RenderTarget, RenderedJob, SourceImage, SpanSlice,
+        },
+        rotation::{RotationSettings, RotationStatus, RotationTimer},
+        schedule::{evaluate, ScheduleRule},
+        selection::{AspectMatching, History, RotationOrder},
+        solar::{SolarEvents, SolarPhase, SolarSettings},
+        source::{ScannedSource, SourceScan},
+        span::{arrange, bounds, MonitorLayout},
+        Adjustments, Background, Caption, Collage, Collection, Fitting, FocalPoint, FolderSource,
+        ImageVariant, Overlay, SmartQuery, TagQuery, Wallpaper, WallpaperKind,
+    },
+    backend::{Monitor, WallpaperBackend},
     mvvm::{EventManager, Observable, Subscription},
-    windows::{Monitor, WallpaperInterface},
 };
 
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    mem::take,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Receiver},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use dunce::canonicalize;
+use image::RgbaImage;
+use log::{error, info};
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::Mutex;
+use rand::{rngs::StdRng, SeedableRng};
+use time::{Date, OffsetDateTime, UtcOffset};
+use uuid::Uuid;
+use vek::Vec2;
+
+/// Delay to gather file changes in folder sources.
+const SOURCE_WATCH_DELAY: Duration = Duration::from_secs(2);
 
 /// Application model object.
 pub struct Application {
     subscribers: EventManager<ApplicationEvent>,
+    backend: Arc<dyn WallpaperBackend>,
+    rendered_directory: PathBuf,
     monitors: Vec<Monitor>,
     wallpapers: Vec<Wallpaper>,
+    assignments: BTreeMap<String, Uuid>,
+    rotations: BTreeMap<String, RotationSettings>,
+    rotation_timers: HashMap<String, RotationTimer>,
+    histories: BTreeMap<String, History>,
+    schedules: Vec<ScheduleRule>,
+    solar: SolarSettings,
+    scheduled: Vec<Option<ScheduleSource>>,
+    local_offset: UtcOffset,
+    manifests: HashMap<Uuid, Option<DynamicManifest>>,
+    dynamic_frames: HashMap<String, usize>,
+    span_groups: BTreeMap<String, String>,
+    layouts: BTreeMap<String, MonitorLayout>,
+    detected_focal_points: BTreeMap<String, FocalPoint>,
+    overlays: Vec<Overlay>,
+    sources: Vec<FolderSource>,
+    collections: Vec<Collection>,
+    source_watcher: Option<(RecommendedWatcher, Receiver<DebouncedEvent>)>,
+    pending_scans: HashSet<Uuid>,
+    aspect_matching: AspectMatching,
+    base_images: HashMap<String, (Arc<RgbaImage>, Caption)>,
+    overlay_minute: Option<i64>,
+    rotation_summary: Option<RotationSummary>,
+    rng: StdRng,
+    persistent: bool,
 }
 
 impl Application {
     /// Constructs new model.
-    pub fn new() -> Arc<Mutex<Application>> {
+    /// Rendered wallpapers are written into `rendered_directory`.
+    /// `local_offset` is used for local time until rotation ticks determine it.
+    pub fn new(
+        backend: Arc<dyn WallpaperBackend>,
+        rendered_directory: PathBuf,
+        local_offset: UtcOffset,
+    ) -> Arc<Mutex<Application>> {
         Arc::new(Mutex::new(Application {
             subscribers: EventManager::new(),
+            backend,
+            rendered_directory,
             monitors: vec![],
             wallpapers: vec![],
+            assignments: BTreeMap::new(),
+            rotations: BTreeMap::new(),
+            rotation_timers: HashMap::new(),
+            histories: BTreeMap::new(),
+            schedules: vec![],
+            solar: SolarSettings::default(),
+            scheduled: vec![],
+            local_offset,
+            manifests: HashMap::new(),
+            dynamic_frames: HashMap::new(),
+            span_groups: BTreeMap::new(),
+            layouts: BTreeMap::new(),
+            detected_focal_points: BTreeMap::new(),
+            overlays: vec![],
+            sources: vec![],
+            collections: vec![],
+            source_watcher: None,
+            pending_scans: HashSet::new(),
+            aspect_matching: AspectMatching::default(),
+            base_images: HashMap::new(),
+            overlay_minute: None,
+            rotation_summary: None,
+            rng: StdRng::from_entropy(),
+            persistent: false,
         }))
     }
 
+    /// Gets wallpaper backend.
+    pub fn backend(&self) -> Arc<dyn WallpaperBackend> {
+        self.backend.clone()
+    }
+
     /// Refers monitors.
     pub fn monitors(&self) -> &[Monitor] {
         &self.monitors
@@ -36,23 +135,210 @@ impl Application {
         &self.wallpapers
     }
 
+    /// Refers schedule rules.
+    pub fn schedules(&self) -> &[ScheduleRule] {
+        &self.schedules
+    }
+
+    /// Refers folder sources.
+    pub fn sources(&self) -> &[FolderSource] {
+        &self.sources
+    }
+
+    /// Refers named collections.
+    pub fn collections(&self) -> &[Collection] {
+        &self.collections
+    }
+
+    /// Refers text overlays.
+    pub fn overlays(&self) -> &[Overlay] {
+        &self.overlays
+    }
+
+    /// Gets aspect ratio matching in rotation.
+    pub fn aspect_matching(&self) -> AspectMatching {
+        self.aspect_matching
+    }
+
+    /// Refers sun-driven switching settings.
+    pub fn solar(&self) -> &SolarSettings {
+        &self.solar
+    }
+
+    /// Calculates today's sun events in local time.
+    pub fn solar_events(&self) -> SolarEvents {
+        self.solar
+            .events(self.local_now().date(), self.local_offset)
+    }
+
+    /// Gets where wallpapers for the monitor currently come from.
+    pub fn scheduled_source(&self, monitor_index: usize) -> Option<ScheduleSource> {
+        *self.scheduled.get(monitor_index)?
+    }
+
+    /// Gets the span group of the monitor. `None` means the group of ungrouped monitors.
+    pub fn span_group(&self, monitor_index: usize) -> Option<&str> {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        self.span_groups.get(&key).map(|g| g.as_str())
+    }
+
+    /// Gets indices of monitors which a spanned wallpaper covers together with the monitor.
+    pub fn span_members(&self, monitor_index: usize) -> Vec<usize> {
+        let group = self.span_group(monitor_index);
+        (0..self.monitors.len())
+            .filter(|&i| self.span_group(i) == group)
+            .collect()
+    }
+
+    /// Gets bezel and physical size of the monitor.
+    pub fn monitor_layout(&self, monitor_index: usize) -> MonitorLayout {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        self.layouts.get(&key).copied().unwrap_or_default()
+    }
+
+    /// Finds the index of wallpaper which has specified filename.
+    pub fn find_wallpaper(&self, filename: &str) -> Option<usize> {
+        self.wallpapers
+            .iter()
+            .position(|w| w.filename() == filename)
+    }
+
+    /// Gets the index of wallpaper last applied for the monitor.
+    pub fn assigned_wallpaper(&self, monitor_index: usize) -> Option<usize> {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let assigned_id = self.assignments.get(&key)?;
+        self.wallpapers.iter().position(|w| w.id() == *assigned_id)
+    }
+
+    /// Restores persisted library.
+    /// After this, every change of wallpapers will be written back.
+    pub fn restore_library(&mut self) -> Result<()> {
+        let library = Library::load()?;
+        self.wallpapers = library.wallpapers;
+        self.assignments = library.assignments;
+        self.rotations = library.rotations;
+        self.rotation_timers.clear();
+        self.histories = library.histories;
+        let ids: HashSet<_> = self.wallpapers.iter().map(|w| w.id()).collect();
+        for history in self.histories.values_mut() {
+            history.retain(|id| ids.contains(&id));
+        }
+        self.schedules = library.schedules;
+        self.solar = library.solar;
+        self.span_groups = library.span_groups;
+        self.layouts = library.layouts;
+        self.detected_focal_points = library.detected_focal_points;
+        self.overlays = library.overlays;
+        self.aspect_matching = library.aspect_matching;
+        self.sources = library.sources;
+        self.collections = library.collections;
+        // Folders may be changed while not running
+        self.pending_scans = self.sources.iter().map(|s| s.id()).collect();
+        self.watch_sources();
+        self.scheduled.clear();
+        self.manifests.clear();
+        self.dynamic_frames.clear();
+        self.persistent = true;
+        self.subscribers.notify(ApplicationEvent::MonitorsUpdated);
+        self.subscribers.notify(ApplicationEvent::WallpapersUpdated);
+        self.subscribers.notify(ApplicationEvent::SchedulesUpdated);
+        self.subscribers.notify(ApplicationEvent::OverlaysUpdated);
+        Ok(())
+    }
+
     /// Sets monitors information.
     pub fn set_monitors(&mut self, monitors: Vec<Monitor>) {
         self.monitors = monitors;
+        self.scheduled.clear();
+        self.subscribers.notify(ApplicationEvent::MonitorsUpdated);
+    }
+
+    /// Sets the span group of the monitor. `None` makes it ungrouped.
+    pub fn set_span_group(&mut self, monitor_index: usize, group: Option<String>) {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        match group {
+            Some(g) => self.span_groups.insert(key, g),
+            None => self.span_groups.remove(&key),
+        };
+
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::MonitorsUpdated);
+    }
+
+    /// Sets bezel and physical size of the monitor.
+    pub fn set_monitor_layout(&mut self, monitor_index: usize, layout: MonitorLayout) {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        if layout == MonitorLayout::default() {
+            self.layouts.remove(&key);
+        } else {
+            self.layouts.insert(key, layout);
+        }
+
+        self.save_library();
         self.subscribers.notify(ApplicationEvent::MonitorsUpdated);
     }
 
     /// Pushes new wallpaper.
     pub fn add_wallpaper(&mut self, wallpaper: Wallpaper) {
         self.wallpapers.push(wallpaper);
-        self.subscribers.notify(ApplicationEvent::WallpapersUpdated);
+        self.notify_wallpapers_updated();
+    }
+
+    /// Adds an image file or a dynamic wallpaper manifest if it is not in the list yet,
+    /// and returns its index.
+    /// Images are validated by reading their header.
+    pub fn add_wallpaper_file(&mut self, path: &Path, fitting: Fitting) -> Result<usize> {
+        let wallpaper = read_wallpaper_file(path, fitting)?;
+        let index = self.insert_wallpaper(wallpaper);
+        self.notify_wallpapers_updated();
+        Ok(index)
+    }
+
+    /// Adds files like `add_wallpaper_file` at once, and returns the UUID for each.
+    /// Files are read without holding the lock.
+    pub fn add_wallpaper_files(
+        this: &Mutex<Application>,
+        paths: &[PathBuf],
+        fitting: Fitting,
+    ) -> Vec<Result<Uuid>> {
+        let read: Vec<_> = paths
+            .iter()
+            .map(|p| read_wallpaper_file(p, fitting))
+            .collect();
+
+        let mut locked = this.lock();
+        let results = read
+            .into_iter()
+            .map(|r| {
+                r.map(|w| {
+                    let index = locked.insert_wallpaper(w);
+                    locked.wallpapers[index].id()
+                })
+            })
+            .collect();
+        locked.notify_wallpapers_updated();
+        results
+    }
+
+    /// Adds a wallpaper to the list without notification, unless the file is already there.
+    fn insert_wallpaper(&mut self, wallpaper: Wallpaper) -> usize {
+        if let Some(index) = self.find_wallpaper(wallpaper.filename()) {
+            return index;
+        }
+        self.wallpapers.push(wallpaper);
+        self.wallpapers.len() - 1
     }
 
     /// Performs an operation for specified indexed item.
+    /// Ignored if the index is stale, as folder sources may change the list meanwhile.
     pub fn update_wallpaper(&mut self, index: usize, op: WallpaperListOperation) {
+        if index >= self.wallpapers.len() {
+            return;
+        }
         match op {
             WallpaperListOperation::Remove => {
-                self.wallpapers.remove(index);
+                let id = self.wallpapers[index].id();
+                self.remove_wallpapers(&HashSet::from([id]));
             }
             WallpaperListOperation::MoveUp if index > 0 => {
                 self.wallpapers.swap(index, index - 1);
@@ -63,24 +349,1256 @@ impl Application {
             WallpaperListOperation::SetFitting(f) => {
                 self.wallpapers[index].set_fitting(f);
             }
+            WallpaperListOperation::SetBackground(b) => {
+                self.wallpapers[index].set_background(b);
+            }
+            WallpaperListOperation::SetFocalPoint(p) => {
+                self.wallpapers[index].set_focal_point(p);
+            }
+            WallpaperListOperation::SetAdjustments(a) => {
+                self.wallpapers[index].set_adjustments(a);
+            }
+            WallpaperListOperation::RemoveVariant(v) => {
+                self.wallpapers[index].remove_variant(v);
+            }
+            WallpaperListOperation::SetTags(t) => {
+                self.wallpapers[index].set_tags(t);
+            }
+            WallpaperListOperation::SetRating(r) => {
+                self.wallpapers[index].set_rating(r);
+            }
+            WallpaperListOperation::AddToCollection(c) => {
+                let id = self.wallpapers[index].id();
+                if let Some(collection) = self.collections.iter_mut().find(|x| x.id() == c) {
+                    collection.add(id);
+                }
+            }
+            WallpaperListOperation::RemoveFromCollection(c) => {
+                let id = self.wallpapers[index].id();
+                if let Some(collection) = self.collections.iter_mut().find(|x| x.id() == c) {
+                    collection.remove(id);
+                }
+            }
+            WallpaperListOperation::MoveUpInCollection(c) => {
+                let id = self.wallpapers[index].id();
+                if let Some(collection) = self.collections.iter_mut().find(|x| x.id() == c) {
+                    collection.move_member(id, false);
+                }
+            }
+            WallpaperListOperation::MoveDownInCollection(c) => {
+                let id = self.wallpapers[index].id();
+                if let Some(collection) = self.collections.iter_mut().find(|x| x.id() == c) {
+                    collection.move_member(id, true);
+                }
+            }
             _ => (),
         }
-        self.subscribers.notify(ApplicationEvent::WallpapersUpdated);
+        self.notify_wallpapers_updated();
+    }
+
+    /// Performs an operation for collections.
+    pub fn update_collection(&mut self, op: CollectionOperation) {
+        match op {
+            CollectionOperation::Add(name) => {
+                self.collections.push(Collection::new(name));
+            }
+            CollectionOperation::AddSmart(name, query) => {
+                self.collections.push(Collection::new_smart(name, query));
+            }
+            // Indices may be stale when operations are dispatched twice
+            CollectionOperation::SetQuery(index, query) if index < self.collections.len() => {
+                self.collections[index].set_query(query);
+            }
+            CollectionOperation::Rename(index, name) if index < self.collections.len() => {
+                self.collections[index].name = name;
+            }
+            CollectionOperation::Remove(index) if index < self.collections.len() => {
+                let removed = self.collections.remove(index);
+                for settings in self.rotations.values_mut() {
+                    if settings.collection == Some(removed.id()) {
+                        settings.collection = None;
+                    }
+                }
+                self.subscribers.notify(ApplicationEvent::RotationUpdated);
+            }
+            _ => (),
+        }
+        self.notify_wallpapers_updated();
+    }
+
+    /// Adds and removes tags of multiple wallpapers at once. Stale indices are ignored.
+    pub fn edit_tags(&mut self, indices: &[usize], added: &[String], removed: &[String]) {
+        let removed: Vec<_> = removed.iter().filter_map(|t| normalize_tag(t)).collect();
+        for &index in indices {
+            let wallpaper = match self.wallpapers.get_mut(index) {
+                Some(w) => w,
+                None => continue,
+            };
+            let tags: Vec<_> = wallpaper
+                .tags()
+                .iter()
+                .filter(|t| !removed.contains(t))
+                .chain(added)
+                .cloned()
+                .collect();
+            wallpaper.set_tags(tags);
+        }
+        self.notify_wallpapers_updated();
+    }
+
+    /// Removes wallpapers, and drops them from collages.
+    fn remove_wallpapers(&mut self, ids: &HashSet<Uuid>) {
+        self.wallpapers.retain(|w| !ids.contains(&w.id()));
+        for collection in &mut self.collections {
+            for id in ids {
+                collection.remove(*id);
+            }
+        }
+        for wallpaper in &mut self.wallpapers {
+            if let Some(mut collage) = wallpaper.collage().cloned() {
+                collage.members.retain(|id| !ids.contains(id));
+                wallpaper.set_collage(wallpaper.filename().to_string(), collage);
+            }
+        }
+        for history in self.histories.values_mut() {
+            history.retain(|id| !ids.contains(&id));
+        }
+    }
+
+    /// Adds a folder whose images are added to the library, and starts watching it.
+    /// The folder is scanned in `tick_sources`.
+    pub fn add_source(
+        &mut self,
+        path: &Path,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    ) -> Result<()> {
+        let path = canonicalize(path)?;
+        if !path.is_dir() {
+            bail!("Not a folder: {}", path.display());
+        }
+        if self.sources.iter().any(|s| s.path() == path) {
+            bail!("Already added: {}", path.display());
+        }
+        let source = FolderSource::new(path.to_string_lossy(), include, exclude);
+        source.matcher()?;
+
+        self.pending_scans.insert(source.id());
+        self.sources.push(source);
+        self.watch_sources();
+        self.notify_wallpapers_updated();
+        Ok(())
+    }
+
+    /// Sets glob patterns of the folder source. They are applied in `tick_sources`.
+    pub fn set_source_globs(
+        &mut self,
+        index: usize,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    ) -> Result<()> {
+        let mut source = self
+            .sources
+            .get(index)
+            .context("Folder source no longer exists")?
+            .clone();
+        source.include = include;
+        source.exclude = exclude;
+        source.matcher()?;
+
+        self.pending_scans.insert(source.id());
+        self.sources[index] = source;
+        self.notify_wallpapers_updated();
+        Ok(())
+    }
+
+    /// Removes the folder source with wallpapers found in it. Ignored if the index is stale.
+    pub fn remove_source(&mut self, index: usize) {
+        if index >= self.sources.len() {
+            return;
+        }
+        let source = self.sources.remove(index);
+        let ids = self
+            .wallpapers
+            .iter()
+            .filter(|w| w.source() == Some(source.id()))
+            .map(|w| w.id())
+            .collect();
+        self.remove_wallpapers(&ids);
+        self.watch_sources();
+        self.notify_wallpapers_updated();
+    }
+
+    /// Requests scanning all folder sources again in `tick_sources`.
+    pub fn rescan_sources(&mut self) {
+        self.pending_scans
+            .extend(self.sources.iter().map(|s| s.id()));
+    }
+
+    /// Applies the scan result of the folder source, adding new images and removing vanished ones.
+    /// Returns whether wallpapers are changed.
+    fn finish_scan(&mut self, scanned: ScannedSource) -> bool {
+        let source_id = scanned.source_id;
+        if !self.sources.iter().any(|s| s.id() == source_id) {
+            return false;
+        }
+
+        let vanished: HashSet<_> = self
+            .wallpapers
+            .iter()
+            .filter(|w| w.source() == Some(source_id) && !scanned.found.contains(w.filename()))
+            .map(|w| w.id())
+            .collect();
+        let known: HashSet<_> = self
+            .wallpapers
+            .iter()
+            .map(|w| w.filename().to_string())
+            .collect();
+        let added: Vec<_> = scanned
+            .added
+            .into_iter()
+            .filter(|w| !known.contains(w.filename()))
+            .collect();
+
+        let changed = !vanished.is_empty() || !added.is_empty();
+        self.remove_wallpapers(&vanished);
+        self.wallpapers.extend(added);
+        changed
+    }
+
+    /// Starts watching all folder sources, replacing the previous watcher.
+    fn watch_sources(&mut self) {
+        self.source_watcher = None;
+        if self.sources.is_empty() {
+            return;
+        }
+
+        let (sender, receiver) = channel();
+        let mut watcher = match watcher(sender, SOURCE_WATCH_DELAY) {
+            Ok(w) => w,
+            Err(e) => {
+                error!("Failed to start watching folders: {e}");
+                return;
+            }
+        };
+        for source in &self.sources {
+            if let Err(e) = watcher.watch(source.path(), RecursiveMode::Recursive) {
+                error!("Failed to watch {}: {e}", source.path().display());
+            }
+        }
+        self.source_watcher = Some((watcher, receiver));
+    }
+
+    /// Synchronizes folder sources which are added, changed or requested to rescan,
+    /// and ones which files are created, removed or renamed in.
+    /// Nothing is removed if a folder cannot be scanned, as it may be offline.
+    /// Folders are scanned without holding the lock, as it may take long.
+    pub fn tick_sources(this: &Mutex<Application>) {
+        let scans = this.lock().prepare_scans();
+        if scans.is_empty() {
+            return;
+        }
+
+        let mut scanned = vec![];
+        for scan in scans {
+            let path = scan.source.path().to_path_buf();
+            info!("Synchronizing folder {}", path.display());
+            match scan.run() {
+                Ok(s) => scanned.push(s),
+                Err(e) => error!("Failed to scan {}: {e}", path.display()),
+            }
+        }
+
+        let mut locked = this.lock();
+        let mut changed = false;
+        for scanned in scanned {
+            changed |= locked.finish_scan(scanned);
+        }
+        if changed {
+            locked.notify_wallpapers_updated();
+        }
+    }
+
+    /// Takes out folder sources to scan, including ones notified by the watcher.
+    fn prepare_scans(&mut self) -> Vec<SourceScan> {
+        if let Some((_, receiver)) = &self.source_watcher {
+            let mut paths = vec![];
+            let mut rescan = false;
+            for event in receiver.try_iter() {
+                match event {
+                    DebouncedEvent::Create(p) | DebouncedEvent::Remove(p) => paths.push(p),
+                    DebouncedEvent::Rename(from, to) => paths.extend([from, to]),
+                    DebouncedEvent::Rescan => rescan = true,
+                    DebouncedEvent::Error(e, p) => error!("Failed to watch folders: {e} ({p:?})"),
+                    _ => (),
+                }
+            }
+            let changed = self
+                .sources
+                .iter()
+                .filter(|s| rescan || paths.iter().any(|p| p.starts_with(s.path())))
+                .map(|s| s.id());
+            self.pending_scans.extend(changed);
+        }
+        if self.pending_scans.is_empty() {
+            return vec![];
+        }
+
+        let known: HashSet<_> = self
+            .wallpapers
+            .iter()
+            .map(|w| w.filename().to_string())
+            .collect();
+        let pending = take(&mut self.pending_scans);
+        self.sources
+            .iter()
+            .filter(|s| pending.contains(&s.id()))
+            .map(|s| SourceScan {
+                source: s.clone(),
+                known: known.clone(),
+            })
+            .collect()
+    }
+
+    /// Sets new settings and display name of the collage wallpaper.
+    /// Ignored if the index is stale.
+    pub fn set_collage(&mut self, index: usize, name: String, collage: Collage) {
+        let wallpaper = match self.wallpapers.get_mut(index) {
+            Some(w) => w,
+            None => return,
+        };
+        wallpaper.set_collage(name, collage);
+        self.notify_wallpapers_updated();
     }
 
     /// Applies selected wallpaper for selected monitor.
+    /// The image is rendered into monitor size according to its `Fitting` beforehand,
+    /// without holding the lock.
+    /// Spanned wallpapers are applied for all monitors in the span group.
     pub fn apply_wallpaper_for_monitor(
-        &self,
+        this: &Mutex<Application>,
         monitor_index: usize,
         wallpaper_index: usize,
     ) -> Result<()> {
-        let wpi = WallpaperInterface::new()?;
-        wpi.set_wallpaper(
-            self.monitors[monitor_index].id(),
-            &self.wallpapers[wallpaper_index].filename,
-        )?;
+        let job = {
+            let mut locked = this.lock();
+            let local = locked.local_now();
+            locked.prepare_apply(monitor_index, wallpaper_index, local)?
+        };
+        Application::render_job(this, job)
+    }
+
+    /// Applies the wallpaper like `apply_wallpaper_for_monitor`, identified by its UUID.
+    /// Fails if it has been removed meanwhile.
+    pub fn apply_wallpaper_by_id(
+        this: &Mutex<Application>,
+        monitor_index: usize,
+        wallpaper_id: Uuid,
+    ) -> Result<()> {
+        let job = {
+            let mut locked = this.lock();
+            let wallpaper_index = locked
+                .wallpapers
+                .iter()
+                .position(|w| w.id() == wallpaper_id)
+                .context("Wallpaper no longer exists")?;
+            let local = locked.local_now();
+            locked.prepare_apply(monitor_index, wallpaper_index, local)?
+        };
+        Application::render_job(this, job)
+    }
+
+    /// Renders the job without holding the lock, then sets rendered images.
+    fn render_job(this: &Mutex<Application>, job: RenderJob) -> Result<()> {
+        let rendered = job.render(|hash| this.lock().detected_focal_points.get(hash).copied())?;
+        this.lock().finish_rendering(rendered)
+    }
+
+    /// Takes out what is needed to render the wallpaper for the monitor.
+    /// `local` selects frames of dynamic wallpapers.
+    /// Fails if the index is stale, as folder sources may change the list meanwhile.
+    fn prepare_apply(
+        &mut self,
+        monitor_index: usize,
+        wallpaper_index: usize,
+        local: OffsetDateTime,
+    ) -> Result<RenderJob> {
+        if wallpaper_index >= self.wallpapers.len() {
+            bail!("Wallpaper no longer exists");
+        }
+        let (source, targets, frame) =
+            if self.wallpapers[wallpaper_index].kind() == WallpaperKind::Collage {
+                let source = self.prepare_collage(monitor_index, wallpaper_index, local)?;
+                (source, vec![self.render_target(monitor_index, false)], None)
+            } else {
+                let target = self.target_size(monitor_index, wallpaper_index);
+                let (image, frame) = self.source_image(wallpaper_index, local, target)?;
+                let wallpaper = &self.wallpapers[wallpaper_index];
+                let targets = match wallpaper.fitting() {
+                    Fitting::Span => self
+                        .span_members(monitor_index)
+                        .into_iter()
+                        .map(|i| self.render_target(i, true))
+                        .collect(),
+                    _ => vec![self.render_target(monitor_index, false)],
+                };
+                let source = RenderSource::Image {
+                    image,
+                    fitting: wallpaper.fitting(),
+                    background: wallpaper.background(),
+                };
+                (source, targets, frame)
+            };
+
+        Ok(RenderJob {
+            source,
+            targets,
+            overlays: self.overlays.clone(),
+            local,
+            rendered_directory: self.rendered_directory.clone(),
+            assignment: Some(Assignment {
+                wallpaper_id: self.wallpapers[wallpaper_index].id(),
+                frame,
+            }),
+        })
+    }
+
+    /// Resolves member images of the collage wallpaper, composed into the monitor size.
+    /// Missing or failing members are skipped, and nested collages are not composed.
+    fn prepare_collage(
+        &mut self,
+        monitor_index: usize,
+        wallpaper_index: usize,
+        local: OffsetDateTime,
+    ) -> Result<RenderSource> {
+        let wallpaper = &self.wallpapers[wallpaper_index];
+        let collage = match wallpaper.collage() {
+            Some(c) => c.clone(),
+            None => bail!("Not a collage: {}", wallpaper.filename()),
+        };
+        let name = wallpaper.filename().to_string();
+        let adjustments = wallpaper.adjustments();
+
+        let canvas_size = self.monitors[monitor_index].size().as_();
+        let mut cells = vec![];
+        for member_id in &collage.members {
+            let member_index = match self
+                .wallpapers
+                .iter()
+                .position(|w| w.id() == *member_id && w.kind() != WallpaperKind::Collage)
+            {
+                Some(i) => i,
+                None => continue,
+            };
+            match self.source_image(member_index, local, canvas_size) {
+                Ok((image, _)) => cells.push(image),
+                Err(e) => error!(
+                    "Failed to load collage member {}: {e}",
+                    self.wallpapers[member_index].filename()
+                ),
+            }
+        }
+
+        Ok(RenderSource::Collage {
+            name,
+            cells,
+            canvas_size,
+            collage,
+            adjustments,
+        })
+    }
+
+    /// Resolves the image file of the wallpaper like `wallpaper_source`, with how it is shown.
+    fn source_image(
+        &mut self,
+        wallpaper_index: usize,
+        local: OffsetDateTime,
+        target: Vec2<u32>,
+    ) -> Result<(SourceImage, Option<usize>)> {
+        let (path, frame) = self.wallpaper_source(wallpaper_index, local, target)?;
+        let wallpaper = &self.wallpapers[wallpaper_index];
+        let image = SourceImage {
+            path,
+            adjustments: wallpaper.adjustments(),
+            focal_point: wallpaper.focal_point(),
+        };
+        Ok((image, frame))
+    }
+
+    /// Describes the monitor to render for.
+    /// Spanned one is sliced from the physical arrangement of the span group if `span`.
+    fn render_target(&self, monitor_index: usize, span: bool) -> RenderTarget {
+        let monitor = &self.monitors[monitor_index];
+        let span = span.then(|| {
+            let members = self.span_members(monitor_index);
+            let arranged: Vec<_> = members
+                .iter()
+                .map(|&i| (&self.monitors[i], self.monitor_layout(i)))
+                .collect();
+            let placements = arrange(&arranged);
+            let desktop = bounds(placements.iter().map(|p| p.screen));
+            let screen = placements[members
+                .iter()
+                .position(|&i| i == monitor_index)
+                .expect("Should be a member")]
+            .screen;
+            SpanSlice {
+                desktop_size: Vec2::<f64>::from(desktop.size()).as_(),
+                position: (screen.min - desktop.min).as_(),
+                size: Vec2::<f64>::from(screen.size()).as_(),
+            }
+        });
+
+        RenderTarget {
+            monitor_index,
+            monitor_key: monitor_key(monitor),
+            size: monitor.size().as_(),
+            span,
+        }
+    }
+
+    /// Sets rendered images, and records the assignment.
+    /// Monitors and wallpapers gone while rendering are skipped.
+    pub fn finish_rendering(&mut self, rendered: RenderedJob) -> Result<()> {
+        for (hash, point) in rendered.detected_focal_points {
+            self.detected_focal_points.insert(hash, point);
+        }
+
+        let mut keys = vec![];
+        for image in rendered.images {
+            let key = image.target.monitor_key;
+            let monitor = match self.monitors.iter().find(|m| monitor_key(m) == key) {
+                Some(m) => m,
+                None => continue,
+            };
+            self.backend.set_wallpaper(monitor.id(), &image.path)?;
+            self.base_images
+                .insert(key.clone(), (image.base, image.caption));
+            keys.push(key);
+        }
+
+        let assignment = match rendered.assignment {
+            Some(a) => a,
+            None => return Ok(()),
+        };
+        let wallpaper = match self
+            .wallpapers
+            .iter_mut()
+            .find(|w| w.id() == assignment.wallpaper_id)
+        {
+            Some(w) => w,
+            None => return Ok(()),
+        };
+        let now_utc = OffsetDateTime::now_utc().unix_timestamp();
+        wallpaper.set_shown_at(now_utc);
+        for key in keys {
+            self.assignments
+                .insert(key.clone(), assignment.wallpaper_id);
+            self.histories
+                .entry(key.clone())
+                .or_default()
+                .record(assignment.wallpaper_id, now_utc);
+            match assignment.frame {
+                Some(f) => self.dynamic_frames.insert(key.clone(), f),
+                None => self.dynamic_frames.remove(&key),
+            };
+            if let Some(settings) = self.rotations.get(&key) {
+                let timer = RotationTimer::start(settings, Instant::now());
+                self.rotation_timers.insert(key, timer);
+            }
+        }
+        self.notify_wallpapers_updated();
         Ok(())
     }
+
+    /// Resolves the image file to show.
+    /// For images, the rendition for `target` size in pixels is selected.
+    /// For dynamic wallpapers, the frame for `local` time is selected and its index is returned.
+    fn wallpaper_source(
+        &mut self,
+        wallpaper_index: usize,
+        local: OffsetDateTime,
+        target: Vec2<u32>,
+    ) -> Result<(PathBuf, Option<usize>)> {
+        let filename = PathBuf::from(self.wallpapers[wallpaper_index].filename());
+        let manifest = match self.dynamic_manifest(wallpaper_index) {
+            Some(m) => m,
+            None if self.wallpapers[wallpaper_index].kind() == WallpaperKind::Image => {
+                let (_, rendition) = self.wallpapers[wallpaper_index].rendition_for(target);
+                return Ok((PathBuf::from(rendition), None));
+            }
+            None => bail!("Invalid manifest: {}", filename.display()),
+        };
+
+        // Location for sun elevation is shared with sun-driven switching
+        let location = Some((self.solar.latitude, self.solar.longitude));
+        let frame = manifest.frame_at(local, location).unwrap_or(0);
+        Ok((manifest.frame_path(&filename, frame), Some(frame)))
+    }
+
+    /// Gets the size in pixels which the wallpaper is rendered into for the monitor.
+    /// Spanned ones cover the bounds of the span group.
+    fn target_size(&self, monitor_index: usize, wallpaper_index: usize) -> Vec2<u32> {
+        let members = match self.wallpapers[wallpaper_index].fitting() {
+            Fitting::Span => self.span_members(monitor_index),
+            _ => vec![monitor_index],
+        };
+        let rects = members.iter().map(|&i| {
+            let monitor = &self.monitors[i];
+            (monitor.position(), monitor.position() + monitor.size())
+        });
+        let (min, max) = rects
+            .reduce(|(a_min, a_max), (b_min, b_max)| {
+                (
+                    Vec2::partial_min(a_min, b_min),
+                    Vec2::partial_max(a_max, b_max),
+                )
+            })
+            .expect("Should have the monitor");
+        (max - min).map(|x| x.max(0) as u32)
+    }
+
+    /// Gets the index of rendition shown on the monitor, 0 for the primary one.
+    /// `None` unless an image wallpaper is assigned.
+    pub fn applied_rendition(&self, monitor_index: usize) -> Option<usize> {
+        let wallpaper_index = self.assigned_wallpaper(monitor_index)?;
+        let wallpaper = &self.wallpapers[wallpaper_index];
+        if wallpaper.kind() != WallpaperKind::Image {
+            return None;
+        }
+        let target = self.target_size(monitor_index, wallpaper_index);
+        Some(wallpaper.rendition_for(target).0)
+    }
+
+    /// Adds an image file as a rendition of the image wallpaper.
+    /// The image is validated by reading its header.
+    pub fn add_variant_file(&mut self, index: usize, path: &Path) -> Result<()> {
+        let wallpaper = self
+            .wallpapers
+            .get(index)
+            .context("Wallpaper no longer exists")?;
+        if wallpaper.kind() != WallpaperKind::Image {
+            bail!("Variants are only for image wallpapers");
+        }
+        let path = canonicalize(path)?;
+        let (width, height) = image::image_dimensions(&path)?;
+        self.wallpapers[index].add_variant(ImageVariant {
+            filename: path.to_string_lossy().to_string(),
+            size: [width, height],
+        });
+        self.notify_wallpapers_updated();
+        Ok(())
+    }
+
+    /// Gets the manifest of dynamic wallpaper, loading it at first time.
+    fn dynamic_manifest(&mut self, wallpaper_index: usize) -> Option<DynamicManifest> {
+        let wallpaper = &self.wallpapers[wallpaper_index];
+        if wallpaper.kind() != WallpaperKind::Dynamic {
+            return None;
+        }
+
+        self.manifests
+            .entry(wallpaper.id())
+            .or_insert_with(
+                || match DynamicManifest::load(Path::new(wallpaper.filename())) {
+                    Ok(m) => Some(m),
+                    Err(e) => {
+                        error!("Failed to load manifest {}: {e}", wallpaper.filename());
+                        None
+                    }
+                },
+            )
+            .clone()
+    }
+
+    /// Stores focal points detected outside, keyed by the file content hash.
+    pub fn cache_focal_points(&mut self, detected: Vec<(String, FocalPoint)>) {
+        let mut changed = false;
+        for (hash, point) in detected {
+            changed |= self.detected_focal_points.insert(hash, point).is_none();
+        }
+        if changed {
+            self.save_library();
+        }
+    }
+
+    /// Records sizes of source images read outside.
+    pub fn cache_image_sizes(&mut self, sizes: Vec<(Uuid, Vec2<u32>)>) {
+        let mut changed = false;
+        for (id, size) in sizes {
+            let wallpaper = match self.wallpapers.iter_mut().find(|w| w.id() == id) {
+                Some(w) => w,
+                None => continue,
+            };
+            if wallpaper.image_size() != Some(size) {
+                wallpaper.set_image_size(size);
+                changed = true;
+            }
+        }
+        if changed {
+            self.notify_wallpapers_updated();
+        }
+    }
+
+    /// Takes out what is needed to redraw overlays onto the rendered image of the monitor.
+    /// The assigned wallpaper is rendered again if the image is not kept, like after restart.
+    /// `local` is the time shown by timed overlays. `None` if nothing is assigned.
+    fn prepare_redraw(
+        &mut self,
+        monitor_index: usize,
+        local: OffsetDateTime,
+    ) -> Result<Option<RenderJob>> {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let source = match self.base_images.get(&key) {
+            Some((base, caption)) => RenderSource::Rendered(base.clone(), caption.clone()),
+            None => {
+                let wallpaper_index = match self.assigned_wallpaper(monitor_index) {
+                    Some(w) => w,
+                    None => return Ok(None),
+                };
+                let job = self.prepare_apply(monitor_index, wallpaper_index, local)?;
+                return Ok(Some(RenderJob {
+                    assignment: None,
+                    ..job
+                }));
+            }
+        };
+
+        Ok(Some(RenderJob {
+            source,
+            targets: vec![self.render_target(monitor_index, false)],
+            overlays: self.overlays.clone(),
+            local,
+            rendered_directory: self.rendered_directory.clone(),
+            assignment: None,
+        }))
+    }
+
+    /// Sets text overlays, and redraws them on current wallpapers without holding the lock.
+    pub fn set_overlays(this: &Mutex<Application>, overlays: Vec<Overlay>) {
+        let jobs = {
+            let mut locked = this.lock();
+            locked.overlays = overlays;
+            locked.save_library();
+            locked.subscribers.notify(ApplicationEvent::OverlaysUpdated);
+
+            let local = locked.local_now();
+            let mut jobs = vec![];
+            for monitor_index in 0..locked.monitors.len() {
+                if is_prepared(&jobs, monitor_index) {
+                    continue;
+                }
+                match locked.prepare_redraw(monitor_index, local) {
+                    Ok(Some(job)) => jobs.push(job),
+                    Ok(None) => (),
+                    Err(e) => error!("Failed to redraw overlays of monitor #{monitor_index}: {e}"),
+                }
+            }
+            jobs
+        };
+
+        for job in jobs {
+            let monitor_index = job.monitor_index();
+            if let Err(e) = Application::render_job(this, job) {
+                error!("Failed to redraw overlays of monitor #{monitor_index}: {e}");
+            }
+        }
+    }
+
+    /// Gets current local time.
+    fn local_now(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc().to_offset(self.local_offset)
+    }
+
+    /// Applies next wallpaper according to rotation order of the monitor,
+    /// rendering it without holding the lock.
+    /// Returns the index of applied wallpaper, or `None` if there is no wallpaper.
+    pub fn apply_next_wallpaper(
+        this: &Mutex<Application>,
+        monitor_index: usize,
+    ) -> Result<Option<usize>> {
+        let (next_index, job) = {
+            let mut locked = this.lock();
+            let next_index = match locked.select_next_wallpaper(monitor_index) {
+                Some(i) => i,
+                None => return Ok(None),
+            };
+            let local = locked.local_now();
+            let job = locked.prepare_apply(monitor_index, next_index, local)?;
+            (next_index, job)
+        };
+        Application::render_job(this, job)?;
+        Ok(Some(next_index))
+    }
+
+    /// Selects next wallpaper according to rotation order of the monitor.
+    fn select_next_wallpaper(&mut self, monitor_index: usize) -> Option<usize> {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let order = self
+            .rotations
+            .get(&key)
+            .map(|s| s.order)
+            .unwrap_or_default();
+        let candidates = self.rotation_candidates(monitor_index);
+        let candidate_ids: Vec<_> = candidates
+            .iter()
+            .map(|&i| self.wallpapers[i].id())
+            .collect();
+        let current = self.assignments.get(&key).copied();
+        let history = self.histories.entry(key).or_default();
+
+        order
+            .strategy()
+            .select(&candidate_ids, current, history, &mut self.rng)
+            .map(|i| candidates[i])
+    }
+
+    /// Takes out what is needed to render next wallpaper of the monitor, if exists.
+    fn prepare_next(
+        &mut self,
+        monitor_index: usize,
+        local: OffsetDateTime,
+    ) -> Result<Option<RenderJob>> {
+        match self.select_next_wallpaper(monitor_index) {
+            Some(i) => Ok(Some(self.prepare_apply(monitor_index, i, local)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets indices of wallpapers to rotate for the monitor.
+    /// Scheduled wallpapers are used if exist, otherwise bound collection or whole library.
+    /// Candidates are narrowed by rotation query and aspect ratio matching.
+    fn rotation_candidates(&self, monitor_index: usize) -> Vec<usize> {
+        let candidates = match self.scheduled_source(monitor_index) {
+            Some(ScheduleSource::Rule(rule_index)) => {
+                self.schedules[rule_index].select_wallpapers(&self.wallpapers)
+            }
+            Some(ScheduleSource::Solar(phase)) => {
+                self.solar.select_wallpapers(phase, &self.wallpapers)
+            }
+            None => match self.rotation_collection(monitor_index) {
+                Some(collection) => collection.select_wallpapers(
+                    &self.wallpapers,
+                    OffsetDateTime::now_utc().unix_timestamp(),
+                ),
+                None => (0..self.wallpapers.len()).collect(),
+            },
+        };
+        let candidates = match self.rotation_query(monitor_index) {
+            Some(query) => candidates
+                .into_iter()
+                .filter(|&i| self.wallpapers[i].matches(query))
+                .collect(),
+            None => candidates,
+        };
+        let size = self.monitors[monitor_index].size().as_::<f32>();
+        self.aspect_matching.filter(
+            candidates,
+            |i| self.wallpapers[i].aspect_ratio(),
+            size.x / size.y.max(1.0),
+        )
+    }
+
+    /// Sets aspect ratio matching in rotation.
+    pub fn set_aspect_matching(&mut self, aspect_matching: AspectMatching) {
+        self.aspect_matching = aspect_matching;
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::RotationUpdated);
+    }
+
+    /// Sets sun-driven switching settings.
+    pub fn set_solar(&mut self, solar: SolarSettings) {
+        self.solar = solar;
+
+        // Re-evaluated in next tick
+        self.scheduled.clear();
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::SchedulesUpdated);
+    }
+
+    /// Performs an operation for schedule rules.
+    pub fn update_schedule(&mut self, op: ScheduleOperation) {
+        match op {
+            ScheduleOperation::Add(rule) => {
+                self.schedules.push(rule);
+            }
+            // Indices may be stale when operations are dispatched twice
+            ScheduleOperation::Replace(index, rule) if index < self.schedules.len() => {
+                self.schedules[index] = rule;
+            }
+            ScheduleOperation::Remove(index) if index < self.schedules.len() => {
+                self.schedules.remove(index);
+            }
+            ScheduleOperation::MoveUp(index) if 0 < index && index < self.schedules.len() => {
+                self.schedules.swap(index, index - 1);
+            }
+            ScheduleOperation::MoveDown(index) if index + 1 < self.schedules.len() => {
+                self.schedules.swap(index, index + 1);
+            }
+            _ => (),
+        }
+
+        // Re-evaluated in next tick
+        self.scheduled.clear();
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::SchedulesUpdated);
+    }
+
+    /// Gets rotation status of the monitor, or `None` if rotation is disabled.
+    pub fn rotation_status(&self, monitor_index: usize, now: Instant) -> Option<RotationStatus> {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let settings = self.rotations.get(&key)?;
+        let remaining = match self.rotation_timers.get(&key) {
+            Some(t) => t.remaining(now),
+            None => settings.interval(),
+        };
+
+        Some(RotationStatus {
+            interval: settings.interval(),
+            paused: settings.paused,
+            order: settings.order,
+            remaining,
+        })
+    }
+
+    /// Sets rotation interval of the monitor. `None` disables rotation.
+    pub fn set_rotation_interval(&mut self, monitor_index: usize, interval: Option<Duration>) {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        match interval {
+            Some(interval) => {
+                let (paused, order, query, collection) = self
+                    .rotations
+                    .get(&key)
+                    .map(|s| (s.paused, s.order, s.query.clone(), s.collection))
+                    .unwrap_or_default();
+                let settings = RotationSettings {
+                    interval: interval.as_secs(),
+                    paused,
+                    order,
+                    query,
+                    collection,
+                };
+                let timer = RotationTimer::start(&settings, Instant::now());
+                self.rotations.insert(key.clone(), settings);
+                self.rotation_timers.insert(key, timer);
+            }
+            None => {
+                self.rotations.remove(&key);
+                self.rotation_timers.remove(&key);
+            }
+        }
+
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::RotationUpdated);
+    }
+
+    /// Pauses or resumes rotation of the monitor.
+    pub fn set_rotation_paused(&mut self, monitor_index: usize, paused: bool) {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let settings = match self.rotations.get_mut(&key) {
+            Some(s) => s,
+            None => return,
+        };
+        settings.paused = paused;
+
+        let now = Instant::now();
+        let timer = self
+            .rotation_timers
+            .entry(key)
+            .or_insert_with(|| RotationTimer::start(settings, now));
+        if paused {
+            timer.pause(now);
+        } else {
+            timer.resume(now);
+        }
+
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::RotationUpdated);
+    }
+
+    /// Sets rotation order of the monitor.
+    pub fn set_rotation_order(&mut self, monitor_index: usize, order: RotationOrder) {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let settings = match self.rotations.get_mut(&key) {
+            Some(s) => s,
+            None => return,
+        };
+        settings.order = order;
+
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::RotationUpdated);
+    }
+
+    /// Gets the query restricting wallpapers of the monitor in rotation.
+    pub fn rotation_query(&self, monitor_index: usize) -> Option<&TagQuery> {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        self.rotations.get(&key).map(|s| &s.query)
+    }
+
+    /// Restricts wallpapers of the monitor in rotation to ones matching the query.
+    pub fn set_rotation_query(&mut self, monitor_index: usize, query: TagQuery) {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let settings = match self.rotations.get_mut(&key) {
+            Some(s) => s,
+            None => return,
+        };
+        settings.query = query;
+
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::RotationUpdated);
+    }
+
+    /// Gets the collection which the monitor rotates through, or `None` for whole library.
+    pub fn rotation_collection(&self, monitor_index: usize) -> Option<&Collection> {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let collection_id = self.rotations.get(&key)?.collection?;
+        self.collections.iter().find(|c| c.id() == collection_id)
+    }
+
+    /// Binds the monitor to the collection in rotation. `None` means whole library.
+    pub fn set_rotation_collection(&mut self, monitor_index: usize, collection: Option<Uuid>) {
+        let key = monitor_key(&self.monitors[monitor_index]);
+        let settings = match self.rotations.get_mut(&key) {
+            Some(s) => s,
+            None => return,
+        };
+        settings.collection = collection;
+
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::RotationUpdated);
+    }
+
+    /// Changes wallpapers of monitors whose schedule has changed or rotation timer has expired,
+    /// and redraws overlays showing time.
+    /// Images are rendered without holding the lock. `local` is used to evaluate schedule rules.
+    pub fn tick_rotation(this: &Mutex<Application>, now: Instant, local: OffsetDateTime) {
+        let jobs = this.lock().prepare_rotation(now, local);
+        for job in jobs {
+            let monitor_index = job.monitor_index();
+            if let Err(e) = Application::render_job(this, job) {
+                error!("Failed to change wallpaper of monitor #{monitor_index}: {e}");
+            }
+        }
+        this.lock().notify_rotation_changes(now, local);
+    }
+
+    /// Takes out renderings needed by schedule, rotation timers, dynamic wallpapers and
+    /// overlays, at most one for each monitor.
+    fn prepare_rotation(&mut self, now: Instant, local: OffsetDateTime) -> Vec<RenderJob> {
+        if self.rotations.is_empty()
+            && self.schedules.is_empty()
+            && !self.solar.enabled
+            && !self.has_dynamic_assignment()
+            && !self.has_timed_overlay()
+        {
+            return vec![];
+        }
+
+        let mut jobs = vec![];
+        self.prepare_schedule(local, &mut jobs);
+
+        for monitor_index in 0..self.monitors.len() {
+            let key = monitor_key(&self.monitors[monitor_index]);
+            let settings = match self.rotations.get(&key) {
+                Some(s) => s.clone(),
+                None => continue,
+            };
+            let timer = self
+                .rotation_timers
+                .entry(key.clone())
+                .or_insert_with(|| RotationTimer::start(&settings, now));
+            if !timer.is_due(now) {
+                continue;
+            }
+
+            if !is_prepared(&jobs, monitor_index) {
+                match self.prepare_next(monitor_index, local) {
+                    Ok(Some(job)) => jobs.push(job),
+                    Ok(None) => (),
+                    Err(e) => error!("Failed to rotate wallpaper of monitor #{monitor_index}: {e}"),
+                }
+            }
+            // Restart anyway, or failing one will be retried every tick
+            self.rotation_timers
+                .insert(key, RotationTimer::start(&settings, now));
+        }
+
+        self.prepare_dynamic(local, &mut jobs);
+        self.prepare_overlays(local, &mut jobs);
+        jobs
+    }
+
+    /// Evaluates schedule rules and the sun, and changes wallpapers out of newly scheduled set.
+    /// Schedule rules take precedence over the sun.
+    fn prepare_schedule(&mut self, local: OffsetDateTime, jobs: &mut Vec<RenderJob>) {
+        self.local_offset = local.offset();
+
+        let solar_phase = if self.solar.enabled {
+            Some(self.solar.phase(local))
+        } else {
+            None
+        };
+        let scheduled: Vec<_> = evaluate(&self.schedules, local, &self.monitors, &self.wallpapers)
+            .into_iter()
+            .map(|set| match set {
+                Some(s) => Some((ScheduleSource::Rule(s.rule_index), s.wallpapers)),
+                None => {
+                    let phase = solar_phase?;
+                    let selected = self.solar.select_wallpapers(phase, &self.wallpapers);
+                    if selected.is_empty() {
+                        return None;
+                    }
+                    Some((ScheduleSource::Solar(phase), selected))
+                }
+            })
+            .collect();
+        let sources = scheduled
+            .iter()
+            .map(|s| s.as_ref().map(|(source, _)| *source))
+            .collect();
+        let previous = std::mem::replace(&mut self.scheduled, sources);
+
+        for (monitor_index, set) in scheduled.into_iter().enumerate() {
+            let wallpapers = match set {
+                Some((source, w)) if previous.get(monitor_index) != Some(&Some(source)) => w,
+                _ => continue,
+            };
+            let assigned = self.assigned_wallpaper(monitor_index);
+            if assigned.map(|i| wallpapers.contains(&i)).unwrap_or(false) {
+                continue;
+            }
+
+            info!("Schedule changed for monitor #{monitor_index}");
+            match self.prepare_next(monitor_index, local) {
+                Ok(Some(job)) => jobs.push(job),
+                Ok(None) => (),
+                Err(e) => {
+                    error!("Failed to apply scheduled wallpaper of monitor #{monitor_index}: {e}")
+                }
+            }
+        }
+    }
+
+    /// Changes frames of dynamic wallpapers applied on monitors.
+    fn prepare_dynamic(&mut self, local: OffsetDateTime, jobs: &mut Vec<RenderJob>) {
+        for monitor_index in 0..self.monitors.len() {
+            if is_prepared(jobs, monitor_index) {
+                continue;
+            }
+            let wallpaper_index = match self.assigned_wallpaper(monitor_index) {
+                Some(w) if self.wallpapers[w].kind() == WallpaperKind::Dynamic => w,
+                _ => continue,
+            };
+            let target = self.target_size(monitor_index, wallpaper_index);
+            let frame = match self.wallpaper_source(wallpaper_index, local, target) {
+                Ok((_, Some(f))) => f,
+                _ => continue,
+            };
+            let key = monitor_key(&self.monitors[monitor_index]);
+            if self.dynamic_frames.get(&key) == Some(&frame) {
+                continue;
+            }
+
+            info!("Changing dynamic wallpaper frame: Monitor #{monitor_index}: Frame #{frame}");
+            match self.prepare_apply(monitor_index, wallpaper_index, local) {
+                Ok(job) => jobs.push(RenderJob {
+                    assignment: None,
+                    ..job
+                }),
+                Err(e) => error!("Failed to change frame of monitor #{monitor_index}: {e}"),
+            }
+            // Recorded anyway, or failing one will be retried every tick
+            self.dynamic_frames.insert(key, frame);
+        }
+    }
+
+    /// Redraws overlays showing time when the minute changes.
+    fn prepare_overlays(&mut self, local: OffsetDateTime, jobs: &mut Vec<RenderJob>) {
+        if !self.has_timed_overlay() {
+            return;
+        }
+        let minute = local.unix_timestamp() / 60;
+        if self.overlay_minute == Some(minute) {
+            return;
+        }
+        self.overlay_minute = Some(minute);
+
+        for monitor_index in 0..self.monitors.len() {
+            if is_prepared(jobs, monitor_index) {
+                continue;
+            }
+            match self.prepare_redraw(monitor_index, local) {
+                Ok(Some(job)) => jobs.push(job),
+                Ok(None) => (),
+                Err(e) => error!("Failed to redraw overlays of monitor #{monitor_index}: {e}"),
+            }
+        }
+    }
+
+    /// Notifies when remaining time or source of rotation, or the date of sun events changes.
+    fn notify_rotation_changes(&mut self, now: Instant, local: OffsetDateTime) {
+        let summary = RotationSummary {
+            date: local.date(),
+            monitors: (0..self.monitors.len())
+                .map(|i| {
+                    let remaining = self.rotation_status(i, now).map(|s| s.remaining.as_secs());
+                    (remaining, self.scheduled_source(i))
+                })
+                .collect(),
+        };
+        if self.rotation_summary.as_ref() == Some(&summary) {
+            return;
+        }
+
+        self.rotation_summary = Some(summary);
+        self.subscribers.notify(ApplicationEvent::RotationUpdated);
+    }
+
+    /// Checks whether any overlay shows time.
+    fn has_timed_overlay(&self) -> bool {
+        self.overlays.iter().any(|o| o.content.is_timed())
+    }
+
+    /// Checks whether any monitor shows dynamic wallpaper.
+    fn has_dynamic_assignment(&self) -> bool {
+        self.wallpapers
+            .iter()
+            .filter(|w| w.kind() == WallpaperKind::Dynamic)
+            .any(|w| self.assignments.values().any(|id| *id == w.id()))
+    }
+
+    /// Writes back the library if needed.
+    fn save_library(&self) {
+        if !self.persistent {
+            return;
+        }
+
+        let library = Library {
+            wallpapers: self.wallpapers.clone(),
+            sources: self.sources.clone(),
+            collections: self.collections.clone(),
+            assignments: self.assignments.clone(),
+            rotations: self.rotations.clone(),
+            histories: self.histories.clone(),
+            schedules: self.schedules.clone(),
+            solar: self.solar.clone(),
+            span_groups: self.span_groups.clone(),
+            layouts: self.layouts.clone(),
+            detected_focal_points: self.detected_focal_points.clone(),
+            overlays: self.overlays.clone(),
+            aspect_matching: self.aspect_matching,
+            ..Default::default()
+        };
+        if let Err(e) = library.store() {
+            error!("Failed to save library: {e}");
+        }
+    }
+
+    /// Writes back the library if needed, then notifies.
+    fn notify_wallpapers_updated(&mut self) {
+        self.save_library();
+        self.subscribers.notify(ApplicationEvent::WallpapersUpdated);
+    }
 }
 
 impl Observable for Application {
@@ -98,15 +1616,111 @@ impl Observable for Application {
     }
 }
 
+/// Source of wallpapers currently scheduled for a monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleSource {
+    /// Schedule rule of the index.
+    Rule(usize),
+
+    /// Sun-driven phase.
+    Solar(SolarPhase),
+}
+
+/// Represents an operation for schedule rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleOperation {
+    /// Appends new rule.
+    Add(ScheduleRule),
+
+    /// Replaces the rule.
+    Replace(usize, ScheduleRule),
+
+    /// Removes the rule.
+    Remove(usize),
+
+    /// Raises priority of the rule.
+    MoveUp(usize),
+
+    /// Lowers priority of the rule.
+    MoveDown(usize),
+}
+
+/// Represents an operation for collections.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollectionOperation {
+    /// Appends new empty collection with the name.
+    Add(String),
+
+    /// Appends new smart playlist with the name.
+    AddSmart(String, SmartQuery),
+
+    /// Replaces the query of the smart playlist.
+    SetQuery(usize, SmartQuery),
+
+    /// Renames the collection.
+    Rename(usize, String),
+
+    /// Removes the collection, unbinding monitors from it.
+    Remove(usize),
+}
+
+/// What rotation status shows, to notify only when it changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RotationSummary {
+    date: Date,
+
+    /// Remaining seconds and scheduled source for each monitor.
+    monitors: Vec<(Option<u64>, Option<ScheduleSource>)>,
+}
+
+/// Checks whether any job renders for the monitor.
+fn is_prepared(jobs: &[RenderJob], monitor_index: usize) -> bool {
+    jobs.iter()
+        .flat_map(|j| &j.targets)
+        .any(|t| t.monitor_index == monitor_index)
+}
+
+/// Makes persistent key for monitor.
+fn monitor_key(monitor: &Monitor) -> String {
+    monitor.id().as_str().to_string()
+}
+
+/// Reads an image file or a dynamic wallpaper manifest as a new wallpaper.
+/// Images are validated by reading their header.
+fn read_wallpaper_file(path: &Path, fitting: Fitting) -> Result<Wallpaper> {
+    let path = canonicalize(path)?;
+    let filename = path.to_string_lossy().to_string();
+    let (mut wallpaper, image_path) = if DynamicManifest::is_manifest(&path) {
+        let manifest = DynamicManifest::load(&path)?;
+        let first_frame = manifest.frame_path(&path, 0);
+        (Wallpaper::new_dynamic(filename, fitting), first_frame)
+    } else {
+        (Wallpaper::new(filename, fitting), path.clone())
+    };
+    match image::image_dimensions(&image_path) {
+        Ok((width, height)) => wallpaper.set_image_size(Vec2::new(width, height)),
+        Err(_) if wallpaper.kind() == WallpaperKind::Dynamic => (),
+        Err(e) => return Err(e.into()),
+    }
+    if wallpaper.kind() == WallpaperKind::Image {
+        wallpaper.set_taken_at(read_taken_at(&path));
+    }
+    Ok(wallpaper)
+}
+
 /// Represents an event in `Application`.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum ApplicationEvent {
     MonitorsUpdated,
     WallpapersUpdated,
+    RotationUpdated,
+    SchedulesUpdated,
+    OverlaysUpdated,
 }
 
 /// Represents an action for wallpapers list.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WallpaperListOperation {
     /// Removes this item.
     Remove,
@@ -119,4 +1733,775 @@ pub enum WallpaperListOperation {
 
     /// Sets new `Fitting` for this.
     SetFitting(Fitting),
+
+    /// Sets new `Background` for this.
+    SetBackground(Background),
+
+    /// Sets new `FocalPoint` for this. `None` uses detected one.
+    SetFocalPoint(Option<FocalPoint>),
+
+    /// Sets new `Adjustments` for this.
+    SetAdjustments(Adjustments),
+
+    /// Removes indexed variant of this.
+    RemoveVariant(usize),
+
+    /// Sets new tags for this.
+    SetTags(Vec<String>),
+
+    /// Sets new rating for this.
+    SetRating(u8),
+
+    /// Appends this to the collection.
+    AddToCollection(Uuid),
+
+    /// Removes this from the collection, keeping it in the library.
+    RemoveFromCollection(Uuid),
+
+    /// Moves this up in the collection.
+    MoveUpInCollection(Uuid),
+
+    /// Moves this down in the collection.
+    MoveDownInCollection(Uuid),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::{AspectMode, OverlayContent},
+        backend::{MemoryBackend, MonitorId},
+    };
+
+    use std::fs::{create_dir_all, remove_file, rename, write};
+
+    use image::RgbaImage;
+    use tempfile::TempDir;
+    use vek::Vec2;
+
+    fn setup() -> (Arc<MemoryBackend>, Arc<Mutex<Application>>, TempDir) {
+        let backend = Arc::new(MemoryBackend::new(vec![
+            Monitor::new(
+                MonitorId::new("primary"),
+                "Primary",
+                Vec2::new(0, 0),
+                Vec2::new(64, 36),
+            ),
+            Monitor::new(
+                MonitorId::new("secondary"),
+                "Secondary",
+                Vec2::new(64, 0),
+                Vec2::new(30, 40),
+            ),
+        ]));
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        let application = Application::new(
+            backend.clone(),
+            directory.path().join("rendered"),
+            UtcOffset::UTC,
+        );
+        {
+            let mut locked = application.lock();
+            let monitors = backend.monitors().expect("Should succeed");
+            locked.set_monitors(monitors);
+        }
+
+        (backend, application, directory)
+    }
+
+    fn add_image(application: &Mutex<Application>, directory: &TempDir, name: &str) {
+        let path = directory.path().join(name);
+        RgbaImage::new(16, 9).save(&path).expect("Failed to save");
+
+        let mut locked = application.lock();
+        locked.add_wallpaper(Wallpaper::new(path.to_string_lossy(), Fitting::Cover));
+    }
+
+    #[test]
+    fn apply_renders_for_monitor() {
+        let (backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+        add_image(&application, &directory, "b.png");
+
+        Application::apply_wallpaper_for_monitor(&application, 1, 0).expect("Should apply");
+        Application::apply_wallpaper_for_monitor(&application, 0, 1).expect("Should apply");
+        let locked = application.lock();
+
+        let applied = backend.applied();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied[0].0, MonitorId::new("secondary"));
+        assert_eq!(applied[1].0, MonitorId::new("primary"));
+
+        let rendered = image::open(&applied[0].1).expect("Should be rendered");
+        assert_eq!((rendered.width(), rendered.height()), (30, 40));
+
+        let wallpaper_id = locked.wallpapers()[1].id();
+        assert_eq!(locked.assignments.get("primary"), Some(&wallpaper_id));
+
+        // Images with the same content share detected focal point
+        assert_eq!(locked.detected_focal_points.len(), 1);
+    }
+
+    #[test]
+    fn collage_composes_members() {
+        let (backend, application, directory) = setup();
+        let mut locked = application.lock();
+        let mut members = vec![];
+        for (name, color) in [
+            ("red.png", [255, 0, 0, 255]),
+            ("blue.png", [0, 0, 255, 255]),
+        ] {
+            let path = directory.path().join(name);
+            RgbaImage::from_pixel(9, 16, image::Rgba(color))
+                .save(&path)
+                .expect("Failed to save");
+            locked.add_wallpaper(Wallpaper::new(path.to_string_lossy(), Fitting::Cover));
+            members.push(locked.wallpapers().last().expect("Should be added").id());
+        }
+        members.push(Uuid::new_v4());
+        let collage = Collage {
+            members,
+            gutter: 0.0,
+            ..Default::default()
+        };
+        locked.add_wallpaper(Wallpaper::new_collage("Collage", collage));
+
+        drop(locked);
+        Application::apply_wallpaper_for_monitor(&application, 0, 2).expect("Should apply");
+        let mut locked = application.lock();
+        let rendered = image::open(&backend.applied()[0].1)
+            .expect("Should be rendered")
+            .to_rgba8();
+        assert_eq!(rendered.dimensions(), (64, 36));
+        assert_eq!(rendered.get_pixel(8, 18).0, [255, 0, 0, 255]);
+        assert_eq!(rendered.get_pixel(56, 18).0, [0, 0, 255, 255]);
+
+        // Removed wallpapers are dropped from collages
+        locked.update_wallpaper(1, WallpaperListOperation::Remove);
+        let collage = locked.wallpapers()[1].collage().expect("Should be collage");
+        assert_eq!(collage.members.len(), 2);
+    }
+
+    #[test]
+    fn rotation_matches_aspect() {
+        let (_backend, application, directory) = setup();
+        let mut locked = application.lock();
+        for (name, width, height) in [("landscape.png", 16, 9), ("portrait.png", 9, 16)] {
+            let path = directory.path().join(name);
+            RgbaImage::new(width, height)
+                .save(&path)
+                .expect("Failed to save");
+            locked
+                .add_wallpaper_file(&path, Fitting::Cover)
+                .expect("Should add");
+        }
+        locked.set_aspect_matching(AspectMatching {
+            mode: AspectMode::Require,
+            tolerance: 40.0,
+        });
+
+        drop(locked);
+
+        // Secondary monitor is portrait, and primary is landscape
+        for _ in 0..3 {
+            let applied = Application::apply_next_wallpaper(&application, 1).expect("Should apply");
+            assert_eq!(applied, Some(1));
+        }
+        assert_eq!(
+            Application::apply_next_wallpaper(&application, 0).expect("Should apply"),
+            Some(0)
+        );
+        let mut locked = application.lock();
+
+        // Rotated one fits the other way
+        locked.update_wallpaper(
+            1,
+            WallpaperListOperation::SetAdjustments(Adjustments {
+                rotation: 1,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(locked.rotation_candidates(1), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn rotation_matches_tags() {
+        let (_backend, application, directory) = setup();
+        for name in ["nebula.png", "galaxy.png", "forest.png"] {
+            add_image(&application, &directory, name);
+        }
+
+        let mut locked = application.lock();
+        locked.edit_tags(&[0, 1], &["Space".into()], &[]);
+        locked.edit_tags(&[1, 2], &["#dark".into()], &[]);
+        assert_eq!(locked.wallpapers()[1].tags(), ["dark", "space"]);
+
+        locked.set_rotation_interval(1, Some(Duration::from_secs(60)));
+        let query: TagQuery = "#space AND NOT #dark".parse().expect("Should parse");
+        locked.set_rotation_query(1, query);
+        assert_eq!(locked.rotation_candidates(1), vec![0]);
+        assert_eq!(locked.rotation_candidates(0), vec![0, 1, 2]);
+
+        locked.edit_tags(&[1], &[], &["dark".into()]);
+        assert_eq!(locked.rotation_candidates(1), vec![0, 1]);
+    }
+
+    #[test]
+    fn rotation_follows_collection() {
+        let (_backend, application, directory) = setup();
+        for name in ["a.png", "b.png", "c.png"] {
+            add_image(&application, &directory, name);
+        }
+
+        let mut locked = application.lock();
+        locked.update_collection(CollectionOperation::Add("Work".into()));
+        let collection_id = locked.collections()[0].id();
+        for index in [2, 0, 1] {
+            locked.update_wallpaper(
+                index,
+                WallpaperListOperation::AddToCollection(collection_id),
+            );
+        }
+        locked.update_wallpaper(1, WallpaperListOperation::MoveUpInCollection(collection_id));
+
+        locked.set_rotation_interval(0, Some(Duration::from_secs(60)));
+        locked.set_rotation_collection(0, Some(collection_id));
+        assert_eq!(locked.rotation_candidates(0), vec![2, 1, 0]);
+        assert_eq!(locked.rotation_candidates(1), vec![0, 1, 2]);
+
+        // Removed wallpapers leave collections, and removed collections unbind monitors
+        locked.update_wallpaper(2, WallpaperListOperation::Remove);
+        assert_eq!(locked.rotation_candidates(0), vec![1, 0]);
+        locked.update_collection(CollectionOperation::Remove(0));
+        assert!(locked.rotation_collection(0).is_none());
+        assert_eq!(locked.rotation_candidates(0), vec![0, 1]);
+    }
+
+    #[test]
+    fn smart_playlist_follows_library() {
+        let (_backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+        add_image(&application, &directory, "b.png");
+
+        let mut locked = application.lock();
+        let query = SmartQuery {
+            min_rating: 3,
+            never_shown: true,
+            ..Default::default()
+        };
+        locked.update_collection(CollectionOperation::AddSmart("Best".into(), query));
+        let collection_id = locked.collections()[0].id();
+        locked.set_rotation_interval(0, Some(Duration::from_secs(60)));
+        locked.set_rotation_collection(0, Some(collection_id));
+        assert_eq!(locked.rotation_candidates(0), Vec::<usize>::new());
+
+        locked.update_wallpaper(0, WallpaperListOperation::SetRating(3));
+        locked.update_wallpaper(1, WallpaperListOperation::SetRating(5));
+        assert_eq!(locked.rotation_candidates(0), vec![0, 1]);
+
+        drop(locked);
+        Application::apply_wallpaper_for_monitor(&application, 1, 1).expect("Should apply");
+        assert_eq!(application.lock().rotation_candidates(0), vec![0]);
+    }
+
+    #[test]
+    fn variant_matches_monitor() {
+        let (_backend, application, directory) = setup();
+        let mut locked = application.lock();
+        let mut paths = vec![];
+        for (name, width, height) in [("large.png", 128, 72), ("small.png", 64, 36)] {
+            let path = directory.path().join(name);
+            RgbaImage::new(width, height)
+                .save(&path)
+                .expect("Failed to save");
+            paths.push(path);
+        }
+        locked
+            .add_wallpaper_file(&paths[0], Fitting::Cover)
+            .expect("Should add");
+        locked.add_variant_file(0, &paths[1]).expect("Should add");
+        locked.add_variant_file(0, &paths[1]).expect("Should add");
+        assert_eq!(locked.wallpapers()[0].variants().len(), 1);
+
+        drop(locked);
+        Application::apply_wallpaper_for_monitor(&application, 0, 0).expect("Should apply");
+        Application::apply_wallpaper_for_monitor(&application, 1, 0).expect("Should apply");
+        let locked = application.lock();
+
+        // Small one is too short for the secondary monitor
+        assert_eq!(locked.applied_rendition(0), Some(1));
+        assert_eq!(locked.applied_rendition(1), Some(0));
+    }
+
+    #[test]
+    fn add_files_validates_headers() {
+        let (_backend, application, directory) = setup();
+        let valid = directory.path().join("valid.png");
+        RgbaImage::new(16, 9).save(&valid).expect("Failed to save");
+        let broken = directory.path().join("broken.png");
+        write(&broken, "not an image").expect("Failed to write");
+
+        let results = Application::add_wallpaper_files(
+            &application,
+            &[
+                valid.clone(),
+                broken,
+                directory.path().join("missing.png"),
+                valid,
+            ],
+            Fitting::Cover,
+        );
+        assert!(matches!(results[..], [Ok(_), Err(_), Err(_), Ok(_)]));
+        assert_eq!(results[0].as_ref().ok(), results[3].as_ref().ok());
+        let locked = application.lock();
+        assert_eq!(locked.wallpapers().len(), 1);
+        drop(locked);
+
+        // Applied even if the list is changed after adding
+        let id = *results[0].as_ref().expect("Should add");
+        add_image(&application, &directory, "a.png");
+        application
+            .lock()
+            .update_wallpaper(1, WallpaperListOperation::MoveUp);
+        Application::apply_wallpaper_by_id(&application, 0, id).expect("Should apply");
+        let locked = application.lock();
+        assert_eq!(locked.assigned_wallpaper(0), Some(1));
+        drop(locked);
+
+        application
+            .lock()
+            .update_wallpaper(1, WallpaperListOperation::Remove);
+        assert!(Application::apply_wallpaper_by_id(&application, 0, id).is_err());
+    }
+
+    #[test]
+    fn source_follows_folder() {
+        let (_backend, application, directory) = setup();
+        let folder = directory.path().join("folder");
+        create_dir_all(folder.join("nested")).expect("Failed to create");
+        for name in ["a.png", "nested/b.png", "nested/c.png"] {
+            RgbaImage::new(16, 9)
+                .save(folder.join(name))
+                .expect("Failed to save");
+        }
+
+        {
+            let mut locked = application.lock();
+            locked
+                .add_source(&folder, vec![], vec!["**/c.png".into()])
+                .expect("Should add");
+            assert!(locked.add_source(&folder, vec![], vec![]).is_err());
+            // Scanned in background
+            assert!(locked.wallpapers().is_empty());
+        }
+        Application::tick_sources(&application);
+        {
+            let locked = application.lock();
+            assert_eq!(locked.wallpapers().len(), 2);
+            assert_eq!(locked.wallpapers()[0].image_size(), Some(Vec2::new(16, 9)));
+        }
+
+        remove_file(folder.join("a.png")).expect("Failed to remove");
+        RgbaImage::new(16, 9)
+            .save(folder.join("d.png"))
+            .expect("Failed to save");
+        application.lock().rescan_sources();
+        Application::tick_sources(&application);
+        {
+            let locked = application.lock();
+            let names: Vec<_> = locked
+                .wallpapers()
+                .iter()
+                .map(|w| Path::new(w.filename()).file_name().expect("Should be file"))
+                .collect();
+            assert_eq!(names, ["b.png", "d.png"]);
+        }
+
+        application
+            .lock()
+            .set_source_globs(0, vec![], vec![])
+            .expect("Should set");
+        Application::tick_sources(&application);
+        let mut locked = application.lock();
+        assert_eq!(locked.wallpapers().len(), 3);
+        locked.remove_source(0);
+        assert!(locked.wallpapers().is_empty());
+    }
+
+    #[test]
+    fn source_keeps_entries_while_offline() {
+        let (_backend, application, directory) = setup();
+        let folder = directory.path().join("folder");
+        create_dir_all(&folder).expect("Failed to create");
+        for name in ["a.png", "b.png"] {
+            RgbaImage::new(16, 9)
+                .save(folder.join(name))
+                .expect("Failed to save");
+        }
+
+        application
+            .lock()
+            .add_source(&folder, vec![], vec![])
+            .expect("Should add");
+        Application::tick_sources(&application);
+        assert_eq!(application.lock().wallpapers().len(), 2);
+
+        // Like an unmounted drive
+        let moved = directory.path().join("moved");
+        rename(&folder, &moved).expect("Failed to rename");
+        application.lock().rescan_sources();
+        Application::tick_sources(&application);
+        assert_eq!(application.lock().wallpapers().len(), 2);
+
+        rename(&moved, &folder).expect("Failed to rename");
+        remove_file(folder.join("a.png")).expect("Failed to remove");
+        application.lock().rescan_sources();
+        Application::tick_sources(&application);
+        assert_eq!(application.lock().wallpapers().len(), 1);
+    }
+
+    #[test]
+    fn source_scan_merges_into_changed_library() {
+        let (_backend, application, directory) = setup();
+        let folder = directory.path().join("folder");
+        create_dir_all(&folder).expect("Failed to create");
+        for name in ["a.png", "b.png"] {
+            RgbaImage::new(16, 9)
+                .save(folder.join(name))
+                .expect("Failed to save");
+        }
+        application
+            .lock()
+            .add_source(&folder, vec![], vec![])
+            .expect("Should add");
+
+        // The library is changed while scanning
+        let scans = application.lock().prepare_scans();
+        let scanned: Vec<_> = scans
+            .into_iter()
+            .map(|s| s.run().expect("Should scan"))
+            .collect();
+        let mut locked = application.lock();
+        locked
+            .add_wallpaper_file(&folder.join("a.png"), Fitting::Contain)
+            .expect("Should add");
+        for scanned in scanned {
+            locked.finish_scan(scanned);
+        }
+
+        let fittings: Vec<_> = locked.wallpapers().iter().map(|w| w.fitting()).collect();
+        assert_eq!(fittings, [Fitting::Contain, Fitting::Cover]);
+
+        // Removed while scanning
+        locked.rescan_sources();
+        let scans = locked.prepare_scans();
+        locked.remove_source(0);
+        for scan in scans {
+            locked.finish_scan(scan.run().expect("Should scan"));
+        }
+        assert_eq!(locked.wallpapers().len(), 1);
+    }
+
+    #[test]
+    fn span_applies_for_group() {
+        let (backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+
+        let mut locked = application.lock();
+        locked.update_wallpaper(0, WallpaperListOperation::SetFitting(Fitting::Span));
+        drop(locked);
+        Application::apply_wallpaper_for_monitor(&application, 1, 0).expect("Should apply");
+        let mut locked = application.lock();
+
+        let applied = backend.applied();
+        assert_eq!(applied.len(), 2);
+        let rendered = image::open(&applied[0].1).expect("Should be rendered");
+        assert_eq!((rendered.width(), rendered.height()), (64, 36));
+        let wallpaper_id = locked.wallpapers()[0].id();
+        assert_eq!(locked.assignments.get("primary"), Some(&wallpaper_id));
+
+        locked.set_span_group(1, Some("Side".into()));
+        assert_eq!(locked.span_members(0), vec![0]);
+        drop(locked);
+        Application::apply_wallpaper_for_monitor(&application, 1, 0).expect("Should apply");
+        let applied = backend.applied();
+        assert_eq!(applied.len(), 3);
+        assert_eq!(applied[2].0, MonitorId::new("secondary"));
+    }
+
+    #[test]
+    fn list_operations() {
+        let (_, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+        add_image(&application, &directory, "b.png");
+        add_image(&application, &directory, "c.png");
+
+        let mut locked = application.lock();
+        let ids: Vec<_> = locked.wallpapers().iter().map(|w| w.id()).collect();
+
+        locked.update_wallpaper(0, WallpaperListOperation::MoveUp);
+        locked.update_wallpaper(2, WallpaperListOperation::MoveUp);
+        locked.update_wallpaper(1, WallpaperListOperation::SetFitting(Fitting::Tile));
+        let current: Vec<_> = locked.wallpapers().iter().map(|w| w.id()).collect();
+        assert_eq!(current, vec![ids[0], ids[2], ids[1]]);
+        assert_eq!(locked.wallpapers()[1].fitting(), Fitting::Tile);
+
+        locked.update_wallpaper(2, WallpaperListOperation::MoveDown);
+        locked.update_wallpaper(0, WallpaperListOperation::Remove);
+        let current: Vec<_> = locked.wallpapers().iter().map(|w| w.id()).collect();
+        assert_eq!(current, vec![ids[2], ids[1]]);
+    }
+
+    #[test]
+    fn local_time_uses_initial_offset() {
+        let backend = Arc::new(MemoryBackend::new(vec![]));
+        let offset = UtcOffset::from_hms(9, 0, 0).expect("Should be valid");
+        let application = Application::new(backend, PathBuf::new(), offset);
+
+        // Used before any tick, as by commands
+        assert_eq!(application.lock().local_now().offset(), offset);
+    }
+
+    #[test]
+    fn rotation_applies_on_tick() {
+        let (backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+        add_image(&application, &directory, "b.png");
+
+        let updates = Arc::new(Mutex::new(0));
+        let counter = updates.clone();
+        let _subscription = application.lock().subscribe(move |e| {
+            if e == ApplicationEvent::RotationUpdated {
+                *counter.lock() += 1;
+            }
+        });
+
+        application
+            .lock()
+            .set_rotation_interval(0, Some(Duration::from_secs(60)));
+        let start = Instant::now();
+        let local = OffsetDateTime::now_utc();
+        Application::tick_rotation(&application, start, local);
+        assert!(backend.applied().is_empty());
+
+        // Notified only when the countdown changes
+        *updates.lock() = 0;
+        Application::tick_rotation(&application, start, local);
+        assert_eq!(*updates.lock(), 0);
+        Application::tick_rotation(&application, start + Duration::from_secs(1), local);
+        assert_eq!(*updates.lock(), 1);
+
+        Application::tick_rotation(&application, start + Duration::from_secs(61), local);
+        application.lock().set_rotation_paused(0, true);
+        Application::tick_rotation(&application, start + Duration::from_secs(600), local);
+        application.lock().set_rotation_paused(0, false);
+        Application::tick_rotation(&application, start + Duration::from_secs(1200), local);
+
+        let applied = backend.applied();
+        assert_eq!(applied.len(), 2);
+        assert!(applied.iter().all(|(m, _)| *m == MonitorId::new("primary")));
+        let mut locked = application.lock();
+        let wallpaper_id = locked.wallpapers()[1].id();
+        assert_eq!(locked.assignments.get("primary"), Some(&wallpaper_id));
+
+        locked.set_rotation_interval(0, None);
+        assert_eq!(locked.rotation_status(0, start), None);
+    }
+
+    #[test]
+    fn schedule_applies_on_change() {
+        let (backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+        add_image(&application, &directory, "b.png");
+
+        let mut locked = application.lock();
+        let scheduled_id = locked.wallpapers()[1].id();
+        let rule = ScheduleRule {
+            monitors: vec!["secondary".into()],
+            wallpapers: vec![scheduled_id],
+            ..ScheduleRule::new("test")
+        };
+        locked.update_schedule(ScheduleOperation::Add(rule));
+
+        drop(locked);
+
+        let start = Instant::now();
+        let local = OffsetDateTime::now_utc();
+        Application::tick_rotation(&application, start, local);
+        Application::tick_rotation(&application, start + Duration::from_secs(1), local);
+
+        let locked = application.lock();
+        let applied = backend.applied();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0].0, MonitorId::new("secondary"));
+        assert_eq!(locked.assignments.get("secondary"), Some(&scheduled_id));
+        assert_eq!(locked.scheduled_source(0), None);
+        assert_eq!(locked.scheduled_source(1), Some(ScheduleSource::Rule(0)));
+    }
+
+    #[test]
+    fn schedule_ignores_stale_indices() {
+        let (_backend, application, _directory) = setup();
+        let mut locked = application.lock();
+        locked.update_schedule(ScheduleOperation::Add(ScheduleRule::new("a")));
+        locked.update_schedule(ScheduleOperation::Add(ScheduleRule::new("b")));
+
+        locked.update_schedule(ScheduleOperation::Remove(1));
+        locked.update_schedule(ScheduleOperation::Remove(1));
+        locked.update_schedule(ScheduleOperation::Replace(1, ScheduleRule::new("c")));
+        locked.update_schedule(ScheduleOperation::MoveUp(1));
+        locked.update_schedule(ScheduleOperation::MoveDown(1));
+        let names: Vec<_> = locked.schedules().iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["a"]);
+    }
+
+    #[test]
+    fn collection_operations_ignore_stale_indices() {
+        let (_backend, application, _directory) = setup();
+        let mut locked = application.lock();
+        locked.update_collection(CollectionOperation::Add("a".into()));
+
+        locked.update_collection(CollectionOperation::Remove(0));
+        locked.update_collection(CollectionOperation::Remove(0));
+        locked.update_collection(CollectionOperation::Rename(0, "b".into()));
+        locked.update_collection(CollectionOperation::SetQuery(0, SmartQuery::default()));
+        assert!(locked.collections().is_empty());
+    }
+
+    #[test]
+    fn wallpaper_operations_ignore_stale_indices() {
+        let (_backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+
+        // Like the list shrunk by folder sources before the operation arrives
+        let mut locked = application.lock();
+        locked.update_wallpaper(1, WallpaperListOperation::Remove);
+        locked.update_wallpaper(1, WallpaperListOperation::SetRating(3));
+        locked.edit_tags(&[0, 1], &["sea".into()], &[]);
+        locked.set_collage(1, "collage".into(), Collage::default());
+        locked.remove_source(0);
+        assert!(locked
+            .add_variant_file(1, &directory.path().join("a.png"))
+            .is_err());
+        assert!(locked.set_source_globs(0, vec![], vec![]).is_err());
+        drop(locked);
+        assert!(Application::apply_wallpaper_for_monitor(&application, 0, 1).is_err());
+        let locked = application.lock();
+
+        assert_eq!(locked.wallpapers().len(), 1);
+        assert_eq!(locked.wallpapers()[0].tags(), ["sea"]);
+    }
+
+    #[test]
+    fn solar_applies_by_phase() {
+        let (backend, application, directory) = setup();
+        add_image(&application, &directory, "day.png");
+        add_image(&application, &directory, "night.png");
+
+        let mut locked = application.lock();
+        let day_id = locked.wallpapers()[0].id();
+        let night_id = locked.wallpapers()[1].id();
+        locked.set_solar(SolarSettings {
+            enabled: true,
+            latitude: 51.5,
+            longitude: 0.0,
+            day: vec![day_id],
+            night: vec![night_id],
+            ..Default::default()
+        });
+
+        let at = |hour| {
+            time::Date::from_calendar_date(2022, time::Month::December, 21)
+                .and_then(|d| d.with_hms(hour, 0, 0))
+                .expect("Should be valid")
+                .assume_utc()
+        };
+        drop(locked);
+
+        let start = Instant::now();
+        Application::tick_rotation(&application, start, at(12));
+        assert_eq!(application.lock().assignments.get("primary"), Some(&day_id));
+        Application::tick_rotation(&application, start, at(20));
+        let locked = application.lock();
+        assert_eq!(locked.assignments.get("secondary"), Some(&night_id));
+        assert_eq!(
+            locked.scheduled_source(0),
+            Some(ScheduleSource::Solar(SolarPhase::Night))
+        );
+        assert_eq!(backend.applied().len(), 4);
+    }
+
+    #[test]
+    fn clock_overlay_redraws_each_minute() {
+        let (backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+
+        Application::apply_wallpaper_for_monitor(&application, 0, 0).expect("Should apply");
+        let plain = backend.applied()[0].1.clone();
+        let plain = image::open(plain).expect("Should be rendered").to_rgba8();
+
+        Application::set_overlays(
+            &application,
+            vec![Overlay {
+                content: OverlayContent::Clock,
+                size: 30.0,
+                ..Default::default()
+            }],
+        );
+        assert_eq!(backend.applied().len(), 2);
+
+        let at = |minute| {
+            time::Date::from_calendar_date(2022, time::Month::December, 21)
+                .and_then(|d| d.with_hms(12, minute, 0))
+                .expect("Should be valid")
+                .assume_utc()
+        };
+
+        let start = Instant::now();
+        Application::tick_rotation(&application, start, at(0));
+        Application::tick_rotation(&application, start, at(0));
+        assert_eq!(backend.applied().len(), 3);
+        Application::tick_rotation(&application, start, at(1));
+        assert_eq!(backend.applied().len(), 4);
+
+        // Drawn onto the same base image
+        let drawn = backend.applied()[3].1.clone();
+        let drawn = image::open(drawn).expect("Should be rendered").to_rgba8();
+        assert_eq!(drawn.dimensions(), plain.dimensions());
+        assert_ne!(drawn, plain);
+    }
+
+    #[test]
+    fn clock_overlay_redraws_after_restart() {
+        let (backend, application, directory) = setup();
+        add_image(&application, &directory, "a.png");
+
+        Application::apply_wallpaper_for_monitor(&application, 0, 0).expect("Should apply");
+        Application::set_overlays(
+            &application,
+            vec![Overlay {
+                content: OverlayContent::Clock,
+                size: 30.0,
+                ..Default::default()
+            }],
+        );
+        let mut locked = application.lock();
+        let history = locked.histories.clone();
+
+        // Rendered images are not kept over restart
+        locked.base_images.clear();
+        drop(locked);
+        Application::tick_rotation(&application, Instant::now(), OffsetDateTime::now_utc());
+
+        let applied = backend.applied();
+        assert_eq!(applied.len(), 3);
+        assert_eq!(applied[2].0, MonitorId::new("primary"));
+        let drawn = image::open(&applied[2].1).expect("Should be rendered");
+        assert_eq!((drawn.width(), drawn.height()), (64, 36));
+
+        // Redrawing is not showing a wallpaper again
+        let locked = application.lock();
+        assert!(locked.base_images.contains_key("primary"));
+        assert_eq!(locked.histories, history);
+    }
 }
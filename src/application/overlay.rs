@@ -0,0 +1,195 @@
+//! Provides settings of text overlays rendered into wallpapers.
+
+use std::{fs::File, io::BufReader, path::Path};
+
+use exif::{In, Reader, Tag, Value};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// What an overlay shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayContent {
+    /// Filename of the wallpaper.
+    #[default]
+    Filename,
+
+    /// Date taken from EXIF of the wallpaper.
+    TakenAt,
+
+    /// Text of the overlay.
+    CustomText,
+
+    /// Today's date.
+    Date,
+
+    /// Calendar of this month.
+    Calendar,
+
+    /// Current time in minutes.
+    Clock,
+}
+
+impl OverlayContent {
+    /// All contents, for choices in UI.
+    pub const ALL: &'static [OverlayContent] = &[
+        OverlayContent::Filename,
+        OverlayContent::TakenAt,
+        OverlayContent::CustomText,
+        OverlayContent::Date,
+        OverlayContent::Calendar,
+        OverlayContent::Clock,
+    ];
+
+    /// Gets display name.
+    pub fn label(self) -> &'static str {
+        match self {
+            OverlayContent::Filename => "Filename",
+            OverlayContent::TakenAt => "Date Taken",
+            OverlayContent::CustomText => "Custom Text",
+            OverlayContent::Date => "Date",
+            OverlayContent::Calendar => "Calendar",
+            OverlayContent::Clock => "Clock",
+        }
+    }
+
+    /// Checks whether this changes along the time and needs periodic rendering.
+    pub fn is_timed(self) -> bool {
+        matches!(
+            self,
+            OverlayContent::Date | OverlayContent::Calendar | OverlayContent::Clock
+        )
+    }
+}
+
+/// Where an overlay is placed on the monitor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlayAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    #[default]
+    BottomRight,
+}
+
+impl OverlayAnchor {
+    /// All anchors, for choices in UI.
+    pub const ALL: &'static [OverlayAnchor] = &[
+        OverlayAnchor::TopLeft,
+        OverlayAnchor::Top,
+        OverlayAnchor::TopRight,
+        OverlayAnchor::Left,
+        OverlayAnchor::Center,
+        OverlayAnchor::Right,
+        OverlayAnchor::BottomLeft,
+        OverlayAnchor::Bottom,
+        OverlayAnchor::BottomRight,
+    ];
+
+    /// Gets display name.
+    pub fn label(self) -> &'static str {
+        match self {
+            OverlayAnchor::TopLeft => "Top Left",
+            OverlayAnchor::Top => "Top",
+            OverlayAnchor::TopRight => "Top Right",
+            OverlayAnchor::Left => "Left",
+            OverlayAnchor::Center => "Center",
+            OverlayAnchor::Right => "Right",
+            OverlayAnchor::BottomLeft => "Bottom Left",
+            OverlayAnchor::Bottom => "Bottom",
+            OverlayAnchor::BottomRight => "Bottom Right",
+        }
+    }
+
+    /// Gets alignment in 0.0 (left or top) to 1.0 (right or bottom).
+    pub fn alignment(self) -> (f32, f32) {
+        let index = OverlayAnchor::ALL
+            .iter()
+            .position(|a| *a == self)
+            .expect("Should be listed");
+        ((index % 3) as f32 / 2.0, (index / 3) as f32 / 2.0)
+    }
+}
+
+/// Text overlay rendered into wallpapers of all monitors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Overlay {
+    pub content: OverlayContent,
+
+    /// Shown by `OverlayContent::CustomText`.
+    pub text: String,
+
+    /// Path of TrueType or OpenType font. Bundled one is used if empty.
+    pub font: String,
+
+    /// Height of text in percent of the monitor height.
+    pub size: f32,
+    pub color: [u8; 3],
+    pub anchor: OverlayAnchor,
+    pub shadow: bool,
+}
+
+impl Default for Overlay {
+    fn default() -> Overlay {
+        Overlay {
+            content: OverlayContent::default(),
+            text: String::new(),
+            font: String::new(),
+            size: 3.0,
+            color: [255, 255, 255],
+            anchor: OverlayAnchor::default(),
+            shadow: true,
+        }
+    }
+}
+
+/// Wallpaper properties shown by overlays.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Caption {
+    pub filename: String,
+
+    /// Date taken from EXIF, as `YYYY-MM-DD`.
+    pub taken_at: Option<String>,
+}
+
+impl Caption {
+    /// Reads properties of the image file.
+    pub fn read(path: &Path) -> Caption {
+        Caption {
+            filename: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            taken_at: read_taken_at(path),
+        }
+    }
+}
+
+/// Reads the date taken from EXIF, as `YYYY-MM-DD`.
+pub fn read_taken_at(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let exif = match Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(e) => e,
+        Err(exif::Error::NotFound(_)) => return None,
+        Err(e) => {
+            warn!("Failed to read EXIF of {}: {e}", path.display());
+            return None;
+        }
+    };
+    let field = exif
+        .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+        .or_else(|| exif.get_field(Tag::DateTime, In::PRIMARY))?;
+    match &field.value {
+        // Formatted as "YYYY:MM:DD HH:MM:SS"
+        Value::Ascii(values) => {
+            let date = String::from_utf8_lossy(values.first()?.get(..10)?).replace(':', "-");
+            Some(date)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,270 @@
+//! Provides tag queries which filter wallpapers by tags and filenames.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, Error as AnyhowError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Boolean expression of tags and words, such as `#space AND NOT #dark`.
+/// `#tag` matches tags exactly, and other words match filenames or tags partially.
+/// Adjacent terms are combined by `AND`. Empty query matches everything.
+/// Serialized as its text.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TagQuery {
+    text: String,
+    expression: Option<Expression>,
+}
+
+impl TagQuery {
+    /// Checks whether this query matches everything.
+    pub fn is_empty(&self) -> bool {
+        self.expression.is_none()
+    }
+
+    /// Checks whether a wallpaper with the filename and tags matches.
+    pub fn matches(&self, filename: &str, tags: &[String]) -> bool {
+        // Either separator, as libraries may hold Windows paths
+        let name = filename
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or(filename)
+            .to_lowercase();
+        match &self.expression {
+            Some(e) => e.evaluate(&name, tags),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for TagQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl FromStr for TagQuery {
+    type Err = AnyhowError;
+
+    fn from_str(s: &str) -> Result<TagQuery, AnyhowError> {
+        let tokens = tokenize(s);
+        let expression = if tokens.is_empty() {
+            None
+        } else {
+            let mut parser = Parser {
+                tokens,
+                position: 0,
+            };
+            let expression = parser.parse_or()?;
+            if let Some(token) = parser.tokens.get(parser.position) {
+                bail!("Unexpected {token:?} in query");
+            }
+            Some(expression)
+        };
+        Ok(TagQuery {
+            text: s.trim().to_string(),
+            expression,
+        })
+    }
+}
+
+impl TryFrom<String> for TagQuery {
+    type Error = AnyhowError;
+
+    fn try_from(s: String) -> Result<TagQuery, AnyhowError> {
+        s.parse()
+    }
+}
+
+impl From<TagQuery> for String {
+    fn from(query: TagQuery) -> String {
+        query.text
+    }
+}
+
+/// Normalizes a tag into lowercase without leading `#`, or `None` if it is empty.
+/// Whitespaces are replaced with `-` so that tags can be written in queries.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let normalized: String = tag
+        .trim()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    if normalized.is_empty() {
+        None
+    } else {
+        Some(normalized)
+    }
+}
+
+/// Parsed node of queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expression {
+    Tag(String),
+    Word(String),
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Evaluates for lowercase filename and tags.
+    fn evaluate(&self, name: &str, tags: &[String]) -> bool {
+        match self {
+            Expression::Tag(t) => tags.iter().any(|tag| tag == t),
+            Expression::Word(w) => name.contains(w) || tags.iter().any(|tag| tag.contains(w)),
+            Expression::Not(e) => !e.evaluate(name, tags),
+            Expression::And(a, b) => a.evaluate(name, tags) && b.evaluate(name, tags),
+            Expression::Or(a, b) => a.evaluate(name, tags) || b.evaluate(name, tags),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+/// Splits the query into tokens. `"` quotes words containing whitespaces,
+/// and `-` or `!` before a term negates it.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '-' | '!' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            '"' => {
+                chars.next();
+                let quoted: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                tokens.push(Token::Term(quoted));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.as_str() {
+                    "AND" | "&&" => Token::And,
+                    "OR" | "||" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Term(word),
+                });
+            }
+        }
+    }
+    tokens
+}
+
+/// Recursive descent parser, `NOT` binding tighter than `AND` tighter than `OR`.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_or(&mut self) -> Result<Expression> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            let right = self.parse_and()?;
+            left = Expression::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression> {
+        let mut left = self.parse_not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.position += 1,
+                Some(Token::Open | Token::Not | Token::Term(_)) => (),
+                _ => break,
+            }
+            let right = self.parse_not()?;
+            left = Expression::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expression> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(Expression::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression> {
+        let token = match self.tokens.get(self.position) {
+            Some(t) => t.clone(),
+            None => bail!("Query ends unexpectedly"),
+        };
+        self.position += 1;
+        match token {
+            Token::Open => {
+                let expression = self.parse_or()?;
+                if self.peek() != Some(&Token::Close) {
+                    bail!("Missing closing parenthesis in query");
+                }
+                self.position += 1;
+                Ok(expression)
+            }
+            Token::Term(term) if term.starts_with('#') => match normalize_tag(&term) {
+                Some(tag) => Ok(Expression::Tag(tag)),
+                None => bail!("Empty tag in query"),
+            },
+            Token::Term(term) => Ok(Expression::Word(term.to_lowercase())),
+            token => bail!("Unexpected {token:?} in query"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_combines_terms() {
+        let query: TagQuery = "#space AND NOT #dark".parse().expect("Should parse");
+        let tags = |t: &[&str]| t.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert!(query.matches("a.png", &tags(&["space"])));
+        assert!(!query.matches("a.png", &tags(&["space", "dark"])));
+        assert!(!query.matches("space.png", &[]));
+
+        let query: TagQuery = "(#Forest OR lake) -#winter".parse().expect("Should parse");
+        assert!(query.matches("C:\\Lake.jpg", &[]));
+        assert!(query.matches("a.png", &tags(&["forest"])));
+        assert!(!query.matches("lake.png", &tags(&["winter"])));
+        assert!(!query.matches("C:\\lake\\a.png", &[]));
+
+        assert!(TagQuery::default().matches("a.png", &[]));
+        assert!("#space AND".parse::<TagQuery>().is_err());
+        assert!("(#space".parse::<TagQuery>().is_err());
+        assert_eq!(normalize_tag(" #Deep Space "), Some("deep-space".into()));
+    }
+}
@@ -0,0 +1,270 @@
+//! Provides rendering of wallpapers apart from the model, so that it does not hold the lock.
+
+use crate::{
+    application::{Adjustments, Background, Caption, Collage, Fitting, FocalPoint, Overlay},
+    render::{
+        adjust_colors, compose, compose_collage, compose_span, content_hash, detect_focal_point,
+        draw_overlays, orient, save_rendered, CollageCell,
+    },
+};
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use image::{DynamicImage, RgbaImage};
+use log::error;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use vek::Vec2;
+
+/// Image file and how it is shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceImage {
+    pub path: PathBuf,
+    pub adjustments: Adjustments,
+
+    /// `None` uses detected one.
+    pub focal_point: Option<FocalPoint>,
+}
+
+/// What a rendering starts from.
+#[derive(Debug, Clone)]
+pub enum RenderSource {
+    /// Image file composed into monitor size.
+    Image {
+        image: SourceImage,
+        fitting: Fitting,
+        background: Background,
+    },
+
+    /// Member images composed into the canvas size. Failing ones are skipped.
+    Collage {
+        name: String,
+        cells: Vec<SourceImage>,
+        canvas_size: Vec2<u32>,
+        collage: Collage,
+        adjustments: Adjustments,
+    },
+
+    /// Already rendered image, only overlays are drawn.
+    Rendered(Arc<RgbaImage>, Caption),
+}
+
+/// Slice of the span group covered by a monitor, in physical units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpanSlice {
+    pub desktop_size: Vec2<f32>,
+    pub position: Vec2<f32>,
+    pub size: Vec2<f32>,
+}
+
+/// Monitor which a rendering is set for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderTarget {
+    pub monitor_index: usize,
+    pub monitor_key: String,
+    pub size: Vec2<u32>,
+
+    /// Set for spanned wallpapers.
+    pub span: Option<SpanSlice>,
+}
+
+/// Recorded as an assignment when the rendering is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Assignment {
+    pub wallpaper_id: Uuid,
+
+    /// Frame index of dynamic wallpaper.
+    pub frame: Option<usize>,
+}
+
+/// Everything needed to render a wallpaper, taken out of the model.
+#[derive(Debug, Clone)]
+pub struct RenderJob {
+    pub source: RenderSource,
+    pub targets: Vec<RenderTarget>,
+    pub overlays: Vec<Overlay>,
+
+    /// Time shown by timed overlays.
+    pub local: OffsetDateTime,
+    pub rendered_directory: PathBuf,
+
+    /// `None` if assignments do not change, like redrawing overlays.
+    pub assignment: Option<Assignment>,
+}
+
+/// Rendered and saved image for a monitor.
+#[derive(Debug, Clone)]
+pub struct RenderedImage {
+    pub target: RenderTarget,
+
+    /// Kept to redraw overlays without decoding the source.
+    pub base: Arc<RgbaImage>,
+    pub caption: Caption,
+    pub path: PathBuf,
+}
+
+/// Result of `RenderJob`.
+#[derive(Debug, Clone)]
+pub struct RenderedJob {
+    pub images: Vec<RenderedImage>,
+    pub assignment: Option<Assignment>,
+
+    /// Focal points newly detected, keyed by the file content hash.
+    pub detected_focal_points: Vec<(String, FocalPoint)>,
+}
+
+impl RenderJob {
+    /// Gets the index of the monitor which the job is for, used in messages.
+    pub fn monitor_index(&self) -> usize {
+        self.targets.first().map(|t| t.monitor_index).unwrap_or(0)
+    }
+
+    /// Decodes, composes and saves images for all targets.
+    /// `cached_focal_point` looks up focal points detected before by the file content hash.
+    pub fn render(
+        self,
+        cached_focal_point: impl Fn(&str) -> Option<FocalPoint>,
+    ) -> Result<RenderedJob> {
+        let mut detected = vec![];
+        let mut load = |image: &SourceImage| -> Result<(DynamicImage, FocalPoint)> {
+            let source = image::open(&image.path)?;
+            let focus = match image.focal_point {
+                Some(p) => p,
+                None => {
+                    let point =
+                        detect_cached(&image.path, &source, &cached_focal_point, &mut detected);
+                    image.adjustments.orient_point(point)
+                }
+            };
+            Ok((orient(&source, image.adjustments), focus))
+        };
+
+        let (bases, caption): (Vec<Arc<RgbaImage>>, _) = match &self.source {
+            RenderSource::Image {
+                image,
+                fitting,
+                background,
+            } => {
+                let (source, focus) = load(image)?;
+                let bases = self
+                    .targets
+                    .iter()
+                    .map(|t| {
+                        let mut rendered = compose_target(&source, t, *fitting, *background, focus);
+                        adjust_colors(&mut rendered, image.adjustments);
+                        Arc::new(rendered)
+                    })
+                    .collect();
+                (bases, Caption::read(&image.path))
+            }
+            RenderSource::Collage {
+                name,
+                cells,
+                canvas_size,
+                collage,
+                adjustments,
+            } => {
+                let mut loaded = vec![];
+                for cell in cells {
+                    match load(cell) {
+                        Ok((image, focus)) => loaded.push(CollageCell {
+                            image,
+                            focus,
+                            adjustments: cell.adjustments,
+                        }),
+                        Err(e) => {
+                            error!("Failed to load collage member {}: {e}", cell.path.display())
+                        }
+                    }
+                }
+                let mut composed = compose_collage(&loaded, *canvas_size, collage);
+                adjust_colors(&mut composed, *adjustments);
+                let caption = Caption {
+                    filename: name.clone(),
+                    taken_at: None,
+                };
+                let composed = Arc::new(composed);
+                let bases = self.targets.iter().map(|_| composed.clone()).collect();
+                (bases, caption)
+            }
+            RenderSource::Rendered(base, caption) => {
+                let bases = self.targets.iter().map(|_| base.clone()).collect();
+                (bases, caption.clone())
+            }
+        };
+
+        let mut images = vec![];
+        for (target, base) in self.targets.into_iter().zip(bases) {
+            let path = if self.overlays.is_empty() {
+                save_rendered(&self.rendered_directory, &target.monitor_key, &base)?
+            } else {
+                let mut image = (*base).clone();
+                draw_overlays(&mut image, &self.overlays, &caption, self.local);
+                save_rendered(&self.rendered_directory, &target.monitor_key, &image)?
+            };
+            images.push(RenderedImage {
+                target,
+                base,
+                caption: caption.clone(),
+                path,
+            });
+        }
+
+        Ok(RenderedJob {
+            images,
+            assignment: self.assignment,
+            detected_focal_points: detected,
+        })
+    }
+}
+
+/// Renders the oriented source image into monitor size according to `Fitting` and `Background`.
+/// `focus` is kept visible as possible when cropped.
+/// Spanned one is sliced from the physical arrangement of the span group.
+fn compose_target(
+    source: &DynamicImage,
+    target: &RenderTarget,
+    fitting: Fitting,
+    background: Background,
+    focus: FocalPoint,
+) -> RgbaImage {
+    match (fitting, target.span) {
+        (Fitting::Span, Some(span)) => compose_span(
+            source,
+            span.desktop_size,
+            span.position,
+            span.size,
+            target.size,
+            focus,
+        ),
+        _ => compose(source, target.size, fitting, background, focus),
+    }
+}
+
+/// Detects the focal point of the image file, unless cached by the file content hash.
+fn detect_cached(
+    path: &Path,
+    source: &DynamicImage,
+    cached: impl Fn(&str) -> Option<FocalPoint>,
+    detected: &mut Vec<(String, FocalPoint)>,
+) -> FocalPoint {
+    let hash = match content_hash(path) {
+        Ok(h) => h,
+        Err(e) => {
+            error!("Failed to hash {}: {e}", path.display());
+            return detect_focal_point(source);
+        }
+    };
+    if let Some(point) =
+        cached(&hash).or_else(|| detected.iter().find(|(h, _)| *h == hash).map(|(_, p)| *p))
+    {
+        return point;
+    }
+    let point = detect_focal_point(source);
+    detected.push((hash, point));
+    point
+}
@@ -0,0 +1,140 @@
+//! Provides timed wallpaper rotation.
+
+use crate::application::{query::TagQuery, selection::RotationOrder};
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Rotation settings of a monitor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RotationSettings {
+    /// Interval in seconds.
+    pub interval: u64,
+
+    /// Whether rotation is paused.
+    pub paused: bool,
+
+    /// Order of wallpapers.
+    #[serde(default)]
+    pub order: RotationOrder,
+
+    /// Restricts wallpapers shown on the monitor.
+    #[serde(default)]
+    pub query: TagQuery,
+
+    /// UUID of `Collection` to rotate through instead of whole library.
+    #[serde(default)]
+    pub collection: Option<Uuid>,
+}
+
+impl RotationSettings {
+    /// Gets interval as `Duration`.
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval.max(1))
+    }
+}
+
+/// Current rotation status of a monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationStatus {
+    pub interval: Duration,
+    pub paused: bool,
+    pub order: RotationOrder,
+    pub remaining: Duration,
+}
+
+/// Measures time until next rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationTimer {
+    deadline: Instant,
+    paused_remaining: Option<Duration>,
+}
+
+impl RotationTimer {
+    /// Starts new timer from settings.
+    pub fn start(settings: &RotationSettings, now: Instant) -> RotationTimer {
+        let mut timer = RotationTimer {
+            deadline: now + settings.interval(),
+            paused_remaining: None,
+        };
+        if settings.paused {
+            timer.pause(now);
+        }
+        timer
+    }
+
+    /// Gets remaining time.
+    pub fn remaining(&self, now: Instant) -> Duration {
+        match self.paused_remaining {
+            Some(r) => r,
+            None => self.deadline.saturating_duration_since(now),
+        }
+    }
+
+    /// Checks whether the wallpaper should be changed.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.paused_remaining.is_none() && now >= self.deadline
+    }
+
+    /// Stops counting down.
+    pub fn pause(&mut self, now: Instant) {
+        if self.paused_remaining.is_none() {
+            self.paused_remaining = Some(self.remaining(now));
+        }
+    }
+
+    /// Restarts counting down from paused time.
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(remaining) = self.paused_remaining.take() {
+            self.deadline = now + remaining;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timer_pause_and_resume() {
+        let now = Instant::now();
+        let settings = RotationSettings {
+            interval: 60,
+            paused: false,
+            order: RotationOrder::Sequential,
+            query: Default::default(),
+            collection: None,
+        };
+        let mut timer = RotationTimer::start(&settings, now);
+        assert!(!timer.is_due(now + Duration::from_secs(59)));
+        assert!(timer.is_due(now + Duration::from_secs(60)));
+
+        timer.pause(now + Duration::from_secs(20));
+        assert!(!timer.is_due(now + Duration::from_secs(600)));
+        assert_eq!(
+            timer.remaining(now + Duration::from_secs(600)),
+            Duration::from_secs(40)
+        );
+
+        timer.resume(now + Duration::from_secs(100));
+        assert!(!timer.is_due(now + Duration::from_secs(139)));
+        assert!(timer.is_due(now + Duration::from_secs(140)));
+    }
+
+    #[test]
+    fn timer_starts_paused() {
+        let now = Instant::now();
+        let settings = RotationSettings {
+            interval: 30,
+            paused: true,
+            order: RotationOrder::Sequential,
+            query: Default::default(),
+            collection: None,
+        };
+        let timer = RotationTimer::start(&settings, now);
+        assert!(!timer.is_due(now + Duration::from_secs(60)));
+        assert_eq!(timer.remaining(now), Duration::from_secs(30));
+    }
+}
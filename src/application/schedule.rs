@@ -0,0 +1,381 @@
+//! Provides schedule rules which select wallpapers by time of day and day of week.
+
+use crate::{
+    application::{TagQuery, Wallpaper},
+    backend::Monitor,
+};
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{bail, Error as AnyhowError, Result};
+use serde::{Deserialize, Serialize};
+use time::{OffsetDateTime, Weekday};
+use uuid::Uuid;
+
+/// Day of week in schedule rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Day {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Day {
+    /// All days from Monday.
+    pub const ALL: &'static [Day] = &[
+        Day::Monday,
+        Day::Tuesday,
+        Day::Wednesday,
+        Day::Thursday,
+        Day::Friday,
+        Day::Saturday,
+        Day::Sunday,
+    ];
+
+    /// Gets abbreviated name.
+    pub fn label(self) -> &'static str {
+        match self {
+            Day::Monday => "Mon",
+            Day::Tuesday => "Tue",
+            Day::Wednesday => "Wed",
+            Day::Thursday => "Thu",
+            Day::Friday => "Fri",
+            Day::Saturday => "Sat",
+            Day::Sunday => "Sun",
+        }
+    }
+}
+
+impl From<Weekday> for Day {
+    fn from(weekday: Weekday) -> Day {
+        Day::ALL[weekday.number_days_from_monday() as usize]
+    }
+}
+
+/// Time of day in minutes precision.
+/// Serialized as `HH:MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ClockTime {
+    hour: u8,
+    minute: u8,
+}
+
+impl ClockTime {
+    /// Constructs new time.
+    pub fn new(hour: u8, minute: u8) -> Result<ClockTime> {
+        if hour >= 24 || minute >= 60 {
+            bail!("Invalid time {hour}:{minute}");
+        }
+        Ok(ClockTime { hour, minute })
+    }
+
+    /// Gets hour.
+    pub fn hour(self) -> u8 {
+        self.hour
+    }
+
+    /// Gets minute.
+    pub fn minute(self) -> u8 {
+        self.minute
+    }
+
+    /// Gets minutes since midnight.
+    pub fn minutes(self) -> u16 {
+        self.hour as u16 * 60 + self.minute as u16
+    }
+}
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+impl FromStr for ClockTime {
+    type Err = AnyhowError;
+
+    fn from_str(s: &str) -> Result<ClockTime, AnyhowError> {
+        let (hour, minute) = match s.split_once(':') {
+            Some(hm) => hm,
+            None => bail!("Time must be HH:MM: {s}"),
+        };
+        ClockTime::new(hour.trim().parse()?, minute.trim().parse()?)
+    }
+}
+
+impl TryFrom<String> for ClockTime {
+    type Error = AnyhowError;
+
+    fn try_from(s: String) -> Result<ClockTime, AnyhowError> {
+        s.parse()
+    }
+}
+
+impl From<ClockTime> for String {
+    fn from(time: ClockTime) -> String {
+        time.to_string()
+    }
+}
+
+/// A rule which assigns wallpapers to a time window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    /// Rule name.
+    pub name: String,
+
+    /// Whether this rule is evaluated.
+    pub enabled: bool,
+
+    /// Start of time window.
+    pub start: ClockTime,
+
+    /// End of time window, exclusive.
+    /// Earlier than `start` means the window crosses midnight, equal means whole day.
+    pub end: ClockTime,
+
+    /// Days when the window starts. Empty means every day.
+    #[serde(default)]
+    pub days: Vec<Day>,
+
+    /// IDs of target monitors. Empty means all monitors.
+    #[serde(default)]
+    pub monitors: Vec<String>,
+
+    /// IDs of wallpapers to show. Empty means whole library.
+    #[serde(default)]
+    pub wallpapers: Vec<Uuid>,
+
+    /// Narrows down wallpapers to show.
+    #[serde(default)]
+    pub query: TagQuery,
+}
+
+impl ScheduleRule {
+    /// Constructs new rule for whole day, every day, all monitors.
+    pub fn new(name: impl Into<String>) -> ScheduleRule {
+        let midnight = ClockTime::new(0, 0).expect("Should be valid");
+        ScheduleRule {
+            name: name.into(),
+            enabled: true,
+            start: midnight,
+            end: midnight,
+            days: vec![],
+            monitors: vec![],
+            wallpapers: vec![],
+            query: TagQuery::default(),
+        }
+    }
+
+    /// Checks whether the time is in the window of this rule.
+    pub fn is_active(&self, now: OffsetDateTime) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let today = now.weekday();
+        let minutes = now.hour() as u16 * 60 + now.minute() as u16;
+        let (start, end) = (self.start.minutes(), self.end.minutes());
+        if start == end {
+            self.starts_on(today)
+        } else if start < end {
+            start <= minutes && minutes < end && self.starts_on(today)
+        } else if minutes >= start {
+            self.starts_on(today)
+        } else if minutes < end {
+            // Continued from the window started yesterday
+            self.starts_on(today.previous())
+        } else {
+            false
+        }
+    }
+
+    /// Checks whether this rule targets the monitor.
+    pub fn targets(&self, monitor: &Monitor) -> bool {
+        self.monitors.is_empty() || self.monitors.iter().any(|m| m == monitor.id().as_str())
+    }
+
+    /// Gets indices of wallpapers which this rule shows.
+    pub fn select_wallpapers(&self, wallpapers: &[Wallpaper]) -> Vec<usize> {
+        (0..wallpapers.len())
+            .filter(|&i| {
+                self.wallpapers.is_empty() || self.wallpapers.contains(&wallpapers[i].id())
+            })
+            .filter(|&i| wallpapers[i].matches(&self.query))
+            .collect()
+    }
+
+    fn starts_on(&self, weekday: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&Day::from(weekday))
+    }
+}
+
+/// Wallpapers selected by a schedule rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledSet {
+    /// Index of matched rule.
+    pub rule_index: usize,
+
+    /// Indices of wallpapers in library.
+    pub wallpapers: Vec<usize>,
+}
+
+/// Evaluates rules and returns scheduled wallpapers for each monitor.
+/// The first matched rule wins, and `None` means no rule matched.
+pub fn evaluate(
+    rules: &[ScheduleRule],
+    now: OffsetDateTime,
+    monitors: &[Monitor],
+    wallpapers: &[Wallpaper],
+) -> Vec<Option<ScheduledSet>> {
+    let active_rules: Vec<_> = rules
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.is_active(now))
+        .collect();
+
+    monitors
+        .iter()
+        .map(|monitor| {
+            active_rules
+                .iter()
+                .filter(|(_, r)| r.targets(monitor))
+                .find_map(|(rule_index, rule)| {
+                    let selected = rule.select_wallpapers(wallpapers);
+                    if selected.is_empty() {
+                        return None;
+                    }
+
+                    Some(ScheduledSet {
+                        rule_index: *rule_index,
+                        wallpapers: selected,
+                    })
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{application::Fitting, backend::MonitorId};
+
+    use time::{Date, Month};
+    use vek::Vec2;
+
+    fn monitors() -> Vec<Monitor> {
+        ["left", "right"]
+            .iter()
+            .map(|id| {
+                Monitor::new(
+                    MonitorId::new(*id),
+                    *id,
+                    Vec2::zero(),
+                    Vec2::new(1920, 1080),
+                )
+            })
+            .collect()
+    }
+
+    fn wallpapers() -> Vec<Wallpaper> {
+        ["calm.png", "dark.png", "photo.png"]
+            .iter()
+            .map(|f| Wallpaper::new(*f, Fitting::Cover))
+            .collect()
+    }
+
+    fn rule(name: &str, start: &str, end: &str, wallpapers: Vec<Uuid>) -> ScheduleRule {
+        ScheduleRule {
+            start: start.parse().expect("Should parse"),
+            end: end.parse().expect("Should parse"),
+            wallpapers,
+            ..ScheduleRule::new(name)
+        }
+    }
+
+    /// Makes time in April 2022. 13th is Wednesday.
+    fn at(day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2022, Month::April, day)
+            .and_then(|d| d.with_hms(hour, minute, 0))
+            .expect("Should be valid")
+            .assume_utc()
+    }
+
+    fn rule_indices(sets: &[Option<ScheduledSet>]) -> Vec<Option<usize>> {
+        sets.iter()
+            .map(|s| s.as_ref().map(|s| s.rule_index))
+            .collect()
+    }
+
+    #[test]
+    fn clock_time_roundtrip() {
+        let time: ClockTime = "7:05".parse().expect("Should parse");
+        assert_eq!(time.to_string(), "07:05");
+        assert!("24:00".parse::<ClockTime>().is_err());
+        assert!("1200".parse::<ClockTime>().is_err());
+    }
+
+    #[test]
+    fn first_active_rule_wins() {
+        let monitors = monitors();
+        let wallpapers = wallpapers();
+        let ids: Vec<_> = wallpapers.iter().map(|w| w.id()).collect();
+        let rules = vec![
+            ScheduleRule {
+                days: vec![Day::Saturday, Day::Sunday],
+                ..rule("weekends", "00:00", "00:00", vec![ids[2]])
+            },
+            ScheduleRule {
+                days: vec![
+                    Day::Monday,
+                    Day::Tuesday,
+                    Day::Wednesday,
+                    Day::Thursday,
+                    Day::Friday,
+                ],
+                ..rule("work", "09:00", "17:00", vec![ids[0]])
+            },
+            rule("night", "19:00", "07:00", vec![ids[1]]),
+        ];
+
+        let sets = evaluate(&rules, at(13, 10, 0), &monitors, &wallpapers);
+        assert_eq!(rule_indices(&sets), vec![Some(1), Some(1)]);
+        assert_eq!(sets[0].as_ref().map(|s| &s.wallpapers[..]), Some(&[0][..]));
+
+        let sets = evaluate(&rules, at(13, 18, 0), &monitors, &wallpapers);
+        assert_eq!(rule_indices(&sets), vec![None, None]);
+
+        // Crosses midnight into Thursday
+        let sets = evaluate(&rules, at(14, 6, 59), &monitors, &wallpapers);
+        assert_eq!(rule_indices(&sets), vec![Some(2), Some(2)]);
+
+        // Saturday night is still weekend
+        let sets = evaluate(&rules, at(16, 23, 0), &monitors, &wallpapers);
+        assert_eq!(rule_indices(&sets), vec![Some(0), Some(0)]);
+    }
+
+    #[test]
+    fn rules_target_monitors_and_existing_wallpapers() {
+        let monitors = monitors();
+        let wallpapers = wallpapers();
+        let rules = vec![
+            ScheduleRule {
+                monitors: vec!["right".into()],
+                ..rule("right only", "00:00", "00:00", vec![])
+            },
+            rule("removed", "00:00", "00:00", vec![Uuid::new_v4()]),
+            ScheduleRule {
+                enabled: false,
+                ..rule("disabled", "00:00", "00:00", vec![])
+            },
+        ];
+
+        let sets = evaluate(&rules, at(13, 12, 0), &monitors, &wallpapers);
+        assert_eq!(rule_indices(&sets), vec![None, Some(0)]);
+        assert_eq!(sets[1].as_ref().map(|s| s.wallpapers.len()), Some(3));
+    }
+}
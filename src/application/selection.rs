@@ -0,0 +1,385 @@
+//! Provides strategies to select next wallpaper.
+
+use std::collections::BTreeMap;
+
+use rand::{seq::SliceRandom, Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Order of wallpapers in rotation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RotationOrder {
+    /// Follows list order.
+    #[default]
+    Sequential,
+
+    /// Picks uniformly at random, but never the current one.
+    Random,
+
+    /// Shows every wallpaper once before any repeat.
+    ShuffleBag,
+
+    /// Prefers wallpapers which have not been shown for the longest time.
+    LeastRecentlyShown,
+}
+
+impl RotationOrder {
+    /// All orders, for choices in UI.
+    pub const ALL: &'static [RotationOrder] = &[
+        RotationOrder::Sequential,
+        RotationOrder::Random,
+        RotationOrder::ShuffleBag,
+        RotationOrder::LeastRecentlyShown,
+    ];
+
+    /// Gets the strategy implementing this order.
+    pub fn strategy(self) -> &'static dyn SelectionStrategy {
+        match self {
+            RotationOrder::Sequential => &Sequential,
+            RotationOrder::Random => &UniformRandom,
+            RotationOrder::ShuffleBag => &ShuffleBag,
+            RotationOrder::LeastRecentlyShown => &LeastRecentlyShown,
+        }
+    }
+
+    /// Gets human-readable name.
+    pub fn label(self) -> &'static str {
+        match self {
+            RotationOrder::Sequential => "Sequential",
+            RotationOrder::Random => "Random",
+            RotationOrder::ShuffleBag => "Shuffle",
+            RotationOrder::LeastRecentlyShown => "Least Recently Shown",
+        }
+    }
+}
+
+/// How wallpapers are matched to the aspect ratio of monitors in rotation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AspectMode {
+    /// Every wallpaper is a candidate.
+    #[default]
+    Off,
+
+    /// Suitable wallpapers are used, or all of them if there is none.
+    Prefer,
+
+    /// Only suitable wallpapers are used.
+    Require,
+}
+
+impl AspectMode {
+    /// All modes, for choices in UI.
+    pub const ALL: &'static [AspectMode] =
+        &[AspectMode::Off, AspectMode::Prefer, AspectMode::Require];
+
+    /// Gets human-readable name.
+    pub fn label(self) -> &'static str {
+        match self {
+            AspectMode::Off => "Off",
+            AspectMode::Prefer => "Prefer",
+            AspectMode::Require => "Require",
+        }
+    }
+}
+
+/// Aspect ratio matching shared by all monitors.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AspectMatching {
+    pub mode: AspectMode,
+
+    /// Allowed difference of aspect ratios, in percent.
+    pub tolerance: f32,
+}
+
+impl Default for AspectMatching {
+    fn default() -> AspectMatching {
+        AspectMatching {
+            mode: AspectMode::default(),
+            tolerance: 20.0,
+        }
+    }
+}
+
+impl AspectMatching {
+    /// Checks whether an image of `aspect` ratio suits a monitor of `target` ratio.
+    /// Unknown ratio is regarded as suitable.
+    pub fn is_suitable(&self, aspect: Option<f32>, target: f32) -> bool {
+        match aspect {
+            Some(a) => (a / target).ln().abs() <= (1.0 + self.tolerance / 100.0).ln(),
+            None => true,
+        }
+    }
+
+    /// Narrows `candidates` by their aspect ratios according to the mode.
+    pub fn filter(
+        &self,
+        candidates: Vec<usize>,
+        aspect_of: impl Fn(usize) -> Option<f32>,
+        target: f32,
+    ) -> Vec<usize> {
+        if self.mode == AspectMode::Off {
+            return candidates;
+        }
+
+        let suitable: Vec<_> = candidates
+            .iter()
+            .copied()
+            .filter(|&i| self.is_suitable(aspect_of(i), target))
+            .collect();
+        match self.mode {
+            AspectMode::Prefer if suitable.is_empty() => candidates,
+            _ => suitable,
+        }
+    }
+}
+
+/// Selection history of a monitor.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct History {
+    /// Remaining wallpapers in shuffle bag.
+    #[serde(default)]
+    bag: Vec<Uuid>,
+
+    /// UNIX timestamps in seconds when wallpapers were shown last.
+    #[serde(default)]
+    last_shown: BTreeMap<Uuid, i64>,
+}
+
+impl History {
+    /// Records that the wallpaper has been shown.
+    pub fn record(&mut self, id: Uuid, at: i64) {
+        self.last_shown.insert(id, at);
+    }
+
+    /// Gets the time when the wallpaper was shown last.
+    pub fn last_shown(&self, id: Uuid) -> Option<i64> {
+        self.last_shown.get(&id).copied()
+    }
+
+    /// Forgets wallpapers for which `keep` returns false, like removed ones.
+    pub fn retain(&mut self, keep: impl Fn(Uuid) -> bool) {
+        self.bag.retain(|id| keep(*id));
+        self.last_shown.retain(|id, _| keep(*id));
+    }
+}
+
+/// Selects next wallpaper in rotation.
+pub trait SelectionStrategy: Send + Sync {
+    /// Selects next one from `candidates` and returns its index.
+    /// `current` is the wallpaper currently shown.
+    fn select(
+        &self,
+        candidates: &[Uuid],
+        current: Option<Uuid>,
+        history: &mut History,
+        rng: &mut dyn RngCore,
+    ) -> Option<usize>;
+}
+
+/// Strategy for `RotationOrder::Sequential`.
+pub struct Sequential;
+
+impl SelectionStrategy for Sequential {
+    fn select(
+        &self,
+        candidates: &[Uuid],
+        current: Option<Uuid>,
+        _history: &mut History,
+        _rng: &mut dyn RngCore,
+    ) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        match candidates.iter().position(|c| Some(*c) == current) {
+            Some(i) => Some((i + 1) % candidates.len()),
+            None => Some(0),
+        }
+    }
+}
+
+/// Strategy for `RotationOrder::Random`.
+pub struct UniformRandom;
+
+impl SelectionStrategy for UniformRandom {
+    fn select(
+        &self,
+        candidates: &[Uuid],
+        current: Option<Uuid>,
+        _history: &mut History,
+        rng: &mut dyn RngCore,
+    ) -> Option<usize> {
+        let others: Vec<_> = (0..candidates.len())
+            .filter(|&i| Some(candidates[i]) != current)
+            .collect();
+        match others.len() {
+            0 if candidates.is_empty() => None,
+            0 => Some(0),
+            n => Some(others[rng.gen_range(0..n)]),
+        }
+    }
+}
+
+/// Strategy for `RotationOrder::ShuffleBag`.
+pub struct ShuffleBag;
+
+impl SelectionStrategy for ShuffleBag {
+    fn select(
+        &self,
+        candidates: &[Uuid],
+        current: Option<Uuid>,
+        history: &mut History,
+        rng: &mut dyn RngCore,
+    ) -> Option<usize> {
+        if candidates.is_empty() {
+            return None;
+        }
+
+        history.bag.retain(|id| candidates.contains(id));
+        if history.bag.is_empty() {
+            history.bag = candidates.to_vec();
+            history.bag.shuffle(rng);
+
+            // Avoid repeating across refills
+            let last = history.bag.len() - 1;
+            if history.bag.len() > 1 && Some(history.bag[last]) == current {
+                history.bag.swap(0, last);
+            }
+        }
+
+        let id = history.bag.pop()?;
+        candidates.iter().position(|c| *c == id)
+    }
+}
+
+/// Strategy for `RotationOrder::LeastRecentlyShown`.
+pub struct LeastRecentlyShown;
+
+impl SelectionStrategy for LeastRecentlyShown {
+    fn select(
+        &self,
+        candidates: &[Uuid],
+        _current: Option<Uuid>,
+        history: &mut History,
+        _rng: &mut dyn RngCore,
+    ) -> Option<usize> {
+        // Never shown ones come first, then ties are broken by list order
+        (0..candidates.len()).min_by_key(|&i| history.last_shown(candidates[i]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn candidates(count: usize) -> Vec<Uuid> {
+        (0..count).map(|_| Uuid::new_v4()).collect()
+    }
+
+    /// Runs `strategy` for `count` times, recording each selection.
+    fn run(order: RotationOrder, candidates: &[Uuid], count: usize, seed: u64) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut history = History::default();
+        let mut current = None;
+        let mut selected = vec![];
+        for at in 0..count {
+            let index = order
+                .strategy()
+                .select(candidates, current, &mut history, &mut rng)
+                .expect("Should select");
+            history.record(candidates[index], at as i64);
+            current = Some(candidates[index]);
+            selected.push(index);
+        }
+        selected
+    }
+
+    #[test]
+    fn sequential_wraps() {
+        let ids = candidates(3);
+        assert_eq!(
+            run(RotationOrder::Sequential, &ids, 5, 0),
+            vec![0, 1, 2, 0, 1]
+        );
+    }
+
+    #[test]
+    fn random_is_deterministic_and_never_repeats() {
+        let ids = candidates(4);
+        let selected = run(RotationOrder::Random, &ids, 64, 42);
+        assert_eq!(selected, run(RotationOrder::Random, &ids, 64, 42));
+        assert!(selected.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn shuffle_bag_shows_all_before_repeat() {
+        let ids = candidates(5);
+        let selected = run(RotationOrder::ShuffleBag, &ids, 20, 7);
+        for round in selected.chunks(5) {
+            let unique: HashSet<_> = round.iter().collect();
+            assert_eq!(unique.len(), 5);
+        }
+        assert!(selected.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn least_recently_shown_prefers_oldest() {
+        let ids = candidates(3);
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut history = History::default();
+        history.record(ids[1], 10);
+        history.record(ids[0], 20);
+        history.record(ids[2], 30);
+        history.record(ids[1], 40);
+
+        let strategy = RotationOrder::LeastRecentlyShown.strategy();
+        let selected = strategy.select(&ids, Some(ids[1]), &mut history, &mut rng);
+        assert_eq!(selected, Some(0));
+
+        let new_id = Uuid::new_v4();
+        let with_new = [ids[0], ids[1], ids[2], new_id];
+        let selected = strategy.select(&with_new, Some(ids[1]), &mut history, &mut rng);
+        assert_eq!(selected, Some(3));
+    }
+
+    #[test]
+    fn least_recently_shown_covers_large_library() {
+        let ids = candidates(1000);
+        let selected = run(RotationOrder::LeastRecentlyShown, &ids, 2000, 0);
+        assert_eq!(selected[..1000], (0..1000).collect::<Vec<_>>());
+        assert_eq!(selected[1000..], selected[..1000]);
+
+        let mut history = History::default();
+        history.record(ids[0], 0);
+        history.record(ids[1], 1);
+        history.retain(|id| id != ids[0]);
+        assert_eq!(history.last_shown(ids[0]), None);
+        assert_eq!(history.last_shown(ids[1]), Some(1));
+    }
+
+    #[test]
+    fn aspect_prefer_falls_back() {
+        let aspects = [Some(16.0 / 9.0), Some(9.0 / 16.0), None, Some(3.0 / 4.0)];
+        let aspect_of = |i: usize| aspects[i];
+        let mut matching = AspectMatching {
+            mode: AspectMode::Prefer,
+            tolerance: 40.0,
+        };
+
+        assert_eq!(
+            matching.filter(vec![0, 1, 2, 3], aspect_of, 9.0 / 16.0),
+            [1, 2, 3]
+        );
+        assert_eq!(matching.filter(vec![0], aspect_of, 9.0 / 16.0), [0]);
+
+        matching.mode = AspectMode::Require;
+        matching.tolerance = 10.0;
+        assert_eq!(matching.filter(vec![0, 1, 3], aspect_of, 9.0 / 16.0), [1]);
+        assert!(matching.filter(vec![0], aspect_of, 9.0 / 16.0).is_empty());
+    }
+}
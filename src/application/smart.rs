@@ -0,0 +1,132 @@
+//! Provides saved queries which define members of smart playlists.
+
+use crate::application::{TagQuery, Wallpaper};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Seconds in a day, for `added_within_days`.
+const DAY_SECONDS: i64 = 24 * 60 * 60;
+
+/// Conditions over wallpaper properties, all of which are required.
+/// Unset conditions match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartQuery {
+    /// Tags and filenames.
+    pub tags: TagQuery,
+
+    /// Inclusive range of the aspect ratio of oriented image.
+    pub aspect_ratio: Option<[f32; 2]>,
+
+    /// Minimum width and height of the image.
+    pub min_resolution: Option<[u32; 2]>,
+
+    /// Minimum rating, 0 to 5.
+    pub min_rating: u8,
+
+    /// UUID of `FolderSource` which wallpapers are found in.
+    pub source: Option<Uuid>,
+    pub added_within_days: Option<u32>,
+
+    /// Inclusive range of EXIF date taken, as prefixes of `YYYY-MM-DD` like `2021` or `2021-06`.
+    pub taken_from: Option<String>,
+    pub taken_until: Option<String>,
+
+    /// Only wallpapers which have never been applied.
+    pub never_shown: bool,
+}
+
+impl SmartQuery {
+    /// Checks whether the wallpaper matches. `now` is UNIX time in seconds.
+    pub fn matches(&self, wallpaper: &Wallpaper, now: i64) -> bool {
+        if !wallpaper.matches(&self.tags) || wallpaper.rating() < self.min_rating {
+            return false;
+        }
+        if let Some([min, max]) = self.aspect_ratio {
+            match wallpaper.aspect_ratio() {
+                Some(r) if min <= r && r <= max => (),
+                _ => return false,
+            }
+        }
+        if let Some([width, height]) = self.min_resolution {
+            match wallpaper.image_size() {
+                Some(s) if s.x >= width && s.y >= height => (),
+                _ => return false,
+            }
+        }
+        if self.source.is_some() && wallpaper.source() != self.source {
+            return false;
+        }
+        if let Some(days) = self.added_within_days {
+            match wallpaper.added_at() {
+                Some(t) if now - t <= days as i64 * DAY_SECONDS => (),
+                _ => return false,
+            }
+        }
+        if self.taken_from.is_some() || self.taken_until.is_some() {
+            let taken_at = match wallpaper.taken_at() {
+                Some(t) => t,
+                None => return false,
+            };
+            // Compared by the length of bounds, so that `2021` includes whole year
+            let prefix = |bound: &str| taken_at.get(..bound.len()).unwrap_or(taken_at).to_string();
+            if let Some(from) = &self.taken_from {
+                if prefix(from) < *from {
+                    return false;
+                }
+            }
+            if let Some(until) = &self.taken_until {
+                if prefix(until) > *until {
+                    return false;
+                }
+            }
+        }
+        !self.never_shown || wallpaper.shown_at().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::Fitting;
+
+    use vek::Vec2;
+
+    #[test]
+    fn query_checks_properties() {
+        let mut wallpaper = Wallpaper::new("C:\\Photos\\lake.jpg", Fitting::Cover);
+        wallpaper.set_image_size(Vec2::new(3840, 2160));
+        wallpaper.set_rating(4);
+        wallpaper.set_taken_at(Some("2021-06-12".into()));
+        let now = wallpaper.added_at().expect("Should be recorded") + 3 * DAY_SECONDS;
+
+        let query = SmartQuery {
+            tags: "lake".parse().expect("Should parse"),
+            aspect_ratio: Some([1.7, 1.8]),
+            min_resolution: Some([2560, 1440]),
+            min_rating: 3,
+            added_within_days: Some(7),
+            taken_from: Some("2021".into()),
+            taken_until: Some("2021-06".into()),
+            never_shown: true,
+            ..Default::default()
+        };
+        assert!(query.matches(&wallpaper, now));
+        assert!(SmartQuery::default().matches(&wallpaper, now));
+
+        assert!(!query.matches(&wallpaper, now + 7 * DAY_SECONDS));
+        let query = SmartQuery {
+            taken_until: Some("2021-05".into()),
+            ..Default::default()
+        };
+        assert!(!query.matches(&wallpaper, now));
+
+        wallpaper.set_shown_at(now);
+        let query = SmartQuery {
+            never_shown: true,
+            ..Default::default()
+        };
+        assert!(!query.matches(&wallpaper, now));
+    }
+}
@@ -0,0 +1,359 @@
+//! Provides wallpaper switching by sunrise and sunset.
+//! Solar position is calculated locally with NOAA's algorithm.
+
+use crate::application::Wallpaper;
+
+use serde::{Deserialize, Serialize};
+use time::{Date, Duration as TimeDuration, OffsetDateTime, UtcOffset};
+use uuid::Uuid;
+
+/// Sun elevation in degrees at sunrise and sunset, including refraction.
+const SUNRISE_ELEVATION: f64 = -0.833;
+
+/// Twilight phase treated as dusk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Twilight {
+    /// No dusk phase.
+    #[default]
+    None,
+
+    /// Until the sun is 6 degrees below horizon.
+    Civil,
+
+    /// Until the sun is 12 degrees below horizon.
+    Nautical,
+}
+
+impl Twilight {
+    /// All twilight options, for choices in UI.
+    pub const ALL: &'static [Twilight] = &[Twilight::None, Twilight::Civil, Twilight::Nautical];
+
+    /// Gets sun elevation where this twilight ends.
+    pub fn elevation(self) -> Option<f64> {
+        match self {
+            Twilight::None => None,
+            Twilight::Civil => Some(-6.0),
+            Twilight::Nautical => Some(-12.0),
+        }
+    }
+
+    /// Gets human-readable name.
+    pub fn label(self) -> &'static str {
+        match self {
+            Twilight::None => "None",
+            Twilight::Civil => "Civil",
+            Twilight::Nautical => "Nautical",
+        }
+    }
+}
+
+/// Phase of the day determined by sun elevation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolarPhase {
+    Day,
+
+    /// Twilight in both morning and evening.
+    Dusk,
+
+    Night,
+}
+
+impl SolarPhase {
+    /// Gets human-readable name.
+    pub fn label(self) -> &'static str {
+        match self {
+            SolarPhase::Day => "Day",
+            SolarPhase::Dusk => "Dusk",
+            SolarPhase::Night => "Night",
+        }
+    }
+}
+
+/// Settings of sun-driven wallpaper switching.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SolarSettings {
+    /// Whether this is evaluated.
+    pub enabled: bool,
+
+    /// Latitude in degrees, north positive.
+    pub latitude: f64,
+
+    /// Longitude in degrees, east positive.
+    pub longitude: f64,
+
+    /// Twilight phase treated as dusk.
+    #[serde(default)]
+    pub twilight: Twilight,
+
+    /// IDs of wallpapers shown in daytime.
+    #[serde(default)]
+    pub day: Vec<Uuid>,
+
+    /// IDs of wallpapers shown in twilight. Night ones are used if empty.
+    #[serde(default)]
+    pub dusk: Vec<Uuid>,
+
+    /// IDs of wallpapers shown in nighttime.
+    #[serde(default)]
+    pub night: Vec<Uuid>,
+}
+
+impl SolarSettings {
+    /// Determines the phase at the time.
+    pub fn phase(&self, now: OffsetDateTime) -> SolarPhase {
+        let elevation = solar_elevation(now, self.latitude, self.longitude);
+        if elevation >= SUNRISE_ELEVATION {
+            SolarPhase::Day
+        } else if self.twilight.elevation().map(|e| elevation >= e) == Some(true) {
+            SolarPhase::Dusk
+        } else {
+            SolarPhase::Night
+        }
+    }
+
+    /// Gets indices of wallpapers for the phase.
+    /// Empty means nothing is configured for it.
+    pub fn select_wallpapers(&self, phase: SolarPhase, wallpapers: &[Wallpaper]) -> Vec<usize> {
+        let ids = match phase {
+            SolarPhase::Day => &self.day,
+            SolarPhase::Dusk if !self.dusk.is_empty() => &self.dusk,
+            SolarPhase::Dusk | SolarPhase::Night => &self.night,
+        };
+        (0..wallpapers.len())
+            .filter(|&i| ids.contains(&wallpapers[i].id()))
+            .collect()
+    }
+
+    /// Calculates sunrise, sunset and twilight of the date in `offset`.
+    pub fn events(&self, date: Date, offset: UtcOffset) -> SolarEvents {
+        let sun = sun_crossings(
+            date,
+            offset,
+            self.latitude,
+            self.longitude,
+            SUNRISE_ELEVATION,
+        );
+        let twilight = self
+            .twilight
+            .elevation()
+            .map(|e| sun_crossings(date, offset, self.latitude, self.longitude, e));
+        SolarEvents { sun, twilight }
+    }
+}
+
+/// Times when the sun crosses an elevation in a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Crossing {
+    /// Rising and setting time.
+    Times(OffsetDateTime, OffsetDateTime),
+
+    /// The sun stays above all day.
+    AlwaysAbove,
+
+    /// The sun stays below all day.
+    AlwaysBelow,
+}
+
+/// Sun events in a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolarEvents {
+    /// Sunrise and sunset.
+    pub sun: Crossing,
+
+    /// Dawn and dusk, if twilight is configured.
+    pub twilight: Option<Crossing>,
+}
+
+/// Declination and equation of time of the sun.
+struct SolarPosition {
+    /// Declination in radians.
+    declination: f64,
+
+    /// Equation of time in minutes.
+    equation_of_time: f64,
+}
+
+impl SolarPosition {
+    fn at(time: OffsetDateTime) -> SolarPosition {
+        let julian_day = time.unix_timestamp() as f64 / 86400.0 + 2440587.5;
+        let jc = (julian_day - 2451545.0) / 36525.0;
+
+        let mean_longitude = (280.46646 + jc * (36000.76983 + jc * 0.0003032)).rem_euclid(360.0);
+        let mean_anomaly = 357.52911 + jc * (35999.05029 - 0.0001537 * jc);
+        let eccentricity = 0.016708634 - jc * (0.000042037 + 0.0000001267 * jc);
+        let center = mean_anomaly.to_radians().sin() * (1.914602 - jc * (0.004817 + 0.000014 * jc))
+            + (2.0 * mean_anomaly).to_radians().sin() * (0.019993 - 0.000101 * jc)
+            + (3.0 * mean_anomaly).to_radians().sin() * 0.000289;
+        let omega = (125.04 - 1934.136 * jc).to_radians();
+        let apparent_longitude = mean_longitude + center - 0.00569 - 0.00478 * omega.sin();
+        let mean_obliquity =
+            23.0 + (26.0 + (21.448 - jc * (46.815 + jc * (0.00059 - jc * 0.001813))) / 60.0) / 60.0;
+        let obliquity = (mean_obliquity + 0.00256 * omega.cos()).to_radians();
+
+        let declination = (obliquity.sin() * apparent_longitude.to_radians().sin()).asin();
+
+        let y = (obliquity / 2.0).tan().powi(2);
+        let l0 = mean_longitude.to_radians();
+        let m = mean_anomaly.to_radians();
+        let equation_of_time = 4.0
+            * (y * (2.0 * l0).sin() - 2.0 * eccentricity * m.sin()
+                + 4.0 * eccentricity * y * m.sin() * (2.0 * l0).cos()
+                - 0.5 * y * y * (4.0 * l0).sin()
+                - 1.25 * eccentricity * eccentricity * (2.0 * m).sin())
+            .to_degrees();
+
+        SolarPosition {
+            declination,
+            equation_of_time,
+        }
+    }
+}
+
+/// Calculates sun elevation in degrees, without refraction.
+pub fn solar_elevation(time: OffsetDateTime, latitude: f64, longitude: f64) -> f64 {
+    let position = SolarPosition::at(time);
+    let utc = time.to_offset(UtcOffset::UTC);
+    let minutes = utc.hour() as f64 * 60.0 + utc.minute() as f64 + utc.second() as f64 / 60.0;
+    let true_solar_time =
+        (minutes + position.equation_of_time + 4.0 * longitude).rem_euclid(1440.0);
+    let hour_angle = (true_solar_time / 4.0 - 180.0).to_radians();
+
+    let latitude = latitude.to_radians();
+    let cos_zenith = latitude.sin() * position.declination.sin()
+        + latitude.cos() * position.declination.cos() * hour_angle.cos();
+    90.0 - cos_zenith.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// Calculates when the sun crosses `elevation` at the date in `offset`.
+pub fn sun_crossings(
+    date: Date,
+    offset: UtcOffset,
+    latitude: f64,
+    longitude: f64,
+    elevation: f64,
+) -> Crossing {
+    let midnight = date.midnight().assume_utc();
+
+    // Solar position is evaluated at approximate solar noon, then refined once
+    let mut noon = midnight + TimeDuration::seconds(((720.0 - 4.0 * longitude) * 60.0) as i64);
+    let mut half_day = 0.0;
+    for _ in 0..2 {
+        let position = SolarPosition::at(noon);
+        let lat = latitude.to_radians();
+        let cos_hour_angle = ((90.0 - elevation).to_radians().cos()
+            - lat.sin() * position.declination.sin())
+            / (lat.cos() * position.declination.cos());
+        if cos_hour_angle > 1.0 {
+            return Crossing::AlwaysBelow;
+        } else if cos_hour_angle < -1.0 {
+            return Crossing::AlwaysAbove;
+        }
+        half_day = cos_hour_angle.acos().to_degrees() * 4.0;
+
+        let noon_minutes = 720.0 - 4.0 * longitude - position.equation_of_time;
+        noon = midnight + TimeDuration::seconds((noon_minutes * 60.0) as i64);
+    }
+
+    let half_day = TimeDuration::seconds((half_day * 60.0) as i64);
+    Crossing::Times(
+        (noon - half_day).to_offset(offset),
+        (noon + half_day).to_offset(offset),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use time::{Month, Time};
+
+    const TOKYO: (f64, f64) = (35.6895, 139.6917);
+    const LONDON: (f64, f64) = (51.5074, -0.1278);
+    const TROMSO: (f64, f64) = (69.6492, 18.9553);
+
+    fn date(month: Month, day: u8) -> Date {
+        Date::from_calendar_date(2022, month, day).expect("Should be valid")
+    }
+
+    fn assert_near(actual: OffsetDateTime, hour: u8, minute: u8) {
+        let expected =
+            actual.replace_time(Time::from_hms(hour, minute, 0).expect("Should be valid"));
+        let difference = (actual - expected).whole_minutes().abs();
+        assert!(difference <= 2, "{actual} is not near {hour}:{minute:02}");
+    }
+
+    fn times(crossing: Crossing) -> (OffsetDateTime, OffsetDateTime) {
+        match crossing {
+            Crossing::Times(rise, set) => (rise, set),
+            _ => panic!("Should rise and set: {crossing:?}"),
+        }
+    }
+
+    #[test]
+    fn sunrise_and_sunset() {
+        let jst = UtcOffset::from_hms(9, 0, 0).expect("Should be valid");
+        let (rise, set) = times(sun_crossings(
+            date(Month::June, 21),
+            jst,
+            TOKYO.0,
+            TOKYO.1,
+            SUNRISE_ELEVATION,
+        ));
+        assert_near(rise, 4, 25);
+        assert_near(set, 19, 0);
+        assert_eq!(rise.date(), date(Month::June, 21));
+
+        let (rise, set) = times(sun_crossings(
+            date(Month::December, 21),
+            UtcOffset::UTC,
+            LONDON.0,
+            LONDON.1,
+            SUNRISE_ELEVATION,
+        ));
+        assert_near(rise, 8, 4);
+        assert_near(set, 15, 54);
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        let crossing = |month| {
+            sun_crossings(
+                date(month, 21),
+                UtcOffset::UTC,
+                TROMSO.0,
+                TROMSO.1,
+                SUNRISE_ELEVATION,
+            )
+        };
+        assert_eq!(crossing(Month::June), Crossing::AlwaysAbove);
+        assert_eq!(crossing(Month::December), Crossing::AlwaysBelow);
+    }
+
+    #[test]
+    fn phases_with_twilight() {
+        let at = |hour, minute| {
+            date(Month::December, 21)
+                .with_hms(hour, minute, 0)
+                .expect("Should be valid")
+                .assume_utc()
+        };
+        let mut settings = SolarSettings {
+            enabled: true,
+            latitude: LONDON.0,
+            longitude: LONDON.1,
+            twilight: Twilight::None,
+            ..Default::default()
+        };
+        assert_eq!(settings.phase(at(12, 0)), SolarPhase::Day);
+        assert_eq!(settings.phase(at(16, 10)), SolarPhase::Night);
+
+        settings.twilight = Twilight::Civil;
+        assert_eq!(settings.phase(at(7, 50)), SolarPhase::Dusk);
+        assert_eq!(settings.phase(at(16, 10)), SolarPhase::Dusk);
+        assert_eq!(settings.phase(at(17, 0)), SolarPhase::Night);
+
+        settings.twilight = Twilight::Nautical;
+        assert_eq!(settings.phase(at(17, 0)), SolarPhase::Dusk);
+        assert_eq!(settings.phase(at(18, 0)), SolarPhase::Night);
+    }
+}
@@ -0,0 +1,250 @@
+//! Provides folders scanned and watched for wallpapers.
+
+use crate::application::{overlay::read_taken_at, Fitting, Wallpaper};
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use vek::Vec2;
+use walkdir::WalkDir;
+
+/// Extensions of image files collected from folders.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp"];
+
+/// Folder whose images are added to the library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FolderSource {
+    uuid: Uuid,
+    path: String,
+
+    /// Glob patterns of paths relative to the folder. All images are included if empty.
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Glob patterns of paths relative to the folder, taking precedence over `include`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl FolderSource {
+    /// Constructs new instance with generated UUID.
+    pub fn new(
+        path: impl Into<String>,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    ) -> FolderSource {
+        FolderSource {
+            uuid: Uuid::new_v4(),
+            path: path.into(),
+            include,
+            exclude,
+        }
+    }
+
+    /// Gets assigned UUID.
+    pub fn id(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// Gets the folder path.
+    pub fn path(&self) -> &Path {
+        Path::new(&self.path)
+    }
+
+    /// Compiles glob patterns.
+    pub fn matcher(&self) -> Result<SourceMatcher> {
+        let include = if self.include.is_empty() {
+            None
+        } else {
+            Some(build_globs(&self.include)?)
+        };
+        Ok(SourceMatcher {
+            root: self.path().to_path_buf(),
+            include,
+            exclude: build_globs(&self.exclude)?,
+        })
+    }
+
+    /// Lists image files in the folder and its subfolders matching glob patterns.
+    /// Fails if the folder itself cannot be read, so that it is not taken as empty.
+    pub fn scan(&self) -> Result<Vec<PathBuf>> {
+        let matcher = self.matcher()?;
+        if !self.path().is_dir() {
+            bail!("Not a folder: {}", self.path);
+        }
+        let mut files = vec![];
+        for entry in WalkDir::new(self.path()).follow_links(true) {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) if e.depth() == 0 => return Err(e.into()),
+                Err(e) => {
+                    warn!("Failed to scan {}: {e}", self.path);
+                    continue;
+                }
+            };
+            if entry.file_type().is_file() && matcher.matches(entry.path()) {
+                files.push(entry.into_path());
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+/// Folder source taken out of the model, to be scanned without holding the lock.
+pub struct SourceScan {
+    pub source: FolderSource,
+
+    /// Filenames already in the library, which are not read again.
+    pub known: HashSet<String>,
+}
+
+/// Result of `SourceScan`.
+pub struct ScannedSource {
+    pub source_id: Uuid,
+
+    /// All image files found in the folder.
+    pub found: HashSet<String>,
+
+    /// Wallpapers of newly found images, in order of their paths.
+    pub added: Vec<Wallpaper>,
+}
+
+impl SourceScan {
+    /// Scans the folder, and reads size and date taken of new images.
+    /// Unreadable images are skipped.
+    pub fn run(self) -> Result<ScannedSource> {
+        let files = self.source.scan()?;
+        let mut added = vec![];
+        for path in &files {
+            let filename = path.to_string_lossy();
+            if self.known.contains(filename.as_ref()) {
+                continue;
+            }
+            let (width, height) = match image::image_dimensions(path) {
+                Ok(d) => d,
+                Err(e) => {
+                    warn!("Skipping {filename}: {e}");
+                    continue;
+                }
+            };
+            let mut wallpaper =
+                Wallpaper::new_from_source(filename, Fitting::Cover, self.source.id());
+            wallpaper.set_image_size(Vec2::new(width, height));
+            wallpaper.set_taken_at(read_taken_at(path));
+            added.push(wallpaper);
+        }
+
+        Ok(ScannedSource {
+            source_id: self.source.id(),
+            found: files
+                .into_iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            added,
+        })
+    }
+}
+
+/// Compiled glob patterns of a `FolderSource`.
+pub struct SourceMatcher {
+    root: PathBuf,
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl SourceMatcher {
+    /// Checks whether the path is an image in the folder matching glob patterns.
+    pub fn matches(&self, path: &Path) -> bool {
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
+        let is_image = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+            .unwrap_or(false);
+        let included = match &self.include {
+            Some(globs) => globs.is_match(relative),
+            None => true,
+        };
+        is_image && included && !self.exclude.is_match(relative)
+    }
+}
+
+/// Builds a set of glob patterns.
+fn build_globs(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{create_dir_all, write};
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn scan_filters_by_globs() {
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        for name in [
+            "a.png",
+            "b.txt",
+            "nested/c.JPG",
+            "nested/raw/d.png",
+            "e.bmp",
+        ] {
+            let path = directory.path().join(name);
+            create_dir_all(path.parent().expect("Should have parent")).expect("Failed to create");
+            write(&path, []).expect("Failed to write");
+        }
+
+        let all = FolderSource::new(directory.path().to_string_lossy(), vec![], vec![]);
+        assert_eq!(all.scan().expect("Should scan").len(), 4);
+
+        let filtered = FolderSource::new(
+            directory.path().to_string_lossy(),
+            vec!["nested/**".into(), "*.png".into()],
+            vec!["**/raw/**".into()],
+        );
+        let relative: Vec<_> = filtered
+            .scan()
+            .expect("Should scan")
+            .iter()
+            .map(|p| {
+                let relative = p.strip_prefix(directory.path()).expect("Should be inside");
+                relative.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(relative, ["a.png", "nested/c.JPG"]);
+    }
+
+    #[test]
+    fn scan_fails_without_folder() {
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        let source = FolderSource::new(
+            directory.path().join("missing").to_string_lossy(),
+            vec![],
+            vec![],
+        );
+        assert!(source.scan().is_err());
+
+        let file = directory.path().join("a.png");
+        write(&file, []).expect("Failed to write");
+        let source = FolderSource::new(file.to_string_lossy(), vec![], vec![]);
+        assert!(source.scan().is_err());
+    }
+}
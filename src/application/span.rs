@@ -0,0 +1,309 @@
+//! Provides physical arrangement of monitors for spanned wallpapers.
+
+use crate::backend::Monitor;
+
+use serde::{Deserialize, Serialize};
+use vek::{Aabr, Vec2};
+
+/// Millimeters per pixel assumed when physical size is unknown, as 96 DPI.
+const DEFAULT_MM_PER_PIXEL: f64 = 25.4 / 96.0;
+
+/// Unit of bezel widths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BezelUnit {
+    #[default]
+    Millimeters,
+
+    /// Pixels of the monitor itself.
+    Pixels,
+}
+
+impl BezelUnit {
+    /// All units, for choices in UI.
+    pub const ALL: &'static [BezelUnit] = &[BezelUnit::Millimeters, BezelUnit::Pixels];
+
+    /// Gets abbreviated name.
+    pub fn label(self) -> &'static str {
+        match self {
+            BezelUnit::Millimeters => "mm",
+            BezelUnit::Pixels => "px",
+        }
+    }
+}
+
+/// Hidden border around the visible area of a monitor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bezel {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+
+    #[serde(default)]
+    pub unit: BezelUnit,
+}
+
+/// Size of the visible area in millimeters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PhysicalSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PhysicalSize {
+    /// Gets the size assumed from pixels as 96 DPI.
+    pub fn assumed(pixels: Vec2<i32>) -> PhysicalSize {
+        let size = pixels.as_::<f64>() * DEFAULT_MM_PER_PIXEL;
+        PhysicalSize {
+            width: size.x,
+            height: size.y,
+        }
+    }
+}
+
+/// Physical properties of a monitor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonitorLayout {
+    #[serde(default)]
+    pub bezel: Bezel,
+
+    /// Overrides the size assumed from 96 DPI.
+    #[serde(default)]
+    pub physical_size: Option<PhysicalSize>,
+}
+
+impl MonitorLayout {
+    /// Gets millimeters per pixel of the monitor.
+    fn mm_per_pixel(&self, monitor: &Monitor) -> Vec2<f64> {
+        match self.physical_size {
+            Some(s) => {
+                Vec2::new(s.width, s.height) / monitor.size().as_::<f64>().map(|x| x.max(1.0))
+            }
+            None => Vec2::broadcast(DEFAULT_MM_PER_PIXEL),
+        }
+    }
+
+    /// Gets bezel widths in millimeters, as top-left and bottom-right.
+    fn bezel_mm(&self, mm_per_pixel: Vec2<f64>) -> (Vec2<f64>, Vec2<f64>) {
+        let top_left = Vec2::new(self.bezel.left, self.bezel.top);
+        let bottom_right = Vec2::new(self.bezel.right, self.bezel.bottom);
+        match self.bezel.unit {
+            BezelUnit::Millimeters => (top_left, bottom_right),
+            BezelUnit::Pixels => (top_left * mm_per_pixel, bottom_right * mm_per_pixel),
+        }
+    }
+}
+
+/// Physical arrangement of a monitor in millimeters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Visible area.
+    pub screen: Aabr<f64>,
+
+    /// Whole monitor including bezel.
+    pub body: Aabr<f64>,
+}
+
+/// Arranges monitors physically.
+/// Monitors sharing an edge in the virtual desktop are placed side by side with bezels between,
+/// and the rest keep their positions relative to placed one.
+pub fn arrange(monitors: &[(&Monitor, MonitorLayout)]) -> Vec<Placement> {
+    let scales: Vec<_> = monitors.iter().map(|(m, l)| l.mm_per_pixel(m)).collect();
+    let bezels: Vec<_> = monitors
+        .iter()
+        .zip(&scales)
+        .map(|((_, l), s)| l.bezel_mm(*s))
+        .collect();
+    let sizes: Vec<_> = monitors
+        .iter()
+        .zip(&scales)
+        .map(|((m, _), s)| m.size().as_::<f64>() * *s)
+        .collect();
+
+    // Top-left of visible area for each monitor
+    let mut origins: Vec<Option<Vec2<f64>>> = vec![None; monitors.len()];
+    while let Some(root) = origins.iter().position(|o| o.is_none()) {
+        origins[root] = Some(match origins.iter().position(|o| o.is_some()) {
+            Some(placed) => {
+                let offset = monitors[root].0.position() - monitors[placed].0.position();
+                origins[placed].expect("Should be placed") + offset.as_::<f64>() * scales[placed]
+            }
+            None => monitors[root].0.position().as_::<f64>() * scales[root],
+        });
+
+        // Spreads to neighbors until no more is found
+        let mut found = true;
+        while found {
+            found = false;
+            for next in 0..monitors.len() {
+                if origins[next].is_some() {
+                    continue;
+                }
+                let placed = (0..monitors.len()).find_map(|i| {
+                    let origin = origins[i]?;
+                    let edge = neighbor_edge(monitors[i].0, monitors[next].0)?;
+                    let along = (monitors[next].0.position() - monitors[i].0.position())
+                        .as_::<f64>()
+                        * scales[i];
+                    let position = match edge {
+                        Edge::Right => Vec2::new(
+                            origin.x + sizes[i].x + bezels[i].1.x + bezels[next].0.x,
+                            origin.y + along.y,
+                        ),
+                        Edge::Left => Vec2::new(
+                            origin.x - bezels[i].0.x - bezels[next].1.x - sizes[next].x,
+                            origin.y + along.y,
+                        ),
+                        Edge::Bottom => Vec2::new(
+                            origin.x + along.x,
+                            origin.y + sizes[i].y + bezels[i].1.y + bezels[next].0.y,
+                        ),
+                        Edge::Top => Vec2::new(
+                            origin.x + along.x,
+                            origin.y - bezels[i].0.y - bezels[next].1.y - sizes[next].y,
+                        ),
+                    };
+                    Some(position)
+                });
+                if placed.is_some() {
+                    origins[next] = placed;
+                    found = true;
+                }
+            }
+        }
+    }
+
+    origins
+        .into_iter()
+        .enumerate()
+        .map(|(i, origin)| {
+            let origin = origin.expect("Should be placed");
+            let screen = Aabr {
+                min: origin,
+                max: origin + sizes[i],
+            };
+            let body = Aabr {
+                min: screen.min - bezels[i].0,
+                max: screen.max + bezels[i].1,
+            };
+            Placement { screen, body }
+        })
+        .collect()
+}
+
+/// Calculates the bounding box of areas.
+pub fn bounds(areas: impl IntoIterator<Item = Aabr<f64>>) -> Aabr<f64> {
+    areas
+        .into_iter()
+        .reduce(Aabr::union)
+        .unwrap_or_else(|| Aabr::new_empty(Vec2::zero()))
+}
+
+/// Side of a monitor where another one touches.
+enum Edge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+/// Finds the edge of `base` which `other` touches in the virtual desktop.
+fn neighbor_edge(base: &Monitor, other: &Monitor) -> Option<Edge> {
+    let (base_min, base_max) = (base.position(), base.position() + base.size());
+    let (other_min, other_max) = (other.position(), other.position() + other.size());
+    let overlaps_x = base_min.x < other_max.x && other_min.x < base_max.x;
+    let overlaps_y = base_min.y < other_max.y && other_min.y < base_max.y;
+
+    if overlaps_y && other_min.x == base_max.x {
+        Some(Edge::Right)
+    } else if overlaps_y && other_max.x == base_min.x {
+        Some(Edge::Left)
+    } else if overlaps_x && other_min.y == base_max.y {
+        Some(Edge::Bottom)
+    } else if overlaps_x && other_max.y == base_min.y {
+        Some(Edge::Top)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MonitorId;
+
+    fn monitor(id: &str, position: (i32, i32), size: (i32, i32)) -> Monitor {
+        Monitor::new(
+            MonitorId::new(id),
+            id,
+            Vec2::new(position.0, position.1),
+            Vec2::new(size.0, size.1),
+        )
+    }
+
+    fn layout(width: f64, height: f64, bezel: Bezel) -> MonitorLayout {
+        MonitorLayout {
+            bezel,
+            physical_size: Some(PhysicalSize { width, height }),
+        }
+    }
+
+    #[test]
+    fn default_keeps_pixel_arrangement() {
+        let left = monitor("left", (-100, 20), (100, 50));
+        let right = monitor("right", (0, 0), (200, 100));
+        let placements = arrange(&[
+            (&left, MonitorLayout::default()),
+            (&right, MonitorLayout::default()),
+        ]);
+
+        let ratio = placements[1].screen.min.x - placements[0].screen.min.x;
+        assert!((ratio - 100.0 * DEFAULT_MM_PER_PIXEL).abs() < 1e-9);
+        let offset = placements[0].screen.min.y - placements[1].screen.min.y;
+        assert!((offset - 20.0 * DEFAULT_MM_PER_PIXEL).abs() < 1e-9);
+        assert_eq!(placements[0].screen, placements[0].body);
+    }
+
+    #[test]
+    fn mixed_density_with_bezels() {
+        let low = monitor("low", (0, 0), (100, 100));
+        let high = monitor("high", (100, 0), (200, 200));
+        let below = monitor("below", (0, 100), (100, 50));
+        let placements = arrange(&[
+            (
+                &low,
+                layout(
+                    200.0,
+                    200.0,
+                    Bezel {
+                        right: 5.0,
+                        bottom: 2.0,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (
+                &high,
+                layout(
+                    200.0,
+                    200.0,
+                    Bezel {
+                        left: 5.0,
+                        unit: BezelUnit::Pixels,
+                        ..Default::default()
+                    },
+                ),
+            ),
+            (&below, layout(200.0, 100.0, Bezel::default())),
+        ]);
+
+        assert_eq!(placements[1].screen.min, Vec2::new(210.0, 0.0));
+        assert_eq!(placements[1].screen.max, Vec2::new(410.0, 200.0));
+        assert_eq!(placements[1].body.min, Vec2::new(205.0, 0.0));
+        assert_eq!(placements[2].screen.min, Vec2::new(0.0, 202.0));
+
+        let whole = bounds(placements.iter().map(|p| p.screen));
+        assert_eq!(whole.min, Vec2::new(0.0, 0.0));
+        assert_eq!(whole.max, Vec2::new(410.0, 302.0));
+    }
+}
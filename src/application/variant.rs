@@ -0,0 +1,82 @@
+//! Provides selection of image renditions in multiple resolutions.
+
+use serde::{Deserialize, Serialize};
+use vek::Vec2;
+
+/// Difference of aspect ratios regarded as the same, in natural log.
+const ASPECT_STEP: f32 = 0.05;
+
+/// Additional rendition of a wallpaper image.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageVariant {
+    pub filename: String,
+    pub size: [u32; 2],
+}
+
+impl ImageVariant {
+    /// Gets size of the image.
+    pub fn size(&self) -> Vec2<u32> {
+        Vec2::from(self.size)
+    }
+}
+
+/// Selects the rendition for `target` size from oriented `sizes` and returns its index.
+/// Ones covering the target without upscaling are preferred, by the closest aspect ratio
+/// and then the fewest pixels. Otherwise the least upscaled one is selected.
+/// Renditions of unknown size are selected only if no size is known.
+pub fn select_rendition(sizes: &[Option<Vec2<u32>>], target: Vec2<u32>) -> usize {
+    let target = target.as_::<f32>().map(|x| x.max(1.0));
+    let known = sizes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| Some((i, s.as_ref()?.as_::<f32>().map(|x| x.max(1.0)))));
+
+    let covering = known
+        .clone()
+        .filter(|(_, s)| s.x >= target.x && s.y >= target.y)
+        .min_by(|(_, a), (_, b)| {
+            let deviation = |s: &Vec2<f32>| {
+                let ratio = (s.x / s.y) / (target.x / target.y);
+                (ratio.ln().abs() / ASPECT_STEP).round()
+            };
+            deviation(a)
+                .total_cmp(&deviation(b))
+                .then((a.x * a.y).total_cmp(&(b.x * b.y)))
+        });
+    if let Some((i, _)) = covering {
+        return i;
+    }
+
+    known
+        .min_by(|(_, a), (_, b)| {
+            let upscale = |s: &Vec2<f32>| (target.x / s.x).max(target.y / s.y);
+            upscale(a).total_cmp(&upscale(b))
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_smallest_covering() {
+        let sizes = [
+            Some(Vec2::new(3840, 2160)),
+            Some(Vec2::new(1920, 1080)),
+            Some(Vec2::new(2560, 1440)),
+            Some(Vec2::new(5120, 2160)),
+        ];
+
+        assert_eq!(select_rendition(&sizes, Vec2::new(1920, 1080)), 1);
+        assert_eq!(select_rendition(&sizes, Vec2::new(2560, 1440)), 2);
+        assert_eq!(select_rendition(&sizes, Vec2::new(3440, 1440)), 3);
+        assert_eq!(select_rendition(&sizes, Vec2::new(7680, 4320)), 0);
+        assert_eq!(
+            select_rendition(&[None, sizes[1]], Vec2::new(3840, 2160)),
+            1
+        );
+        assert_eq!(select_rendition(&[None], Vec2::new(3840, 2160)), 0);
+    }
+}
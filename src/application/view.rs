@@ -1,50 +1,85 @@
 use crate::{
     application::{
         viewmodel::{
-            ApplicationViewModel, ApplicationViewModelEvent, MonitorCache, WallpaperCache,
+            ApplicationViewModel, ApplicationViewModelEvent, CollagePreview, CollectionCache,
+            CollectionOperation, MonitorCache, ScheduleOperation, WallpaperCache,
             WallpaperListOperation,
         },
-        Fitting,
+        Adjustments, AspectMatching, AspectMode, Background, BezelUnit, ClockTime, Collage,
+        CollageLayout, Day, Fitting, FocalPoint, FolderSource, MonitorLayout, Overlay,
+        OverlayAnchor, OverlayContent, PhysicalSize, RotationOrder, RotationStatus, ScheduleRule,
+        SmartQuery, SolarSettings, TagQuery, Twilight, WallpaperKind,
     },
     egui::{EguiEvent, EventProxy, View},
     mvvm::{Observable, Subscription},
-    windows::{MenuItem, NotifyIcon, PopupMenu},
+    render::{
+        adjust_colors, compose_collage, content_hash, cover_area, detect_focal_point, orient,
+        CollageCell,
+    },
 };
 
 use std::{
     collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::Result;
 use egui::{
-    menu, text::LayoutJob, Align, CentralPanel, Color32, ColorImage, Context, Direction, FontId,
-    Grid, Id, Layout, Pos2 as UiPos2, Rect, Response, RichText, ScrollArea, Sense, Stroke, Style,
-    TextFormat, TextStyle, TextureHandle, TopBottomPanel, Ui, Vec2 as UiVec2,
+    menu, text::LayoutJob, Align, Align2, Button, CentralPanel, CollapsingHeader, Color32,
+    ColorImage, ComboBox, Context, Direction, DragValue, FontId, Grid, Id, LayerId, Layout, Order,
+    Pos2 as UiPos2, Rect, Response, RichText, ScrollArea, Sense, Slider, Stroke, Style, TextEdit,
+    TextFormat, TextStyle, TextureHandle, TopBottomPanel, Ui, Vec2 as UiVec2, Window as UiWindow,
+};
+use epi::{backend::RepaintSignal, App, Frame, Storage};
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, ImageBuffer, RgbaImage,
 };
-use epi::{App, Frame, Storage};
-use image::{imageops::FilterType, DynamicImage, ImageBuffer};
 use log::{error, info};
 use parking_lot::Mutex;
 use tokio::task::spawn_blocking;
 use uuid::Uuid;
 use vek::Vec2;
-use windows::Win32::{
-    Foundation::HWND,
-    UI::WindowsAndMessaging::{WM_CONTEXTMENU, WM_LBUTTONUP},
-};
-use winit::{
-    platform::windows::WindowExtWindows,
-    window::{Icon, Window, WindowId},
+use winit::window::{Icon, Window, WindowId};
+#[cfg(windows)]
+use {
+    crate::windows::{MenuItem, NotifyIcon, PopupMenu},
+    windows::Win32::{
+        Foundation::HWND,
+        UI::WindowsAndMessaging::{WM_CONTEXTMENU, WM_LBUTTONUP},
+    },
+    winit::platform::windows::WindowExtWindows,
 };
 
 const APPLICATION_TITLE: &str = "Adwapach";
 
 const ICON_IMAGE_PNG: &[u8] = include_bytes!("../../resources/Adwapach.png");
-const NOTIFY_ICON_MESSAGE_ID: u32 = 1;
 
+/// Longer side of the image in focal point editor.
+const FOCUS_EDITOR_SIZE: f32 = 480.0;
+
+/// Canvas size of collage thumbnails, as a typical 16:9 monitor.
+const COLLAGE_THUMBNAIL_SIZE: (u32, u32) = (512, 288);
+
+const ROTATION_INTERVALS: &[(&str, u64)] = &[
+    ("1 minute", 60),
+    ("5 minutes", 5 * 60),
+    ("15 minutes", 15 * 60),
+    ("30 minutes", 30 * 60),
+    ("1 hour", 60 * 60),
+    ("3 hours", 3 * 60 * 60),
+    ("1 day", 24 * 60 * 60),
+];
+
+#[cfg(windows)]
+const NOTIFY_ICON_MESSAGE_ID: u32 = 1;
+#[cfg(windows)]
 const MENU_ID_SHOW: u32 = 0x1001;
+#[cfg(windows)]
 const MENU_ID_EXIT: u32 = 0x1002;
+#[cfg(windows)]
 const TASK_MENU_ITEMS: &[MenuItem] = &[
     MenuItem("Show Window", MENU_ID_SHOW),
     MenuItem("Exit", MENU_ID_EXIT),
@@ -54,12 +89,113 @@ const TASK_MENU_ITEMS: &[MenuItem] = &[
 pub struct ApplicationView {
     subscription: Option<Subscription<ApplicationViewModelEvent>>,
     event_proxy: Option<Arc<EventProxy<ApplicationWindowEvent>>>,
+    #[cfg(windows)]
     notify_icon: Option<NotifyIcon>,
     context: Option<Context>,
 
     viewmodel: Arc<Mutex<ApplicationViewModel>>,
     selected_monitor_index: Option<usize>,
-    wallpaper_cache: HashMap<Uuid, (TextureHandle, Vec2<u32>)>,
+    wallpaper_cache: HashMap<Uuid, TextureCache>,
+    schedule_window_open: bool,
+    schedule_name_drafts: HashMap<usize, String>,
+    focus_editor: Option<FocusEditor>,
+    adjustment_drafts: HashMap<Uuid, Adjustments>,
+
+    /// Edited overlays while the window is open, applied at once.
+    overlay_drafts: Option<Vec<Overlay>>,
+    collage_editor: Option<CollageEditor>,
+
+    /// Whether the list hides wallpapers not suitable for selected monitor.
+    suitable_only: bool,
+    sources_window_open: bool,
+
+    /// Edited include and exclude patterns of folder sources, comma-separated.
+    source_glob_drafts: HashMap<Uuid, (String, String)>,
+
+    /// Text of search box, parsed as `TagQuery`.
+    search_text: String,
+
+    /// Tags toggled by filter chips, all of which are required.
+    tag_filters: Vec<String>,
+
+    /// Wallpapers selected for bulk tag editing.
+    selected_wallpapers: HashSet<Uuid>,
+
+    /// Comma-separated tags added to or removed from selected wallpapers.
+    bulk_tags: String,
+
+    /// Edited tags of wallpapers, comma-separated.
+    tag_drafts: HashMap<Uuid, String>,
+    rotation_query_drafts: HashMap<usize, String>,
+    schedule_query_drafts: HashMap<usize, String>,
+
+    /// Edited bezel and physical size of monitors, committed when released.
+    layout_drafts: HashMap<usize, MonitorLayout>,
+
+    /// Collection shown in the list, or `None` for whole library.
+    current_collection: Option<Uuid>,
+    collection_name_drafts: HashMap<Uuid, String>,
+    smart_editor: Option<SmartEditor>,
+}
+
+/// Loaded thumbnails and analysis of a wallpaper.
+struct TextureCache {
+    thumbnails: Vec<TextureHandle>,
+    size: Vec2<u32>,
+    detected_focal_point: FocalPoint,
+
+    /// Applied to thumbnails.
+    adjustments: Adjustments,
+
+    /// Composed into the thumbnail of collages.
+    collage: Option<CollagePreview>,
+}
+
+/// State of collage editor window.
+struct CollageEditor {
+    /// Edited collage, or `None` to create new one.
+    wallpaper_id: Option<Uuid>,
+    name: String,
+    collage: Collage,
+}
+
+/// State of smart playlist editor window.
+struct SmartEditor {
+    /// Edited playlist, or `None` to create new one.
+    collection_id: Option<Uuid>,
+    name: String,
+    query: SmartQuery,
+
+    /// Texts of `query` fields, parsed when saved.
+    tags: String,
+    taken_from: String,
+    taken_until: String,
+}
+
+impl SmartEditor {
+    fn new(collection_id: Option<Uuid>, name: String, query: SmartQuery) -> SmartEditor {
+        SmartEditor {
+            collection_id,
+            name,
+            tags: query.tags.to_string(),
+            taken_from: query.taken_from.clone().unwrap_or_default(),
+            taken_until: query.taken_until.clone().unwrap_or_default(),
+            query,
+        }
+    }
+}
+
+/// State of focal point editor window.
+struct FocusEditor {
+    wallpaper_id: Uuid,
+    focal_point: FocalPoint,
+    detected_focal_point: FocalPoint,
+
+    /// Whether `focal_point` is set manually.
+    manual: bool,
+
+    /// Loaded in background.
+    texture: Arc<Mutex<Option<TextureHandle>>>,
 }
 
 impl ApplicationView {
@@ -67,12 +203,33 @@ impl ApplicationView {
         let view = Arc::new(Mutex::new(ApplicationView {
             subscription: None,
             event_proxy: None,
+            #[cfg(windows)]
             notify_icon: None,
             context: None,
 
             viewmodel: viewmodel.clone(),
             selected_monitor_index: None,
             wallpaper_cache: Default::default(),
+            schedule_window_open: false,
+            schedule_name_drafts: Default::default(),
+            focus_editor: None,
+            adjustment_drafts: Default::default(),
+            overlay_drafts: None,
+            collage_editor: None,
+            suitable_only: false,
+            sources_window_open: false,
+            source_glob_drafts: Default::default(),
+            search_text: String::new(),
+            tag_filters: vec![],
+            selected_wallpapers: Default::default(),
+            bulk_tags: String::new(),
+            tag_drafts: Default::default(),
+            rotation_query_drafts: Default::default(),
+            schedule_query_drafts: Default::default(),
+            layout_drafts: Default::default(),
+            current_collection: None,
+            collection_name_drafts: Default::default(),
+            smart_editor: None,
         }));
 
         let subscription = ApplicationView::setup_subscribe(viewmodel, view.clone());
@@ -101,16 +258,50 @@ impl ApplicationView {
                 let view = viewmodel_view.clone();
                 spawn_blocking(|| ApplicationView::update_texture_cache(view));
             }
+            ApplicationViewModelEvent::RotationUpdated
+            | ApplicationViewModelEvent::SchedulesUpdated
+            | ApplicationViewModelEvent::OverlaysUpdated => {
+                let view = viewmodel_view.clone();
+                spawn_blocking(|| ApplicationView::request_repaint(view));
+            }
         })
     }
 }
 
 /// View events.
 impl View<ApplicationWindowEvent> for ApplicationView {
+    #[cfg_attr(not(windows), allow(unused_variables))]
     fn attach_window(
         &mut self,
         window: &Window,
         event_proxy: Arc<EventProxy<ApplicationWindowEvent>>,
+    ) {
+        #[cfg(windows)]
+        self.attach_notify_icon(window, event_proxy.clone());
+        self.event_proxy = Some(event_proxy);
+    }
+
+    fn get_icon(&self) -> Option<Icon> {
+        let (icon_image, w, h) = {
+            let image = image::load_from_memory(ICON_IMAGE_PNG).ok()?;
+            let w = image.width();
+            let h = image.height();
+            let icon_image = image.to_rgba8().to_vec();
+            (icon_image, w, h)
+        };
+
+        Icon::from_rgba(icon_image, w, h).ok()
+    }
+}
+
+/// Window attachments.
+impl ApplicationView {
+    /// Creates notify icon and its popup menu.
+    #[cfg(windows)]
+    fn attach_notify_icon(
+        &mut self,
+        window: &Window,
+        event_proxy: Arc<EventProxy<ApplicationWindowEvent>>,
     ) {
         let window_id = window.id();
         let hwnd = HWND(window.hwnd() as _);
@@ -140,19 +331,6 @@ impl View<ApplicationWindowEvent> for ApplicationView {
         .expect("Failed to register taskbar icon");
 
         self.notify_icon = Some(notify_icon);
-        self.event_proxy = Some(event_proxy);
-    }
-
-    fn get_icon(&self) -> Option<Icon> {
-        let (icon_image, w, h) = {
-            let image = image::load_from_memory(ICON_IMAGE_PNG).ok()?;
-            let w = image.width();
-            let h = image.height();
-            let icon_image = image.to_rgba8().to_vec();
-            (icon_image, w, h)
-        };
-
-        Icon::from_rgba(icon_image, w, h).ok()
     }
 }
 
@@ -162,8 +340,10 @@ impl App for ApplicationView {
     }
 
     fn setup(&mut self, ctx: &Context, _frame: &Frame, _storage: Option<&dyn Storage>) {
-        let mut style = Style::default();
-        style.override_font_id = Some(FontId::proportional(16.0));
+        let mut style = Style {
+            override_font_id: Some(FontId::proportional(16.0)),
+            ..Default::default()
+        };
         style
             .text_styles
             .insert(TextStyle::Body, FontId::proportional(16.0));
@@ -193,16 +373,112 @@ impl App for ApplicationView {
                             .exit();
                     }
                 });
+                if ui.button("Schedules").clicked() {
+                    self.schedule_window_open = !self.schedule_window_open;
+                }
+                if ui.button("Overlays").clicked() {
+                    self.overlay_drafts = match self.overlay_drafts {
+                        Some(_) => None,
+                        None => Some(viewmodel.overlays.clone()),
+                    };
+                }
+                if ui.button("Sources").clicked() {
+                    self.sources_window_open = !self.sources_window_open;
+                }
             });
         });
 
+        let mut sources_window_open = self.sources_window_open;
+        UiWindow::new("Sources")
+            .open(&mut sources_window_open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                self.ui_draw_sources(ui, &viewmodel.sources, &viewmodel.wallpapers);
+            });
+        self.sources_window_open = sources_window_open;
+
+        let mut schedule_window_open = self.schedule_window_open;
+        UiWindow::new("Schedules")
+            .open(&mut schedule_window_open)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                self.ui_draw_solar(
+                    ui,
+                    &viewmodel.solar,
+                    &viewmodel.solar_events,
+                    &viewmodel.wallpapers,
+                );
+                ui.separator();
+                self.ui_draw_schedules(
+                    ui,
+                    &viewmodel.schedules,
+                    &viewmodel.monitors,
+                    &viewmodel.wallpapers,
+                );
+            });
+        self.schedule_window_open = schedule_window_open;
+
+        let mut overlay_window_open = self.overlay_drafts.is_some();
+        UiWindow::new("Overlays")
+            .open(&mut overlay_window_open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                self.ui_draw_overlays(ui, &viewmodel.overlays);
+            });
+        if !overlay_window_open {
+            self.overlay_drafts = None;
+        }
+
+        let mut collage_window_open = self.collage_editor.is_some();
+        UiWindow::new("Collage")
+            .open(&mut collage_window_open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                self.ui_draw_collage_editor(ui, &viewmodel.wallpapers);
+            });
+        if !collage_window_open {
+            self.collage_editor = None;
+        }
+
+        let mut smart_window_open = self.smart_editor.is_some();
+        UiWindow::new("Smart Playlist")
+            .open(&mut smart_window_open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                self.ui_draw_smart_editor(ui, &viewmodel.collections, &viewmodel.sources);
+            });
+        if !smart_window_open {
+            self.smart_editor = None;
+        }
+
+        let dropped: Vec<_> = ctx
+            .input()
+            .raw
+            .dropped_files
+            .iter()
+            .filter_map(|f| f.path.clone())
+            .collect();
+        if !ctx.input().raw.hovered_files.is_empty() {
+            ui_draw_drop_hint(ctx);
+        }
+
         let mut selected_index = match self.selected_monitor_index {
             Some(i) => i,
-            None => return,
+            None => {
+                self.add_dropped(dropped, None);
+                return;
+            }
         };
+        let mut drop_monitor_index = None;
         let selected_size = viewmodel.monitors[selected_index].size;
         let selected_position = viewmodel.monitors[selected_index].position;
 
+        self.ui_draw_focus_editor(
+            ctx,
+            &viewmodel.wallpapers,
+            &viewmodel.monitors[selected_index],
+        );
+
         CentralPanel::default().show(ctx, |ui| {
             // Monitor preview & selection
             ui.vertical_centered(|ui| {
@@ -212,11 +488,18 @@ impl App for ApplicationView {
                     &viewmodel.monitors,
                     selected_index,
                     &mut selected_index,
+                    &mut drop_monitor_index,
                 );
             });
             ui.horizontal_wrapped(|ui| {
                 for (i, monitor) in viewmodel.monitors.iter().enumerate() {
-                    ui.selectable_value(&mut selected_index, i, &monitor.name);
+                    let text = match viewmodel.rotations.get(i).copied().flatten() {
+                        Some(r) if !r.paused => {
+                            format!("{} ({})", monitor.name, format_duration(r.remaining))
+                        }
+                        _ => monitor.name.clone(),
+                    };
+                    ui.selectable_value(&mut selected_index, i, text);
                 }
             });
             ui.separator();
@@ -241,6 +524,92 @@ impl App for ApplicationView {
                         selected_size.x, selected_size.y
                     ));
                     ui.end_row();
+
+                    ui.label(RichText::new("Bezel").strong())
+                        .on_hover_text("Hidden border skipped by spanned wallpapers");
+                    self.ui_draw_bezel(ui, &viewmodel.monitors[selected_index], selected_index);
+                    ui.end_row();
+
+                    ui.label(RichText::new("Physical Size").strong())
+                        .on_hover_text("Visible area in millimeters, to align mixed DPI monitors");
+                    self.ui_draw_physical_size(
+                        ui,
+                        &viewmodel.monitors[selected_index],
+                        selected_index,
+                    );
+                    ui.end_row();
+
+                    ui.label(RichText::new("Span Group").strong())
+                        .on_hover_text("Monitors which spanned wallpapers continue across");
+                    self.ui_draw_span_group(ui, &viewmodel.monitors, selected_index);
+                    ui.end_row();
+
+                    ui.label(RichText::new("Rotation").strong())
+                        .on_hover_text("Changes wallpaper periodically");
+                    let rotation = viewmodel.rotations.get(selected_index).copied().flatten();
+                    self.ui_draw_rotation(ui, selected_index, rotation);
+                    ui.end_row();
+
+                    if rotation.is_some() {
+                        ui.label(RichText::new("Rotation Source").strong())
+                            .on_hover_text("Collection which wallpapers are rotated through");
+                        let collection = viewmodel
+                            .rotation_collections
+                            .get(selected_index)
+                            .copied()
+                            .flatten();
+                        self.ui_draw_rotation_collection(
+                            ui,
+                            selected_index,
+                            collection,
+                            &viewmodel.collections,
+                        );
+                        ui.end_row();
+                    }
+
+                    let rotation_query = viewmodel
+                        .rotation_queries
+                        .get(selected_index)
+                        .cloned()
+                        .flatten();
+                    if let Some(query) = rotation_query {
+                        ui.label(RichText::new("Rotation Filter").strong())
+                            .on_hover_text("Rotates only wallpapers matching the query");
+                        if let Some(edited) = ui_query_edit(
+                            ui,
+                            &mut self.rotation_query_drafts,
+                            selected_index,
+                            &query,
+                        ) {
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_set_rotation_query(
+                                    viewmodel,
+                                    selected_index,
+                                    edited,
+                                )
+                            });
+                        }
+                        ui.end_row();
+                    }
+
+                    ui.label(RichText::new("Aspect Matching").strong())
+                        .on_hover_text("Prefers or requires wallpapers shaped like the monitor");
+                    self.ui_draw_aspect_matching(ui, viewmodel.aspect_matching);
+                    ui.end_row();
+
+                    ui.label(RichText::new("Schedule").strong())
+                        .on_hover_text("Schedule active for this monitor");
+                    match viewmodel
+                        .scheduled_sources
+                        .get(selected_index)
+                        .cloned()
+                        .flatten()
+                    {
+                        Some(name) => ui.label(name),
+                        None => ui.label("None"),
+                    };
+                    ui.end_row();
                 });
 
             ui.separator();
@@ -250,19 +619,219 @@ impl App for ApplicationView {
                     let viewmodel = self.viewmodel.clone();
                     spawn_blocking(|| ApplicationViewModel::action_add_image(viewmodel));
                 }
+                if ui.button("Add Folder").clicked() {
+                    let viewmodel = self.viewmodel.clone();
+                    spawn_blocking(|| ApplicationViewModel::action_add_folder(viewmodel));
+                }
+                ui.checkbox(&mut self.suitable_only, "Only Suitable for Monitor")
+                    .on_hover_text("Hides wallpapers whose aspect ratio differs from the monitor");
+                if ui.button("New Collage").clicked() {
+                    let count = viewmodel
+                        .wallpapers
+                        .iter()
+                        .filter(|w| w.kind == WallpaperKind::Collage)
+                        .count();
+                    self.collage_editor = Some(CollageEditor {
+                        wallpaper_id: None,
+                        name: format!("Collage {}", count + 1),
+                        collage: Collage::default(),
+                    });
+                }
             });
 
+            let search_query = self.ui_draw_search(ui, &viewmodel.tags);
+            self.ui_draw_bulk_tags(ui, &viewmodel.wallpapers);
+            ui.separator();
+            self.ui_draw_collections(ui, &viewmodel.collections);
+
             ui.add_space(0.0);
 
+            let target_aspect = selected_size.x as f32 / selected_size.y.max(1) as f32;
+            let aspect_matching = viewmodel.aspect_matching;
+            let suitable_only = self.suitable_only;
+            let tag_filters = self.tag_filters.clone();
             ScrollArea::vertical().show(ui, |ui| {
-                self.ui_draw_image_items(ui, &viewmodel.wallpapers);
+                self.ui_draw_image_items(ui, &viewmodel.wallpapers, &viewmodel.collections, |w| {
+                    (!suitable_only || aspect_matching.is_suitable(w.aspect_ratio, target_aspect))
+                        && w.matches(&search_query)
+                        && tag_filters.iter().all(|t| w.tags.contains(t))
+                });
             });
         });
+        self.add_dropped(dropped, drop_monitor_index);
     }
 }
 
 /// Sub-UI functions.
 impl ApplicationView {
+    /// Adds dropped files and folders, applying to the monitor if dropped onto its preview.
+    fn add_dropped(&self, paths: Vec<PathBuf>, monitor_index: Option<usize>) {
+        if paths.is_empty() {
+            return;
+        }
+        let viewmodel = self.viewmodel.clone();
+        spawn_blocking(move || {
+            ApplicationViewModel::action_add_dropped(viewmodel, paths, monitor_index)
+        });
+    }
+
+    /// Draws search box and tag filter chips, and returns parsed search query.
+    /// Invalid query matches everything.
+    fn ui_draw_search(&mut self, ui: &mut Ui, tags: &[(String, usize)]) -> TagQuery {
+        let query = ui
+            .horizontal(|ui| {
+                ui.label("Search")
+                    .on_hover_text("Words and \"#tag\", combined with AND, OR and NOT");
+                ui.add(TextEdit::singleline(&mut self.search_text).hint_text("#space -#dark"));
+                if !self.search_text.is_empty() && ui.small_button("Clear").clicked() {
+                    self.search_text.clear();
+                }
+                match self.search_text.parse::<TagQuery>() {
+                    Ok(q) => q,
+                    Err(e) => {
+                        ui.colored_label(Color32::RED, e.to_string());
+                        TagQuery::default()
+                    }
+                }
+            })
+            .inner;
+
+        self.tag_filters
+            .retain(|f| tags.iter().any(|(tag, _)| tag == f));
+        if !tags.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for (tag, count) in tags {
+                    let active = self.tag_filters.contains(tag);
+                    if ui
+                        .selectable_label(active, format!("#{tag} ({count})"))
+                        .clicked()
+                    {
+                        if active {
+                            self.tag_filters.retain(|f| f != tag);
+                        } else {
+                            self.tag_filters.push(tag.clone());
+                        }
+                    }
+                }
+            });
+        }
+
+        query
+    }
+
+    /// Draws tag editor for selected wallpapers.
+    fn ui_draw_bulk_tags(&mut self, ui: &mut Ui, wallpapers: &[WallpaperCache]) {
+        self.selected_wallpapers
+            .retain(|id| wallpapers.iter().any(|w| w.uuid == *id));
+        if self.selected_wallpapers.is_empty() {
+            ui.weak("Ctrl+Click wallpapers to edit their tags at once");
+            return;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label(format!("{} Selected", self.selected_wallpapers.len()));
+            ui.add(TextEdit::singleline(&mut self.bulk_tags).hint_text("Comma-separated tags"));
+
+            let tags = split_tags(&self.bulk_tags);
+            let mut edit = None;
+            ui.add_enabled_ui(!tags.is_empty(), |ui| {
+                if ui.button("Add Tags").clicked() {
+                    edit = Some((tags.clone(), vec![]));
+                }
+                if ui.button("Remove Tags").clicked() {
+                    edit = Some((vec![], tags.clone()));
+                }
+            });
+            if let Some((added, removed)) = edit {
+                let indices = wallpapers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, w)| self.selected_wallpapers.contains(&w.uuid))
+                    .map(|(i, _)| i)
+                    .collect();
+                let viewmodel = self.viewmodel.clone();
+                spawn_blocking(move || {
+                    ApplicationViewModel::action_edit_tags(viewmodel, indices, added, removed)
+                });
+                self.bulk_tags.clear();
+            }
+
+            if ui.button("Clear Selection").clicked() {
+                self.selected_wallpapers.clear();
+            }
+        });
+    }
+
+    /// Draws collection switcher, and editor of the current collection.
+    fn ui_draw_collections(&mut self, ui: &mut Ui, collections: &[CollectionCache]) {
+        if !collections
+            .iter()
+            .any(|c| Some(c.uuid) == self.current_collection)
+        {
+            self.current_collection = None;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.current_collection, None, "Library");
+            for collection in collections {
+                ui.selectable_value(
+                    &mut self.current_collection,
+                    Some(collection.uuid),
+                    &collection.name,
+                );
+            }
+            if ui.button("New Collection").clicked() {
+                let name = format!("Collection {}", collections.len() + 1);
+                self.perform_collection(CollectionOperation::Add(name));
+            }
+            if ui.button("New Smart Playlist").clicked() {
+                let name = format!("Smart Playlist {}", collections.len() + 1);
+                self.smart_editor = Some(SmartEditor::new(None, name, SmartQuery::default()));
+            }
+        });
+
+        let (index, collection) = match collections
+            .iter()
+            .enumerate()
+            .find(|(_, c)| Some(c.uuid) == self.current_collection)
+        {
+            Some(c) => c,
+            None => return,
+        };
+        ui.horizontal(|ui| {
+            ui.label("Name");
+            let mut name = match self.collection_name_drafts.get(&collection.uuid) {
+                Some(n) => n.clone(),
+                None => collection.name.clone(),
+            };
+            let response = ui.text_edit_singleline(&mut name);
+            if response.lost_focus() {
+                self.collection_name_drafts.remove(&collection.uuid);
+                let name = name.trim();
+                if !name.is_empty() && name != collection.name {
+                    self.perform_collection(CollectionOperation::Rename(index, name.to_string()));
+                }
+            } else if response.has_focus() {
+                self.collection_name_drafts.insert(collection.uuid, name);
+            }
+
+            ui.label(format!("{} Wallpapers", collection.members.len()));
+            if let Some(query) = &collection.query {
+                if ui.button("Edit Query").clicked() {
+                    self.smart_editor = Some(SmartEditor::new(
+                        Some(collection.uuid),
+                        collection.name.clone(),
+                        query.clone(),
+                    ));
+                }
+            }
+            if ui.button("Remove Collection").clicked() {
+                self.current_collection = None;
+                self.perform_collection(CollectionOperation::Remove(index));
+            }
+        });
+    }
+
     /// Draws monitor preview rects.
     fn ui_draw_monitor_preview(
         &self,
@@ -271,6 +840,7 @@ impl ApplicationView {
         monitors: &[MonitorCache],
         selected: usize,
         target: &mut usize,
+        drop_target: &mut Option<usize>,
     ) -> Response {
         let (response, painter) = ui.allocate_painter(UiVec2::new(size, size), Sense::hover());
         let rect_size = response.rect.size();
@@ -281,6 +851,12 @@ impl ApplicationView {
         let stroke = Stroke::new(2.0, Color32::WHITE);
         let stroke_selected = Stroke::new(2.0, Color32::BLUE);
         let fill = Color32::from_white_alpha(32);
+        let fill_spanned = Color32::from_white_alpha(64);
+        let fill_bezel = Color32::from_gray(16);
+        let stroke_dropping = Stroke::new(3.0, Color32::YELLOW);
+        let selected_group = &monitors[selected].span_group;
+        let pointer = ui.input().pointer.hover_pos();
+        let dropping = !ui.input().raw.hovered_files.is_empty();
 
         for (i, monitor) in monitors.iter().enumerate() {
             let mlt = UiPos2::new(
@@ -293,9 +869,32 @@ impl ApplicationView {
             ) + offset.to_vec2();
             let monitor_rect = Rect::from_min_max(mlt, mrb);
 
-            painter.rect_filled(monitor_rect, 2.0, fill);
+            // Bezel is the gap between whole monitor and visible area
+            let screen_rect = Rect::from_min_max(
+                UiPos2::new(
+                    monitor.preview_screen_rect.x * multiplier,
+                    monitor.preview_screen_rect.y * multiplier,
+                ) + offset.to_vec2(),
+                UiPos2::new(
+                    monitor.preview_screen_rect.z * multiplier,
+                    monitor.preview_screen_rect.w * multiplier,
+                ) + offset.to_vec2(),
+            )
+            .intersect(monitor_rect);
+            painter.rect_filled(monitor_rect, 2.0, fill_bezel);
+            if monitor.span_group == *selected_group {
+                painter.rect_filled(screen_rect, 0.0, fill_spanned);
+            } else {
+                painter.rect_filled(screen_rect, 0.0, fill);
+            }
 
-            if i == selected {
+            let pointed = pointer.is_some_and(|p| monitor_rect.contains(p));
+            if pointed {
+                *drop_target = Some(i);
+            }
+            if dropping && pointed {
+                painter.rect_stroke(monitor_rect, 2.0, stroke_dropping);
+            } else if i == selected {
                 painter.rect_stroke(monitor_rect, 2.0, stroke_selected);
             } else {
                 painter.rect_stroke(monitor_rect, 2.0, stroke);
@@ -314,108 +913,1291 @@ impl ApplicationView {
         response
     }
 
-    /// Draw an item of wallpaper image list.
-    fn ui_draw_image_items(&mut self, ui: &mut Ui, wallpapers: &[WallpaperCache]) {
-        let left_center_layout =
-            Layout::centered_and_justified(Direction::TopDown).with_cross_align(Align::LEFT);
-        let head_style = TextFormat {
-            font_id: TextStyle::Heading.resolve(ui.style()),
-            color: Color32::WHITE,
-            ..Default::default()
-        };
-        let prop_style = TextFormat {
-            font_id: TextStyle::Body.resolve(ui.style()),
-            ..Default::default()
-        };
-        let thumbnail_size = UiVec2::splat(100.0);
-
-        for (i, wallpaper) in wallpapers.iter().enumerate() {
-            let (thumbnail, size_text) = match self.wallpaper_cache.get(&wallpaper.uuid) {
-                Some((t, s)) => (Some(t), format!("Size: {}x{}\n", s.x, s.y)),
-                None => (None, "Size: Unknown\n".into()),
-            };
-
-            let inner_response = ui.horizontal(|ui| {
-                match thumbnail {
-                    Some(t) => {
-                        ui.image(t.id(), thumbnail_size);
-                    }
-                    None => {
-                        ui.allocate_painter(thumbnail_size, Sense::hover());
+    /// Draws bezel editor of the monitor.
+    /// Values are committed when released, not on every frame while dragged.
+    fn ui_draw_bezel(&mut self, ui: &mut Ui, monitor: &MonitorCache, monitor_index: usize) {
+        let edited = self
+            .layout_drafts
+            .entry(monitor_index)
+            .or_insert(monitor.layout);
+        let mut committed = false;
+        ui.horizontal(|ui| {
+            let bezel = &mut edited.bezel;
+            let sides = [
+                ("L: ", &mut bezel.left),
+                ("T: ", &mut bezel.top),
+                ("R: ", &mut bezel.right),
+                ("B: ", &mut bezel.bottom),
+            ];
+            for (prefix, value) in sides {
+                let response = ui.add(
+                    DragValue::new(value)
+                        .clamp_range(0.0..=500.0)
+                        .speed(0.5)
+                        .prefix(prefix),
+                );
+                committed |= response.drag_released() || response.lost_focus();
+            }
+            ComboBox::from_id_source("bezel_unit")
+                .selected_text(bezel.unit.label())
+                .width(48.0)
+                .show_ui(ui, |ui| {
+                    for unit in BezelUnit::ALL {
+                        committed |= ui
+                            .selectable_value(&mut bezel.unit, *unit, unit.label())
+                            .changed();
                     }
-                }
+                });
+        });
+        let edited = *edited;
+        if committed {
+            self.set_monitor_layout(monitor, monitor_index, edited);
+        }
+    }
 
-                ui.with_layout(left_center_layout, |ui| {
-                    let mut text = LayoutJob::default();
-                    text.append(
-                        &format!("{}\n", wallpaper.filename),
-                        0.0,
-                        head_style.clone(),
+    /// Draws physical size editor of the monitor.
+    /// Values are committed when released, not on every frame while dragged.
+    fn ui_draw_physical_size(&mut self, ui: &mut Ui, monitor: &MonitorCache, monitor_index: usize) {
+        let edited = self
+            .layout_drafts
+            .entry(monitor_index)
+            .or_insert(monitor.layout);
+        let mut committed = false;
+        ui.horizontal(|ui| {
+            let mut overridden = edited.physical_size.is_some();
+            committed |= ui.checkbox(&mut overridden, "").changed();
+            edited.physical_size = match (overridden, edited.physical_size) {
+                (true, Some(mut size)) => {
+                    let response = ui.add(
+                        DragValue::new(&mut size.width)
+                            .clamp_range(1.0..=5000.0)
+                            .suffix(" mm"),
                     );
-                    text.append(&size_text, 0.0, prop_style.clone());
-                    text.append(
-                        &format!("Fitting: {:?}", wallpaper.fitting),
-                        0.0,
-                        prop_style.clone(),
+                    committed |= response.drag_released() || response.lost_focus();
+                    ui.label("x");
+                    let response = ui.add(
+                        DragValue::new(&mut size.height)
+                            .clamp_range(1.0..=5000.0)
+                            .suffix(" mm"),
                     );
-                    ui.label(text);
-                });
+                    committed |= response.drag_released() || response.lost_focus();
+                    Some(size)
+                }
+                // Starts from the size assumed in 96 DPI
+                (true, None) => Some(PhysicalSize::assumed(monitor.size)),
+                (false, _) => {
+                    ui.label("Assumed from 96 DPI");
+                    None
+                }
+            };
+        });
+        let edited = *edited;
+        if committed {
+            self.set_monitor_layout(monitor, monitor_index, edited);
+        }
+    }
+
+    /// Commits edited layout of the monitor if changed.
+    fn set_monitor_layout(
+        &self,
+        monitor: &MonitorCache,
+        monitor_index: usize,
+        edited: MonitorLayout,
+    ) {
+        if edited == monitor.layout {
+            return;
+        }
+
+        let viewmodel = self.viewmodel.clone();
+        spawn_blocking(move || {
+            ApplicationViewModel::action_set_monitor_layout(viewmodel, monitor_index, edited)
+        });
+    }
+
+    /// Draws span group selector of the monitor.
+    fn ui_draw_span_group(&self, ui: &mut Ui, monitors: &[MonitorCache], monitor_index: usize) {
+        let current = monitors[monitor_index].span_group.clone();
+        let mut groups: Vec<_> = monitors
+            .iter()
+            .filter_map(|m| m.span_group.clone())
+            .collect();
+        groups.sort();
+        groups.dedup();
+        let new_group = (1..)
+            .map(|n| format!("Group {n}"))
+            .find(|g| !groups.contains(g))
+            .expect("Should be found");
+
+        let mut selected = current.clone();
+        ComboBox::from_id_source("span_group")
+            .selected_text(current.as_deref().unwrap_or("Ungrouped"))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, "Ungrouped");
+                for group in groups {
+                    ui.selectable_value(&mut selected, Some(group.clone()), group);
+                }
+                ui.selectable_value(&mut selected, Some(new_group), "New Group");
             });
+        if selected != current {
+            let viewmodel = self.viewmodel.clone();
+            spawn_blocking(move || {
+                ApplicationViewModel::action_set_span_group(viewmodel, monitor_index, selected)
+            });
+        }
+    }
 
-            let response = ui
-                .interact(
-                    inner_response.response.rect,
-                    Id::new(format!("wallpaper_item_{i}")),
-                    Sense::click(),
-                )
-                .context_menu(|ui| {
-                    let mut selected_fitting = wallpaper.fitting;
-                    ui.menu_button("Change Fitting", |ui| {
-                        ui.selectable_value(&mut selected_fitting, Fitting::Cover, "Cover");
-                        ui.selectable_value(&mut selected_fitting, Fitting::Contain, "Contain");
-                        ui.selectable_value(&mut selected_fitting, Fitting::Tile, "Tile");
-                        ui.selectable_value(&mut selected_fitting, Fitting::Center, "Center");
-                    });
-                    if selected_fitting != wallpaper.fitting {
-                        let viewmodel = self.viewmodel.clone();
-                        spawn_blocking(move || {
-                            ApplicationViewModel::action_perform_wallpaper(
-                                viewmodel,
-                                i,
-                                WallpaperListOperation::SetFitting(selected_fitting),
-                            )
-                        });
-                        ui.close_menu();
+    /// Draws rotation controls of the monitor.
+    fn ui_draw_rotation(
+        &self,
+        ui: &mut Ui,
+        monitor_index: usize,
+        rotation: Option<RotationStatus>,
+    ) {
+        ui.horizontal(|ui| {
+            let current = rotation.map(|r| r.interval.as_secs());
+            let mut selected = current;
+            let selected_text = ROTATION_INTERVALS
+                .iter()
+                .find(|(_, secs)| Some(*secs) == current)
+                .map(|(label, _)| label.to_string())
+                .or_else(|| current.map(|secs| format_duration(Duration::from_secs(secs))))
+                .unwrap_or_else(|| "Off".into());
+            ComboBox::from_id_source("rotation_interval")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, None, "Off");
+                    for (label, secs) in ROTATION_INTERVALS {
+                        ui.selectable_value(&mut selected, Some(*secs), *label);
                     }
+                });
+            if selected != current {
+                let viewmodel = self.viewmodel.clone();
+                spawn_blocking(move || {
+                    ApplicationViewModel::action_set_rotation_interval(
+                        viewmodel,
+                        monitor_index,
+                        selected.map(Duration::from_secs),
+                    )
+                });
+            }
 
-                    ui.separator();
+            let rotation = match rotation {
+                Some(r) => r,
+                None => return,
+            };
 
-                    if ui.button("Move Up").clicked() {
-                        let viewmodel = self.viewmodel.clone();
+            let mut selected_order = rotation.order;
+            ComboBox::from_id_source("rotation_order")
+                .selected_text(rotation.order.label())
+                .show_ui(ui, |ui| {
+                    for order in RotationOrder::ALL {
+                        ui.selectable_value(&mut selected_order, *order, order.label());
+                    }
+                });
+            if selected_order != rotation.order {
+                let viewmodel = self.viewmodel.clone();
+                spawn_blocking(move || {
+                    ApplicationViewModel::action_set_rotation_order(
+                        viewmodel,
+                        monitor_index,
+                        selected_order,
+                    )
+                });
+            }
+
+            let pause_text = if rotation.paused { "Resume" } else { "Pause" };
+            if ui.button(pause_text).clicked() {
+                let viewmodel = self.viewmodel.clone();
+                spawn_blocking(move || {
+                    ApplicationViewModel::action_pause_rotation(
+                        viewmodel,
+                        monitor_index,
+                        !rotation.paused,
+                    )
+                });
+            }
+            if ui.button("Skip").clicked() {
+                let viewmodel = self.viewmodel.clone();
+                spawn_blocking(move || {
+                    ApplicationViewModel::action_skip_rotation(viewmodel, monitor_index)
+                });
+            }
+            if rotation.paused {
+                ui.label("Paused");
+            } else {
+                ui.label(format!("Next in {}", format_duration(rotation.remaining)));
+            }
+        });
+    }
+
+    /// Draws collection selector for rotation of the monitor.
+    fn ui_draw_rotation_collection(
+        &self,
+        ui: &mut Ui,
+        monitor_index: usize,
+        current: Option<Uuid>,
+        collections: &[CollectionCache],
+    ) {
+        let mut selected = current;
+        let selected_text = collections
+            .iter()
+            .find(|c| Some(c.uuid) == current)
+            .map(|c| c.name.as_str())
+            .unwrap_or("Whole Library");
+        ComboBox::from_id_source("rotation_collection")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut selected, None, "Whole Library");
+                for collection in collections {
+                    ui.selectable_value(&mut selected, Some(collection.uuid), &collection.name);
+                }
+            });
+        if selected != current {
+            let viewmodel = self.viewmodel.clone();
+            spawn_blocking(move || {
+                ApplicationViewModel::action_set_rotation_collection(
+                    viewmodel,
+                    monitor_index,
+                    selected,
+                )
+            });
+        }
+    }
+
+    /// Draws aspect ratio matching editor, shared by all monitors.
+    fn ui_draw_aspect_matching(&self, ui: &mut Ui, aspect_matching: AspectMatching) {
+        let mut edited = aspect_matching;
+        ui.horizontal(|ui| {
+            ComboBox::from_id_source("aspect_mode")
+                .selected_text(edited.mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in AspectMode::ALL {
+                        ui.selectable_value(&mut edited.mode, *mode, mode.label());
+                    }
+                });
+            ui.add(
+                DragValue::new(&mut edited.tolerance)
+                    .clamp_range(0.0..=100.0)
+                    .speed(0.5)
+                    .prefix("Tolerance: ")
+                    .suffix("%"),
+            );
+        });
+        if edited != aspect_matching {
+            let viewmodel = self.viewmodel.clone();
+            spawn_blocking(move || {
+                ApplicationViewModel::action_set_aspect_matching(viewmodel, edited)
+            });
+        }
+    }
+
+    /// Draws sun-driven switching editor.
+    fn ui_draw_solar(
+        &self,
+        ui: &mut Ui,
+        solar: &SolarSettings,
+        solar_events: &str,
+        wallpapers: &[WallpaperCache],
+    ) {
+        let mut edited = solar.clone();
+        CollapsingHeader::new("Sunrise and Sunset")
+            .id_source("solar")
+            .show(ui, |ui| {
+                ui.label("Used when no rule is active.");
+                Grid::new("solar_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Enabled");
+                    ui.checkbox(&mut edited.enabled, "");
+                    ui.end_row();
+
+                    ui.label("Location");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            DragValue::new(&mut edited.latitude)
+                                .clamp_range(-90.0..=90.0)
+                                .speed(0.1)
+                                .prefix("Lat: "),
+                        );
+                        ui.add(
+                            DragValue::new(&mut edited.longitude)
+                                .clamp_range(-180.0..=180.0)
+                                .speed(0.1)
+                                .prefix("Lon: "),
+                        );
+                    });
+                    ui.end_row();
+
+                    ui.label("Twilight")
+                        .on_hover_text("Twilight is shown with dusk wallpapers");
+                    ComboBox::from_id_source("solar_twilight")
+                        .selected_text(edited.twilight.label())
+                        .show_ui(ui, |ui| {
+                            for twilight in Twilight::ALL {
+                                ui.selectable_value(
+                                    &mut edited.twilight,
+                                    *twilight,
+                                    twilight.label(),
+                                );
+                            }
+                        });
+                    ui.end_row();
+
+                    ui.label("Today");
+                    ui.label(solar_events);
+                    ui.end_row();
+                });
+
+                let sets = [
+                    ("Day", &mut edited.day),
+                    ("Dusk", &mut edited.dusk),
+                    ("Night", &mut edited.night),
+                ];
+                for (name, set) in sets {
+                    CollapsingHeader::new(format!("{name} Wallpapers ({})", set.len()))
+                        .id_source(format!("solar_{name}"))
+                        .show(ui, |ui| {
+                            for wallpaper in wallpapers {
+                                ui_toggle_member(ui, set, wallpaper.uuid, &wallpaper.filename);
+                            }
+                        });
+                }
+            });
+
+        if edited != *solar {
+            let viewmodel = self.viewmodel.clone();
+            spawn_blocking(move || ApplicationViewModel::action_set_solar(viewmodel, edited));
+        }
+    }
+
+    /// Draws text overlays editor.
+    fn ui_draw_overlays(&mut self, ui: &mut Ui, overlays: &[Overlay]) {
+        let drafts = match &mut self.overlay_drafts {
+            Some(d) => d,
+            None => return,
+        };
+
+        let mut removed = None;
+        for (i, overlay) in drafts.iter_mut().enumerate() {
+            CollapsingHeader::new(format!("#{} {}", i + 1, overlay.content.label()))
+                .id_source(format!("overlay_{i}"))
+                .default_open(true)
+                .show(ui, |ui| {
+                    Grid::new(format!("overlay_grid_{i}"))
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Content");
+                            ComboBox::from_id_source(format!("overlay_content_{i}"))
+                                .selected_text(overlay.content.label())
+                                .show_ui(ui, |ui| {
+                                    for content in OverlayContent::ALL {
+                                        ui.selectable_value(
+                                            &mut overlay.content,
+                                            *content,
+                                            content.label(),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+
+                            if overlay.content == OverlayContent::CustomText {
+                                ui.label("Text");
+                                ui.text_edit_multiline(&mut overlay.text);
+                                ui.end_row();
+                            }
+
+                            ui.label("Font")
+                                .on_hover_text("TrueType or OpenType font file");
+                            ui.add(TextEdit::singleline(&mut overlay.font).hint_text("Bundled"));
+                            ui.end_row();
+
+                            ui.label("Size")
+                                .on_hover_text("Height of text relative to the monitor");
+                            ui.add(
+                                DragValue::new(&mut overlay.size)
+                                    .clamp_range(0.5..=25.0)
+                                    .speed(0.1)
+                                    .suffix("%"),
+                            );
+                            ui.end_row();
+
+                            ui.label("Color");
+                            ui.horizontal(|ui| {
+                                ui.color_edit_button_srgb(&mut overlay.color);
+                                ui.checkbox(&mut overlay.shadow, "Shadow");
+                            });
+                            ui.end_row();
+
+                            ui.label("Position");
+                            ComboBox::from_id_source(format!("overlay_anchor_{i}"))
+                                .selected_text(overlay.anchor.label())
+                                .show_ui(ui, |ui| {
+                                    for anchor in OverlayAnchor::ALL {
+                                        ui.selectable_value(
+                                            &mut overlay.anchor,
+                                            *anchor,
+                                            anchor.label(),
+                                        );
+                                    }
+                                });
+                            ui.end_row();
+                        });
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                });
+        }
+        if let Some(i) = removed {
+            drafts.remove(i);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Add Overlay").clicked() {
+                drafts.push(Overlay::default());
+            }
+            let changed = drafts[..] != *overlays;
+            if ui.add_enabled(changed, Button::new("Revert")).clicked() {
+                *drafts = overlays.to_vec();
+            }
+            if ui.add_enabled(changed, Button::new("Apply")).clicked() {
+                let viewmodel = self.viewmodel.clone();
+                let overlays = drafts.clone();
+                spawn_blocking(move || {
+                    ApplicationViewModel::action_set_overlays(viewmodel, overlays)
+                });
+            }
+        });
+    }
+
+    /// Draws folder sources with their glob patterns.
+    fn ui_draw_sources(
+        &mut self,
+        ui: &mut Ui,
+        sources: &[FolderSource],
+        wallpapers: &[WallpaperCache],
+    ) {
+        self.source_glob_drafts
+            .retain(|id, _| sources.iter().any(|s| s.id() == *id));
+
+        for (i, source) in sources.iter().enumerate() {
+            let count = wallpapers
+                .iter()
+                .filter(|w| w.source == Some(source.id()))
+                .count();
+            CollapsingHeader::new(format!("{} ({count} Images)", source.path().display()))
+                .id_source(source.id())
+                .default_open(true)
+                .show(ui, |ui| {
+                    let (include, exclude) = self
+                        .source_glob_drafts
+                        .entry(source.id())
+                        .or_insert_with(|| (source.include.join(", "), source.exclude.join(", ")));
+                    Grid::new(format!("source_grid_{i}"))
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            ui.label("Include")
+                                .on_hover_text("Comma-separated glob patterns, like \"**/*.png\"");
+                            ui.add(TextEdit::singleline(include).hint_text("All Images"));
+                            ui.end_row();
+
+                            ui.label("Exclude")
+                                .on_hover_text("Comma-separated glob patterns, like \"raw/**\"");
+                            ui.add(TextEdit::singleline(exclude).hint_text("None"));
+                            ui.end_row();
+                        });
+
+                    let include = split_patterns(include);
+                    let exclude = split_patterns(exclude);
+                    ui.horizontal(|ui| {
+                        let changed = include != source.include || exclude != source.exclude;
+                        if ui.add_enabled(changed, Button::new("Apply")).clicked() {
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_set_source_globs(
+                                    viewmodel, i, include, exclude,
+                                )
+                            });
+                        }
+                        if ui.button("Remove").clicked() {
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_remove_source(viewmodel, i)
+                            });
+                        }
+                    });
+                });
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Add Folder").clicked() {
+                let viewmodel = self.viewmodel.clone();
+                spawn_blocking(|| ApplicationViewModel::action_add_folder(viewmodel));
+            }
+            if ui
+                .add_enabled(!sources.is_empty(), Button::new("Rescan"))
+                .clicked()
+            {
+                let viewmodel = self.viewmodel.clone();
+                spawn_blocking(|| ApplicationViewModel::action_rescan_sources(viewmodel));
+            }
+        });
+    }
+
+    /// Draws collage editor, which creates new collage or changes existing one.
+    fn ui_draw_collage_editor(&mut self, ui: &mut Ui, wallpapers: &[WallpaperCache]) {
+        let editor = match &mut self.collage_editor {
+            Some(e) => e,
+            None => return,
+        };
+        let wallpaper_index = match editor.wallpaper_id {
+            Some(id) => match wallpapers.iter().position(|w| w.uuid == id) {
+                Some(i) => Some(i),
+                None => {
+                    self.collage_editor = None;
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        Grid::new("collage_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut editor.name);
+            ui.end_row();
+
+            ui.label("Layout");
+            ComboBox::from_id_source("collage_layout")
+                .selected_text(editor.collage.layout.label())
+                .show_ui(ui, |ui| {
+                    for layout in CollageLayout::ALL {
+                        ui.selectable_value(&mut editor.collage.layout, *layout, layout.label());
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Gutter")
+                .on_hover_text("Space between images relative to the monitor height");
+            ui.add(
+                DragValue::new(&mut editor.collage.gutter)
+                    .clamp_range(0.0..=10.0)
+                    .speed(0.1)
+                    .suffix("%"),
+            );
+            ui.end_row();
+
+            ui.label("Background");
+            ui.color_edit_button_srgb(&mut editor.collage.background);
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label(RichText::new("Images").strong())
+            .on_hover_text("Placed in the checked order");
+        ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+            for wallpaper in wallpapers
+                .iter()
+                .filter(|w| w.kind != WallpaperKind::Collage)
+            {
+                ui_toggle_member(
+                    ui,
+                    &mut editor.collage.members,
+                    wallpaper.uuid,
+                    &wallpaper.filename,
+                );
+            }
+        });
+
+        ui.separator();
+        let label = match wallpaper_index {
+            Some(_) => "Apply",
+            None => "Create",
+        };
+        let valid = !editor.name.is_empty() && !editor.collage.members.is_empty();
+        if ui.add_enabled(valid, Button::new(label)).clicked() {
+            let viewmodel = self.viewmodel.clone();
+            let (name, collage) = (editor.name.clone(), editor.collage.clone());
+            match wallpaper_index {
+                Some(i) => spawn_blocking(move || {
+                    ApplicationViewModel::action_set_collage(viewmodel, i, name, collage)
+                }),
+                None => spawn_blocking(move || {
+                    ApplicationViewModel::action_add_collage(viewmodel, name, collage)
+                }),
+            };
+            self.collage_editor = None;
+        }
+    }
+
+    /// Draws smart playlist editor, which creates new playlist or changes existing one.
+    fn ui_draw_smart_editor(
+        &mut self,
+        ui: &mut Ui,
+        collections: &[CollectionCache],
+        sources: &[FolderSource],
+    ) {
+        let editor = match &mut self.smart_editor {
+            Some(e) => e,
+            None => return,
+        };
+        let collection_index = match editor.collection_id {
+            Some(id) => match collections.iter().position(|c| c.uuid == id) {
+                Some(i) => Some(i),
+                None => {
+                    self.smart_editor = None;
+                    return;
+                }
+            },
+            None => None,
+        };
+
+        Grid::new("smart_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Name");
+            ui.text_edit_singleline(&mut editor.name);
+            ui.end_row();
+
+            ui.label("Tags")
+                .on_hover_text("Words and \"#tag\", combined with AND, OR and NOT");
+            ui.add(TextEdit::singleline(&mut editor.tags).hint_text("All Wallpapers"));
+            ui.end_row();
+
+            ui.label("Aspect Ratio")
+                .on_hover_text("Width divided by height, like 1.78 for 16:9");
+            ui_optional(
+                ui,
+                &mut editor.query.aspect_ratio,
+                [1.5, 2.0],
+                |ui, [min, max]| {
+                    ui.add(DragValue::new(min).clamp_range(0.1..=10.0).speed(0.01));
+                    ui.label("to");
+                    ui.add(DragValue::new(max).clamp_range(0.1..=10.0).speed(0.01));
+                },
+            );
+            ui.end_row();
+
+            ui.label("Minimum Size");
+            ui_optional(
+                ui,
+                &mut editor.query.min_resolution,
+                [1920, 1080],
+                |ui, [width, height]| {
+                    ui.add(DragValue::new(width).prefix("Width: "));
+                    ui.add(DragValue::new(height).prefix("Height: "));
+                },
+            );
+            ui.end_row();
+
+            ui.label("Minimum Rating");
+            ui.add(Slider::new(&mut editor.query.min_rating, 0..=5));
+            ui.end_row();
+
+            ui.label("Source Folder");
+            let selected_text = sources
+                .iter()
+                .find(|s| Some(s.id()) == editor.query.source)
+                .map(|s| s.path().display().to_string())
+                .unwrap_or_else(|| "Any".into());
+            ComboBox::from_id_source("smart_source")
+                .selected_text(selected_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut editor.query.source, None, "Any");
+                    for source in sources {
+                        ui.selectable_value(
+                            &mut editor.query.source,
+                            Some(source.id()),
+                            source.path().display().to_string(),
+                        );
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Added Within");
+            ui_optional(ui, &mut editor.query.added_within_days, 7, |ui, days| {
+                ui.add(DragValue::new(days).clamp_range(1..=3650).suffix(" days"));
+            });
+            ui.end_row();
+
+            ui.label("Date Taken")
+                .on_hover_text("From EXIF, like \"2021\", \"2021-06\" or \"2021-06-12\"");
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut editor.taken_from)
+                        .hint_text("Any")
+                        .desired_width(80.0),
+                );
+                ui.label("to");
+                ui.add(
+                    TextEdit::singleline(&mut editor.taken_until)
+                        .hint_text("Any")
+                        .desired_width(80.0),
+                );
+            });
+            ui.end_row();
+
+            ui.label("Never Shown");
+            ui.checkbox(&mut editor.query.never_shown, "");
+            ui.end_row();
+        });
+
+        ui.separator();
+        let tags = editor.tags.parse::<TagQuery>();
+        if let Err(e) = &tags {
+            ui.colored_label(Color32::RED, e.to_string());
+        }
+        let label = match collection_index {
+            Some(_) => "Apply",
+            None => "Create",
+        };
+        let valid = !editor.name.trim().is_empty() && tags.is_ok();
+        if ui.add_enabled(valid, Button::new(label)).clicked() {
+            let taken_bound = |text: &str| Some(text.trim().to_string()).filter(|t| !t.is_empty());
+            let query = SmartQuery {
+                tags: tags.unwrap_or_default(),
+                taken_from: taken_bound(&editor.taken_from),
+                taken_until: taken_bound(&editor.taken_until),
+                ..editor.query.clone()
+            };
+            let name = editor.name.trim().to_string();
+            let viewmodel = self.viewmodel.clone();
+            match collection_index {
+                Some(i) => spawn_blocking(move || {
+                    ApplicationViewModel::action_set_smart_playlist(viewmodel, i, name, query)
+                }),
+                None => spawn_blocking(move || {
+                    ApplicationViewModel::action_perform_collection(
+                        viewmodel,
+                        CollectionOperation::AddSmart(name, query),
+                    )
+                }),
+            };
+            self.smart_editor = None;
+        }
+    }
+
+    /// Draws schedule rules editor.
+    fn ui_draw_schedules(
+        &mut self,
+        ui: &mut Ui,
+        schedules: &[ScheduleRule],
+        monitors: &[MonitorCache],
+        wallpapers: &[WallpaperCache],
+    ) {
+        ui.label("Rules are evaluated from top, and the first active one is used.");
+        if ui.button("Add Rule").clicked() {
+            let rule = ScheduleRule::new(format!("Rule {}", schedules.len() + 1));
+            self.perform_schedule(ScheduleOperation::Add(rule));
+        }
+        ui.separator();
+
+        ScrollArea::vertical().show(ui, |ui| {
+            for (i, rule) in schedules.iter().enumerate() {
+                let mut edited = rule.clone();
+                CollapsingHeader::new(&rule.name)
+                    .id_source(format!("schedule_rule_{i}"))
+                    .show(ui, |ui| {
+                        Grid::new(format!("schedule_grid_{i}"))
+                            .num_columns(2)
+                            .show(ui, |ui| {
+                                ui.label("Name");
+                                let mut name = match self.schedule_name_drafts.get(&i) {
+                                    Some(n) => n.clone(),
+                                    None => rule.name.clone(),
+                                };
+                                let response = ui.text_edit_singleline(&mut name);
+                                if response.lost_focus() {
+                                    self.schedule_name_drafts.remove(&i);
+                                    edited.name = name;
+                                } else if response.has_focus() {
+                                    self.schedule_name_drafts.insert(i, name);
+                                }
+                                ui.end_row();
+
+                                ui.label("Enabled");
+                                ui.checkbox(&mut edited.enabled, "");
+                                ui.end_row();
+
+                                ui.label("Time")
+                                    .on_hover_text("End earlier than start continues to next day");
+                                ui.horizontal(|ui| {
+                                    ui_clock_time(ui, &mut edited.start);
+                                    ui.label("to");
+                                    ui_clock_time(ui, &mut edited.end);
+                                });
+                                ui.end_row();
+
+                                ui.label("Days").on_hover_text("None means every day");
+                                ui.horizontal_wrapped(|ui| {
+                                    for day in Day::ALL {
+                                        ui_toggle_member(ui, &mut edited.days, *day, day.label());
+                                    }
+                                });
+                                ui.end_row();
+
+                                ui.label("Monitors")
+                                    .on_hover_text("None means all monitors");
+                                ui.horizontal_wrapped(|ui| {
+                                    for monitor in monitors {
+                                        ui_toggle_member(
+                                            ui,
+                                            &mut edited.monitors,
+                                            monitor.id.clone(),
+                                            &monitor.name,
+                                        );
+                                    }
+                                });
+                                ui.end_row();
+
+                                ui.label("Filter")
+                                    .on_hover_text("Shows only wallpapers matching the query");
+                                if let Some(query) = ui_query_edit(
+                                    ui,
+                                    &mut self.schedule_query_drafts,
+                                    i,
+                                    &rule.query,
+                                ) {
+                                    edited.query = query;
+                                }
+                                ui.end_row();
+                            });
+
+                        let selected_count = edited.wallpapers.len();
+                        CollapsingHeader::new(format!("Wallpapers ({selected_count})"))
+                            .id_source(format!("schedule_wallpapers_{i}"))
+                            .show(ui, |ui| {
+                                ui.label("None means whole library.");
+                                for wallpaper in wallpapers {
+                                    ui_toggle_member(
+                                        ui,
+                                        &mut edited.wallpapers,
+                                        wallpaper.uuid,
+                                        &wallpaper.filename,
+                                    );
+                                }
+                            });
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Move Up").clicked() {
+                                self.perform_schedule(ScheduleOperation::MoveUp(i));
+                            }
+                            if ui.button("Move Down").clicked() {
+                                self.perform_schedule(ScheduleOperation::MoveDown(i));
+                            }
+                            if ui.button("Remove").clicked() {
+                                self.schedule_name_drafts.clear();
+                                self.schedule_query_drafts.clear();
+                                self.perform_schedule(ScheduleOperation::Remove(i));
+                            }
+                        });
+                    });
+
+                if edited != *rule {
+                    self.perform_schedule(ScheduleOperation::Replace(i, edited));
+                }
+            }
+        });
+    }
+
+    /// Draw an item of wallpaper image list.
+    /// Draws wallpaper list items, only ones `visible` returns `true`.
+    /// Members of current collection are drawn in its order if selected.
+    fn ui_draw_image_items(
+        &mut self,
+        ui: &mut Ui,
+        wallpapers: &[WallpaperCache],
+        collections: &[CollectionCache],
+        visible: impl Fn(&WallpaperCache) -> bool,
+    ) {
+        let left_center_layout =
+            Layout::centered_and_justified(Direction::TopDown).with_cross_align(Align::LEFT);
+        let head_style = TextFormat {
+            font_id: TextStyle::Heading.resolve(ui.style()),
+            color: Color32::WHITE,
+            ..Default::default()
+        };
+        let prop_style = TextFormat {
+            font_id: TextStyle::Body.resolve(ui.style()),
+            ..Default::default()
+        };
+        let thumbnail_size = UiVec2::splat(100.0);
+        let frame_size = UiVec2::splat(32.0);
+
+        let current_collection = collections
+            .iter()
+            .find(|c| Some(c.uuid) == self.current_collection);
+        let order: Vec<_> = match current_collection {
+            Some(c) => c
+                .members
+                .iter()
+                .filter_map(|id| wallpapers.iter().position(|w| w.uuid == *id))
+                .collect(),
+            None => (0..wallpapers.len()).collect(),
+        };
+
+        let mut open_editor = None;
+        for i in order {
+            let wallpaper = &wallpapers[i];
+            if !visible(wallpaper) {
+                continue;
+            }
+            let (thumbnails, size_text) = match self.wallpaper_cache.get(&wallpaper.uuid) {
+                Some(c) => (
+                    &c.thumbnails[..],
+                    format!("Size: {}x{}\n", c.size.x, c.size.y),
+                ),
+                None => (&[][..], "Size: Unknown\n".into()),
+            };
+
+            let inner_response = ui.horizontal(|ui| {
+                match thumbnails.first() {
+                    Some(t) => {
+                        ui.image(t.id(), thumbnail_size);
+                    }
+                    None => {
+                        ui.allocate_painter(thumbnail_size, Sense::hover());
+                    }
+                }
+
+                let mut text = LayoutJob::default();
+                text.append(
+                    &format!("{}\n", wallpaper.filename),
+                    0.0,
+                    head_style.clone(),
+                );
+                match &wallpaper.collage {
+                    Some(c) => text.append(
+                        &format!(
+                            "Layout: {}\n{} Images",
+                            c.collage.layout.label(),
+                            c.members.len()
+                        ),
+                        0.0,
+                        prop_style.clone(),
+                    ),
+                    None => {
+                        text.append(&size_text, 0.0, prop_style.clone());
+                        text.append(
+                            &format!("Fitting: {:?}", wallpaper.fitting),
+                            0.0,
+                            prop_style.clone(),
+                        );
+                    }
+                }
+                if !wallpaper.variants.is_empty() {
+                    let applied: Vec<_> = wallpaper
+                        .applied_renditions
+                        .iter()
+                        .map(|(monitor, rendition)| match rendition {
+                            0 => format!("{monitor}: Primary"),
+                            r => {
+                                let size = wallpaper.variants[r - 1].1;
+                                format!("{monitor}: {}x{}", size.x, size.y)
+                            }
+                        })
+                        .collect();
+                    let mut variants_text = format!("\n{} Variants", wallpaper.variants.len());
+                    if !applied.is_empty() {
+                        variants_text.push_str(&format!(" ({})", applied.join(", ")));
+                    }
+                    text.append(&variants_text, 0.0, prop_style.clone());
+                }
+                if wallpaper.rating > 0 {
+                    let rating = format!("\nRating: {}/5", wallpaper.rating);
+                    text.append(&rating, 0.0, prop_style.clone());
+                }
+                if !wallpaper.tags.is_empty() {
+                    let tags: Vec<_> = wallpaper.tags.iter().map(|t| format!("#{t}")).collect();
+                    text.append(&format!("\n{}", tags.join(" ")), 0.0, prop_style.clone());
+                }
+
+                match wallpaper.kind {
+                    WallpaperKind::Image | WallpaperKind::Collage => {
+                        ui.with_layout(left_center_layout, |ui| {
+                            ui.label(text);
+                        });
+                    }
+                    WallpaperKind::Dynamic => {
+                        text.append(
+                            &format!(", {} Frames", wallpaper.images.len()),
+                            0.0,
+                            prop_style.clone(),
+                        );
+                        ui.vertical(|ui| {
+                            ui.label(text);
+                            ui.horizontal_wrapped(|ui| {
+                                for thumbnail in thumbnails {
+                                    ui.image(thumbnail.id(), frame_size);
+                                }
+                            });
+                        });
+                    }
+                }
+            });
+
+            let is_selected = self.selected_wallpapers.contains(&wallpaper.uuid);
+            if is_selected {
+                ui.painter().rect_stroke(
+                    inner_response.response.rect.expand(2.0),
+                    2.0,
+                    ui.visuals().selection.stroke,
+                );
+            }
+
+            let response = ui
+                .interact(
+                    inner_response.response.rect,
+                    Id::new(format!("wallpaper_item_{i}")),
+                    Sense::click(),
+                )
+                .context_menu(|ui| {
+                    let is_collage = wallpaper.kind == WallpaperKind::Collage;
+                    if let Some(preview) = &wallpaper.collage {
+                        if ui.button("Edit Collage").clicked() {
+                            self.collage_editor = Some(CollageEditor {
+                                wallpaper_id: Some(wallpaper.uuid),
+                                name: wallpaper.filename.clone(),
+                                collage: preview.collage.clone(),
+                            });
+                            ui.close_menu();
+                        }
+                    }
+
+                    if !is_collage {
+                        let mut selected_fitting = wallpaper.fitting;
+                        ui.menu_button("Change Fitting", |ui| {
+                            ui.selectable_value(&mut selected_fitting, Fitting::Span, "Span");
+                            ui.selectable_value(&mut selected_fitting, Fitting::Cover, "Cover");
+                            ui.selectable_value(&mut selected_fitting, Fitting::Contain, "Contain");
+                            ui.selectable_value(&mut selected_fitting, Fitting::Stretch, "Stretch");
+                            ui.selectable_value(&mut selected_fitting, Fitting::Tile, "Tile");
+                            ui.selectable_value(&mut selected_fitting, Fitting::Center, "Center");
+                        });
+                        if selected_fitting != wallpaper.fitting {
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_perform_wallpaper(
+                                    viewmodel,
+                                    i,
+                                    WallpaperListOperation::SetFitting(selected_fitting),
+                                )
+                            });
+                            ui.close_menu();
+                        }
+
+                        let mut selected_background = wallpaper.background;
+                        ui.menu_button("Change Background", |ui| {
+                            let solid = matches!(selected_background, Background::Solid(_));
+                            if ui.selectable_label(solid, "Solid Color").clicked() && !solid {
+                                selected_background = Background::default();
+                            }
+                            ui.selectable_value(
+                                &mut selected_background,
+                                Background::Dominant,
+                                "Dominant Color",
+                            );
+                            ui.selectable_value(
+                                &mut selected_background,
+                                Background::Edge,
+                                "Edge Color",
+                            );
+                            ui.selectable_value(
+                                &mut selected_background,
+                                Background::Blurred,
+                                "Blurred",
+                            );
+                            if let Background::Solid(mut color) = selected_background {
+                                ui.separator();
+                                if ui.color_edit_button_srgb(&mut color).changed() {
+                                    selected_background = Background::Solid(color);
+                                }
+                            }
+                        });
+                        if selected_background != wallpaper.background {
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_perform_wallpaper(
+                                    viewmodel,
+                                    i,
+                                    WallpaperListOperation::SetBackground(selected_background),
+                                )
+                            });
+                        }
+                    }
+
+                    let draft = self
+                        .adjustment_drafts
+                        .entry(wallpaper.uuid)
+                        .or_insert(wallpaper.adjustments);
+                    let mut committed = false;
+                    ui.menu_button("Adjust Image", |ui| {
+                        committed = ui_adjustments(ui, draft, !is_collage);
+                    });
+                    if committed {
+                        let adjustments = *draft;
+                        let viewmodel = self.viewmodel.clone();
                         spawn_blocking(move || {
                             ApplicationViewModel::action_perform_wallpaper(
                                 viewmodel,
                                 i,
-                                WallpaperListOperation::MoveUp,
+                                WallpaperListOperation::SetAdjustments(adjustments),
                             )
                         });
-                        ui.close_menu();
                     }
-                    if ui.button("Move Down").clicked() {
+
+                    let tags_draft = self
+                        .tag_drafts
+                        .entry(wallpaper.uuid)
+                        .or_insert_with(|| wallpaper.tags.join(", "));
+                    ui.menu_button("Edit Tags", |ui| {
+                        ui.add(TextEdit::singleline(tags_draft).hint_text("Comma-separated tags"));
+                        if ui.button("Apply").clicked() {
+                            let tags = split_tags(tags_draft);
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_perform_wallpaper(
+                                    viewmodel,
+                                    i,
+                                    WallpaperListOperation::SetTags(tags),
+                                )
+                            });
+                            ui.close_menu();
+                        }
+                    });
+
+                    let mut selected_rating = wallpaper.rating;
+                    ui.menu_button("Rating", |ui| {
+                        ui.selectable_value(&mut selected_rating, 0, "Unrated");
+                        for rating in 1..=5 {
+                            ui.selectable_value(
+                                &mut selected_rating,
+                                rating,
+                                format!("{rating}/5"),
+                            );
+                        }
+                    });
+                    if selected_rating != wallpaper.rating {
                         let viewmodel = self.viewmodel.clone();
                         spawn_blocking(move || {
                             ApplicationViewModel::action_perform_wallpaper(
                                 viewmodel,
                                 i,
-                                WallpaperListOperation::MoveDown,
+                                WallpaperListOperation::SetRating(selected_rating),
                             )
                         });
                         ui.close_menu();
                     }
 
+                    // Members of smart playlists are not edited directly
+                    let editable: Vec<_> =
+                        collections.iter().filter(|c| c.query.is_none()).collect();
+                    if !editable.is_empty() {
+                        ui.menu_button("Collections", |ui| {
+                            for collection in editable {
+                                let mut member = collection.members.contains(&wallpaper.uuid);
+                                if ui.checkbox(&mut member, &collection.name).changed() {
+                                    let op = if member {
+                                        WallpaperListOperation::AddToCollection(collection.uuid)
+                                    } else {
+                                        WallpaperListOperation::RemoveFromCollection(
+                                            collection.uuid,
+                                        )
+                                    };
+                                    let viewmodel = self.viewmodel.clone();
+                                    spawn_blocking(move || {
+                                        ApplicationViewModel::action_perform_wallpaper(
+                                            viewmodel, i, op,
+                                        )
+                                    });
+                                }
+                            }
+                        });
+                    }
+
+                    if !is_collage && ui.button("Edit Focal Point").clicked() {
+                        open_editor = Some(i);
+                        ui.close_menu();
+                    }
+
+                    if wallpaper.kind == WallpaperKind::Image {
+                        ui.separator();
+                        if ui.button("Add Variant").clicked() {
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_add_variant(viewmodel, i)
+                            });
+                            ui.close_menu();
+                        }
+                        ui.add_enabled_ui(!wallpaper.variants.is_empty(), |ui| {
+                            ui.menu_button("Remove Variant", |ui| {
+                                for (v, (filename, size)) in wallpaper.variants.iter().enumerate() {
+                                    let name = Path::new(filename)
+                                        .file_name()
+                                        .map(|n| n.to_string_lossy().to_string())
+                                        .unwrap_or_default();
+                                    let label = format!("{name} ({}x{})", size.x, size.y);
+                                    if ui.button(label).clicked() {
+                                        let viewmodel = self.viewmodel.clone();
+                                        spawn_blocking(move || {
+                                            ApplicationViewModel::action_perform_wallpaper(
+                                                viewmodel,
+                                                i,
+                                                WallpaperListOperation::RemoveVariant(v),
+                                            )
+                                        });
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        });
+                    }
+
                     ui.separator();
 
-                    if ui.button("Remove").clicked() {
+                    let collection_id = current_collection
+                        .filter(|c| c.query.is_none())
+                        .map(|c| c.uuid);
+                    if ui.button("Move Up").clicked() {
+                        let op = match collection_id {
+                            Some(c) => WallpaperListOperation::MoveUpInCollection(c),
+                            None => WallpaperListOperation::MoveUp,
+                        };
+                        let viewmodel = self.viewmodel.clone();
+                        spawn_blocking(move || {
+                            ApplicationViewModel::action_perform_wallpaper(viewmodel, i, op)
+                        });
+                        ui.close_menu();
+                    }
+                    if ui.button("Move Down").clicked() {
+                        let op = match collection_id {
+                            Some(c) => WallpaperListOperation::MoveDownInCollection(c),
+                            None => WallpaperListOperation::MoveDown,
+                        };
+                        let viewmodel = self.viewmodel.clone();
+                        spawn_blocking(move || {
+                            ApplicationViewModel::action_perform_wallpaper(viewmodel, i, op)
+                        });
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if let Some(c) = collection_id {
+                        if ui.button("Remove from Collection").clicked() {
+                            let viewmodel = self.viewmodel.clone();
+                            spawn_blocking(move || {
+                                ApplicationViewModel::action_perform_wallpaper(
+                                    viewmodel,
+                                    i,
+                                    WallpaperListOperation::RemoveFromCollection(c),
+                                )
+                            });
+                            ui.close_menu();
+                        }
+                    }
+
+                    let remove_text = match collection_id {
+                        Some(_) => "Remove from Library",
+                        None => "Remove",
+                    };
+                    if ui.button(remove_text).clicked() {
                         let viewmodel = self.viewmodel.clone();
                         spawn_blocking(move || {
                             ApplicationViewModel::action_perform_wallpaper(
@@ -428,6 +2210,13 @@ impl ApplicationView {
                     }
                 });
 
+            if response.clicked() && ui.input().modifiers.command {
+                if is_selected {
+                    self.selected_wallpapers.remove(&wallpaper.uuid);
+                } else {
+                    self.selected_wallpapers.insert(wallpaper.uuid);
+                }
+            }
             if response.double_clicked() {
                 let selected = self.selected_monitor_index.expect("Should have monitor");
                 let model = self.viewmodel.clone();
@@ -436,11 +2225,190 @@ impl ApplicationView {
                 });
             }
         }
+
+        if let Some(i) = open_editor {
+            self.open_focus_editor(&wallpapers[i]);
+        }
+
+        // Drafts are dropped once reflected
+        self.adjustment_drafts.retain(|id, draft| {
+            wallpapers
+                .iter()
+                .any(|w| w.uuid == *id && w.adjustments != *draft)
+        });
+        self.tag_drafts.retain(|id, draft| {
+            wallpapers
+                .iter()
+                .any(|w| w.uuid == *id && w.tags.join(", ") != *draft)
+        });
+    }
+
+    /// Opens focal point editor for the wallpaper, and loads its image in background.
+    fn open_focus_editor(&mut self, wallpaper: &WallpaperCache) {
+        let texture = Arc::new(Mutex::new(None));
+        let detected_focal_point = self
+            .wallpaper_cache
+            .get(&wallpaper.uuid)
+            .map(|c| c.detected_focal_point)
+            .unwrap_or_default();
+        self.focus_editor = Some(FocusEditor {
+            wallpaper_id: wallpaper.uuid,
+            focal_point: wallpaper.focal_point.unwrap_or(detected_focal_point),
+            detected_focal_point,
+            manual: wallpaper.focal_point.is_some(),
+            texture: texture.clone(),
+        });
+
+        // Dynamic wallpapers are edited with the first frame
+        let filename = match wallpaper.images.first() {
+            Some(f) => f.clone(),
+            None => return,
+        };
+        let ctx = self.context.clone().expect("Context must be attached");
+        let event_proxy = self.event_proxy.clone();
+        let adjustments = wallpaper.adjustments;
+        spawn_blocking(move || {
+            let ui_image = load_preview(&filename, adjustments);
+            *texture.lock() = Some(ctx.load_texture(&filename, ui_image));
+            if let Some(event_proxy) = event_proxy {
+                event_proxy.request_repaint();
+            }
+        });
+    }
+
+    /// Draws focal point editor window with the visible area on the monitor.
+    fn ui_draw_focus_editor(
+        &mut self,
+        ctx: &Context,
+        wallpapers: &[WallpaperCache],
+        monitor: &MonitorCache,
+    ) {
+        let editor = match &mut self.focus_editor {
+            Some(e) => e,
+            None => return,
+        };
+        let wallpaper_index = match wallpapers
+            .iter()
+            .position(|w| w.uuid == editor.wallpaper_id)
+        {
+            Some(i) => i,
+            None => {
+                self.focus_editor = None;
+                return;
+            }
+        };
+
+        let mut open = true;
+        let mut committed = None;
+        UiWindow::new("Focal Point")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("Visible area on {}", monitor.name));
+                let texture = match editor.texture.lock().clone() {
+                    Some(t) => t,
+                    None => {
+                        ui.label("Loading...");
+                        return;
+                    }
+                };
+                let [width, height] = texture.size();
+                let display_size = UiVec2::new(width as f32, height as f32)
+                    * (FOCUS_EDITOR_SIZE / width.max(height).max(1) as f32);
+                let rect = ui.image(texture.id(), display_size).rect;
+
+                let response = ui.interact(rect, Id::new("focus_editor"), Sense::click_and_drag());
+                if let Some(pointer) = response.interact_pointer_pos() {
+                    let position = (pointer - rect.min) / rect.size();
+                    editor.focal_point = FocalPoint {
+                        x: position.x.clamp(0.0, 1.0),
+                        y: position.y.clamp(0.0, 1.0),
+                    };
+                    editor.manual = true;
+                }
+                if response.clicked() || response.drag_released() {
+                    committed = Some(Some(editor.focal_point));
+                }
+
+                // Shades cropped area
+                let area = cover_area(
+                    Vec2::new(width, height).as_(),
+                    monitor.size.as_(),
+                    editor.focal_point,
+                );
+                let visible = Rect::from_min_max(
+                    rect.min + UiVec2::new(area.min.x, area.min.y) * rect.size(),
+                    rect.min + UiVec2::new(area.max.x, area.max.y) * rect.size(),
+                );
+                let cropped = [
+                    Rect::from_min_max(rect.min, UiPos2::new(rect.max.x, visible.min.y)),
+                    Rect::from_min_max(UiPos2::new(rect.min.x, visible.max.y), rect.max),
+                    Rect::from_min_max(
+                        UiPos2::new(rect.min.x, visible.min.y),
+                        UiPos2::new(visible.min.x, visible.max.y),
+                    ),
+                    Rect::from_min_max(
+                        UiPos2::new(visible.max.x, visible.min.y),
+                        UiPos2::new(rect.max.x, visible.max.y),
+                    ),
+                ];
+                let painter = ui.painter();
+                let stroke = Stroke::new(2.0, Color32::YELLOW);
+                for area in cropped {
+                    painter.rect_filled(area, 0.0, Color32::from_black_alpha(160));
+                }
+                painter.rect_stroke(visible, 0.0, stroke);
+                let focus = UiVec2::new(editor.focal_point.x, editor.focal_point.y);
+                painter.circle_stroke(rect.min + focus * rect.size(), 6.0, stroke);
+
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "X: {:.2}, Y: {:.2}{}",
+                        editor.focal_point.x,
+                        editor.focal_point.y,
+                        if editor.manual { "" } else { " (Detected)" }
+                    ));
+                    if ui
+                        .add_enabled(editor.manual, Button::new("Use Detected"))
+                        .clicked()
+                    {
+                        editor.focal_point = editor.detected_focal_point;
+                        editor.manual = false;
+                        committed = Some(None);
+                    }
+                });
+            });
+
+        if !open {
+            self.focus_editor = None;
+        }
+        if let Some(focal_point) = committed {
+            let viewmodel = self.viewmodel.clone();
+            spawn_blocking(move || {
+                ApplicationViewModel::action_perform_wallpaper(
+                    viewmodel,
+                    wallpaper_index,
+                    WallpaperListOperation::SetFocalPoint(focal_point),
+                )
+            });
+        }
     }
 }
 
 /// UI Actions.
 impl ApplicationView {
+    /// Performs schedule rules operation in background.
+    fn perform_schedule(&self, op: ScheduleOperation) {
+        let viewmodel = self.viewmodel.clone();
+        spawn_blocking(move || ApplicationViewModel::action_perform_schedule(viewmodel, op));
+    }
+
+    /// Performs collections operation in background.
+    fn perform_collection(&self, op: CollectionOperation) {
+        let viewmodel = self.viewmodel.clone();
+        spawn_blocking(move || ApplicationViewModel::action_perform_collection(viewmodel, op));
+    }
+
     /// Updates thumbnail and wallpaper size cache.
     fn update_texture_cache(this: Arc<Mutex<ApplicationView>>) -> Result<()> {
         let (mut active_files, unmet_files, ctx) = {
@@ -455,68 +2423,364 @@ impl ApplicationView {
             let mut unmet_files = HashMap::new();
             let mut active_files = HashSet::new();
             for wallpaper in &viewmodel.wallpapers {
-                if !view.wallpaper_cache.contains_key(&wallpaper.uuid) {
-                    unmet_files.insert(wallpaper.uuid, wallpaper.filename.clone());
+                let is_met = view.wallpaper_cache.get(&wallpaper.uuid).is_some_and(|c| {
+                    c.adjustments == wallpaper.adjustments && c.collage == wallpaper.collage
+                });
+                if !is_met {
+                    unmet_files.insert(
+                        wallpaper.uuid,
+                        (
+                            wallpaper.images.clone(),
+                            wallpaper.adjustments,
+                            wallpaper.collage.clone(),
+                        ),
+                    );
                 }
                 active_files.insert(wallpaper.uuid);
             }
             (active_files, unmet_files, ctx)
         };
 
-        // Load unmet files
+        // Load unmet files, detecting focal points as well
         let mut newly_loaded = HashMap::new();
-        for (wallpaper_id, filename) in unmet_files {
-            info!("Loading {filename}");
-            let (mut resized_image, original_size) = match image::open(&filename) {
-                Ok(i) => {
-                    let size = Vec2::new(i.width(), i.height());
-                    let resized_image = i.resize(512, 512, FilterType::Gaussian);
-                    (resized_image, size)
+        let mut detected = vec![];
+        let mut sizes = vec![];
+        for (wallpaper_id, (filenames, adjustments, collage)) in unmet_files {
+            let mut cache = TextureCache {
+                thumbnails: vec![],
+                size: Vec2::new(0, 0),
+                detected_focal_point: FocalPoint::default(),
+                adjustments,
+                collage: collage.clone(),
+            };
+            if let Some(preview) = collage {
+                let ui_image = load_collage_thumbnail(&preview, adjustments);
+                let name = format!("collage_{wallpaper_id}");
+                cache.thumbnails.push(ctx.load_texture(name, ui_image));
+            }
+            for (i, filename) in filenames.iter().enumerate() {
+                let (ui_image, size, focal_point) = load_thumbnail(filename, adjustments);
+                if i == 0 {
+                    cache.size = size;
+                    if size.x > 0 && size.y > 0 {
+                        sizes.push((wallpaper_id, size));
+                    }
+                    cache.detected_focal_point =
+                        adjustments.orient_point(focal_point.unwrap_or_default());
                 }
-                Err(e) => {
-                    error!("Image load error: {e}");
-                    let placeholder = DynamicImage::ImageRgba8(ImageBuffer::new(128, 128));
-                    (placeholder, Vec2::new(0, 0))
+                if let Some(point) = focal_point {
+                    match content_hash(Path::new(filename)) {
+                        Ok(hash) => detected.push((hash, point)),
+                        Err(e) => error!("Failed to hash {filename}: {e}"),
+                    }
                 }
-            };
-            let rect_size = resized_image.width().min(resized_image.height());
-            resized_image = resized_image.crop(
-                (resized_image.width() - rect_size) / 2,
-                (resized_image.height() - rect_size) / 2,
-                rect_size,
-                rect_size,
-            );
-
-            let ui_image = ColorImage::from_rgba_unmultiplied(
-                [rect_size as _, rect_size as _],
-                &resized_image.to_rgba8(),
-            );
-            let texture_handle = ctx.load_texture(&filename, ui_image);
-            newly_loaded.insert(wallpaper_id, (texture_handle, original_size));
+                cache.thumbnails.push(ctx.load_texture(filename, ui_image));
+            }
+            newly_loaded.insert(wallpaper_id, cache);
             active_files.insert(wallpaper_id);
         }
 
         // Propagate change
         let mut view = this.lock();
-        view.wallpaper_cache.extend(newly_loaded.into_iter());
+        view.wallpaper_cache.extend(newly_loaded);
         view.wallpaper_cache.retain(|k, _| active_files.contains(k));
+        if !detected.is_empty() {
+            ApplicationViewModel::action_cache_focal_points(view.viewmodel.clone(), detected);
+        }
+        if !sizes.is_empty() {
+            ApplicationViewModel::action_cache_image_sizes(view.viewmodel.clone(), sizes);
+        }
 
         Ok(())
     }
 
+    /// Requests repaint to reflect changes of ViewModel.
+    fn request_repaint(this: Arc<Mutex<ApplicationView>>) {
+        let view = this.lock();
+        if let Some(event_proxy) = &view.event_proxy {
+            event_proxy.request_repaint();
+        }
+    }
+
     fn update_monitors(this: Arc<Mutex<ApplicationView>>) {
         let mut view = this.lock();
         let viewmodel_ref = view.viewmodel.clone();
         let viewmodel = viewmodel_ref.lock();
 
-        view.selected_monitor_index = if viewmodel.monitors.is_empty() {
-            None
-        } else {
-            Some(0)
+        // Kept while editing settings of the selected monitor
+        let count = viewmodel.monitors.len();
+        view.layout_drafts.clear();
+        view.selected_monitor_index = match view.selected_monitor_index {
+            _ if count == 0 => None,
+            Some(i) if i < count => Some(i),
+            _ => Some(0),
         };
     }
 }
 
+/// Loads an adjusted image as a square thumbnail,
+/// with its original size and focal point detected before adjustments.
+fn load_thumbnail(
+    filename: &str,
+    adjustments: Adjustments,
+) -> (ColorImage, Vec2<u32>, Option<FocalPoint>) {
+    info!("Loading {filename}");
+    let (mut resized_image, original_size, focal_point) = match image::open(filename) {
+        Ok(i) => {
+            let size = Vec2::new(i.width(), i.height());
+            let resized_image = i.resize(512, 512, FilterType::Gaussian);
+            let focal_point = detect_focal_point(&resized_image);
+            (orient(&resized_image, adjustments), size, Some(focal_point))
+        }
+        Err(e) => {
+            error!("Image load error: {e}");
+            let placeholder = DynamicImage::ImageRgba8(ImageBuffer::new(128, 128));
+            (placeholder, Vec2::new(0, 0), None)
+        }
+    };
+    let rect_size = resized_image.width().min(resized_image.height());
+    resized_image = resized_image.crop(
+        (resized_image.width() - rect_size) / 2,
+        (resized_image.height() - rect_size) / 2,
+        rect_size,
+        rect_size,
+    );
+
+    let mut rgba_image = resized_image.to_rgba8();
+    adjust_colors(&mut rgba_image, adjustments);
+    let ui_image =
+        ColorImage::from_rgba_unmultiplied([rect_size as _, rect_size as _], &rgba_image);
+    (ui_image, original_size, focal_point)
+}
+
+/// Composes members of a collage in 16:9, and letterboxes it as a square thumbnail.
+fn load_collage_thumbnail(preview: &CollagePreview, adjustments: Adjustments) -> ColorImage {
+    let cells: Vec<_> = preview
+        .members
+        .iter()
+        .filter_map(|member| {
+            info!("Loading {}", member.image);
+            let image = match image::open(&member.image) {
+                Ok(i) => i.resize(512, 512, FilterType::Triangle),
+                Err(e) => {
+                    error!("Image load error: {e}");
+                    return None;
+                }
+            };
+            let focus = member
+                .focal_point
+                .unwrap_or_else(|| member.adjustments.orient_point(detect_focal_point(&image)));
+            Some(CollageCell {
+                image: orient(&image, member.adjustments),
+                focus,
+                adjustments: member.adjustments,
+            })
+        })
+        .collect();
+
+    let (width, height) = COLLAGE_THUMBNAIL_SIZE;
+    let mut composed = compose_collage(&cells, Vec2::new(width, height), &preview.collage);
+    adjust_colors(&mut composed, adjustments);
+    let mut square = RgbaImage::new(width, width);
+    imageops::overlay(&mut square, &composed, 0, ((width - height) / 2) as i64);
+    ColorImage::from_rgba_unmultiplied([width as _, width as _], &square)
+}
+
+/// Loads an adjusted image for preview, keeping its aspect ratio.
+fn load_preview(filename: &str, adjustments: Adjustments) -> ColorImage {
+    info!("Loading {filename}");
+    let image = match image::open(filename) {
+        Ok(i) => orient(&i.resize(1024, 1024, FilterType::Triangle), adjustments),
+        Err(e) => {
+            error!("Image load error: {e}");
+            DynamicImage::ImageRgba8(ImageBuffer::new(128, 128))
+        }
+    };
+    let mut rgba_image = image.to_rgba8();
+    adjust_colors(&mut rgba_image, adjustments);
+    ColorImage::from_rgba_unmultiplied([image.width() as _, image.height() as _], &rgba_image)
+}
+
+/// Draws image adjustments editor, with rotation and flips if `orientable`.
+/// Returns `true` when changes should be committed; sliders do so when released.
+fn ui_adjustments(ui: &mut Ui, adjustments: &mut Adjustments, orientable: bool) -> bool {
+    let mut committed = false;
+    if orientable {
+        ui.horizontal(|ui| {
+            if ui.button("Rotate Left").clicked() {
+                adjustments.rotation = (adjustments.rotation + 3) % 4;
+                committed = true;
+            }
+            if ui.button("Rotate Right").clicked() {
+                adjustments.rotation = (adjustments.rotation + 1) % 4;
+                committed = true;
+            }
+        });
+        committed |= ui
+            .checkbox(&mut adjustments.flip_horizontal, "Flip Horizontally")
+            .changed();
+        committed |= ui
+            .checkbox(&mut adjustments.flip_vertical, "Flip Vertically")
+            .changed();
+    }
+    committed |= ui
+        .checkbox(&mut adjustments.grayscale, "Grayscale")
+        .changed();
+
+    ui.separator();
+    let sliders = [
+        (&mut adjustments.brightness, -1.0..=1.0, "Brightness"),
+        (&mut adjustments.contrast, -1.0..=1.0, "Contrast"),
+        (&mut adjustments.dim, 0.0..=1.0, "Dim"),
+        (&mut adjustments.blur, 0.0..=5.0, "Blur"),
+    ];
+    for (value, range, label) in sliders {
+        let response = ui.add(Slider::new(value, range).text(label));
+        committed |= response.drag_released() || response.lost_focus();
+    }
+
+    ui.separator();
+    if ui.button("Reset Adjustments").clicked() {
+        *adjustments = Adjustments::default();
+        committed = true;
+    }
+    committed
+}
+
+/// Draws hour and minute editor.
+fn ui_clock_time(ui: &mut Ui, time: &mut ClockTime) {
+    let mut hour = time.hour();
+    let mut minute = time.minute();
+    ui.add(DragValue::new(&mut hour).clamp_range(0..=23));
+    ui.label(":");
+    ui.add(DragValue::new(&mut minute).clamp_range(0..=59));
+    if let Ok(t) = ClockTime::new(hour, minute) {
+        *time = t;
+    }
+}
+
+/// Draws a checkbox which toggles whether `list` contains `item`.
+fn ui_toggle_member<T: PartialEq>(ui: &mut Ui, list: &mut Vec<T>, item: T, label: &str) {
+    let mut checked = list.contains(&item);
+    if ui.checkbox(&mut checked, label).changed() {
+        if checked {
+            list.push(item);
+        } else {
+            list.retain(|i| *i != item);
+        }
+    }
+}
+
+/// Draws a checkbox which enables the value, and its editor while enabled.
+fn ui_optional<T>(
+    ui: &mut Ui,
+    value: &mut Option<T>,
+    default: T,
+    add_editor: impl FnOnce(&mut Ui, &mut T),
+) {
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, "").changed() {
+            *value = if enabled { Some(default) } else { None };
+        }
+        if let Some(v) = value {
+            add_editor(ui, v);
+        }
+    });
+}
+
+/// Draws tag query editor, and returns edited query once committed.
+/// Text being edited or failed to parse is kept in `drafts`.
+fn ui_query_edit(
+    ui: &mut Ui,
+    drafts: &mut HashMap<usize, String>,
+    key: usize,
+    query: &TagQuery,
+) -> Option<TagQuery> {
+    let mut text = match drafts.get(&key) {
+        Some(t) => t.clone(),
+        None => query.to_string(),
+    };
+    ui.horizontal(|ui| {
+        let response = ui.add(TextEdit::singleline(&mut text).hint_text("All Wallpapers"));
+        let parsed = text.parse::<TagQuery>();
+        if let Err(e) = &parsed {
+            ui.colored_label(Color32::RED, e.to_string());
+        }
+        match parsed {
+            Ok(parsed) if response.lost_focus() => {
+                drafts.remove(&key);
+                (parsed != *query).then_some(parsed)
+            }
+            _ => {
+                if response.has_focus() || drafts.contains_key(&key) {
+                    drafts.insert(key, text);
+                }
+                None
+            }
+        }
+    })
+    .inner
+}
+
+/// Draws a hint over the window while files are dragged onto it.
+fn ui_draw_drop_hint(ctx: &Context) {
+    let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("drop_hint")));
+    let rect = ctx.input().screen_rect();
+    painter.rect_filled(rect, 0.0, Color32::from_black_alpha(160));
+    painter.text(
+        rect.center(),
+        Align2::CENTER_CENTER,
+        "Drop to add, or onto a monitor to apply",
+        TextStyle::Heading.resolve(&ctx.style()),
+        Color32::WHITE,
+    );
+}
+
+/// Splits comma-separated glob patterns, keeping commas in `{a,b}` alternations.
+fn split_patterns(text: &str) -> Vec<String> {
+    let mut patterns = vec![];
+    let mut current = String::new();
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                patterns.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => (),
+        }
+        current.push(c);
+    }
+    patterns.push(current);
+    patterns
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// Splits comma-separated tags. Normalized by the model.
+fn split_tags(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Formats duration as `m:ss` or `h:mm:ss`.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
 /// User event type for `Application`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ApplicationWindowEvent {
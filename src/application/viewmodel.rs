@@ -1,23 +1,37 @@
-pub use crate::application::model::WallpaperListOperation;
+pub use crate::application::model::{
+    CollectionOperation, ScheduleOperation, WallpaperListOperation,
+};
 
 use crate::{
     application::{
+        dynamic::DynamicManifest,
         model::{Application, ApplicationEvent},
-        Fitting, Wallpaper,
+        span::{arrange, bounds, Placement},
+        Adjustments, AspectMatching, Background, Collage, Collection, Crossing, Fitting,
+        FocalPoint, FolderSource, MonitorLayout, Overlay, RotationOrder, RotationStatus,
+        ScheduleRule, ScheduleSource, SmartQuery, SolarSettings, TagQuery, Wallpaper,
+        WallpaperKind,
     },
+    backend::Monitor,
+    cli::{perform_command, Command},
     mvvm::{EventManager, Observable, Subscription},
-    windows::Monitor,
 };
 
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use log::{error, info};
 use native_dialog::FileDialog;
 use parking_lot::Mutex;
+use time::OffsetDateTime;
 use tokio::task::spawn_blocking;
 use uuid::Uuid;
-use vek::{Vec2, Vec4};
+use vek::{Aabr, Vec2, Vec4};
 
 pub struct ApplicationViewModel {
     model: Arc<Mutex<Application>>,
@@ -26,6 +40,27 @@ pub struct ApplicationViewModel {
 
     pub monitors: Vec<MonitorCache>,
     pub wallpapers: Vec<WallpaperCache>,
+    pub sources: Vec<FolderSource>,
+    pub collections: Vec<CollectionCache>,
+
+    /// Tags used in the library and the number of wallpapers having each, most used first.
+    pub tags: Vec<(String, usize)>,
+    pub rotations: Vec<Option<RotationStatus>>,
+
+    /// Restriction of rotation for each monitor.
+    pub rotation_queries: Vec<Option<TagQuery>>,
+
+    /// UUID of collection which each monitor rotates through.
+    pub rotation_collections: Vec<Option<Uuid>>,
+    pub schedules: Vec<ScheduleRule>,
+    pub scheduled_sources: Vec<Option<String>>,
+    pub solar: SolarSettings,
+    pub solar_events: String,
+    pub overlays: Vec<Overlay>,
+    pub aspect_matching: AspectMatching,
+
+    /// Frame images of dynamic wallpapers, so that manifests are read only once.
+    frame_images: HashMap<Uuid, Vec<String>>,
 }
 
 impl ApplicationViewModel {
@@ -38,6 +73,19 @@ impl ApplicationViewModel {
 
             monitors: vec![],
             wallpapers: vec![],
+            sources: vec![],
+            collections: vec![],
+            tags: vec![],
+            rotations: vec![],
+            rotation_queries: vec![],
+            rotation_collections: vec![],
+            schedules: vec![],
+            scheduled_sources: vec![],
+            solar: SolarSettings::default(),
+            solar_events: String::new(),
+            overlays: vec![],
+            aspect_matching: AspectMatching::default(),
+            frame_images: HashMap::new(),
         }));
 
         let subscription = ApplicationViewModel::setup_subscribe(model, viewmodel.clone());
@@ -66,6 +114,18 @@ impl ApplicationViewModel {
                 let vm = vm.clone();
                 spawn_blocking(|| ApplicationViewModel::update_wallpapers(vm));
             }
+            ApplicationEvent::RotationUpdated => {
+                let vm = vm.clone();
+                spawn_blocking(|| ApplicationViewModel::update_rotations(vm));
+            }
+            ApplicationEvent::SchedulesUpdated => {
+                let vm = vm.clone();
+                spawn_blocking(|| ApplicationViewModel::update_schedules(vm));
+            }
+            ApplicationEvent::OverlaysUpdated => {
+                let vm = vm.clone();
+                spawn_blocking(|| ApplicationViewModel::update_overlays(vm));
+            }
         })
     }
 }
@@ -78,39 +138,39 @@ impl ApplicationViewModel {
         let mut viewmodel = this.lock();
 
         viewmodel.monitors.clear();
-        let monitors_source = {
+        let (monitors_source, span_groups, layouts) = {
             let model = viewmodel.model.lock();
-            model.monitors().to_vec()
+            let span_groups: Vec<_> = (0..model.monitors().len())
+                .map(|i| model.span_group(i).map(|g| g.to_string()))
+                .collect();
+            let layouts: Vec<_> = (0..model.monitors().len())
+                .map(|i| model.monitor_layout(i))
+                .collect();
+            (model.monitors().to_vec(), span_groups, layouts)
         };
         if monitors_source.is_empty() {
             return;
         }
 
-        let (x_points, y_points): (Vec<_>, Vec<_>) = monitors_source
+        // Previewed in physical arrangement to show bezels and sizes
+        let arranged: Vec<_> = monitors_source
             .iter()
-            .flat_map(|m| {
-                let position = m.position();
-                let size = m.size();
-                [
-                    (position.x, position.y),
-                    (position.x + size.x, position.y + size.y),
-                ]
-            })
-            .unzip();
-
-        let left_all = *x_points.iter().min().expect("No monitor calculated") as f32;
-        let right_all = *x_points.iter().max().expect("No monitor calculated") as f32;
-        let top_all = *y_points.iter().min().expect("No monitor calculated") as f32;
-        let bottom_all = *y_points.iter().max().expect("No monitor calculated") as f32;
-
-        let whole_topleft = Vec2::new(left_all, top_all);
-        let whole_size = Vec2::new(right_all - left_all, bottom_all - top_all);
+            .zip(layouts.iter().copied())
+            .collect();
+        let placements = arrange(&arranged);
+        let whole = bounds(placements.iter().map(|p| p.body));
+
+        let whole_topleft = whole.min.as_::<f32>();
+        let whole_size = Vec2::<f64>::from(whole.size()).as_::<f32>();
         let divider = whole_size.x.max(whole_size.y);
         let whole_offset = (Vec2::new(divider, divider) - whole_size) / 2.0;
 
-        for monitor in monitors_source {
+        for (i, monitor) in monitors_source.iter().enumerate() {
             viewmodel.monitors.push(MonitorCache::new(
-                &monitor,
+                monitor,
+                span_groups[i].clone(),
+                layouts[i],
+                placements[i],
                 whole_topleft,
                 whole_offset,
                 divider,
@@ -125,19 +185,152 @@ impl ApplicationViewModel {
     pub fn update_wallpapers(this: Arc<Mutex<ApplicationViewModel>>) {
         let mut viewmodel = this.lock();
 
-        let wallpapers_source = {
+        let (wallpapers_source, sources, collections, applied_renditions) = {
             let model = viewmodel.model.lock();
-            model.wallpapers().to_vec()
+            let applied_renditions: Vec<_> = (0..model.monitors().len())
+                .filter_map(|i| {
+                    let wallpaper_id = model.wallpapers()[model.assigned_wallpaper(i)?].id();
+                    let name = model.monitors()[i].name().to_string();
+                    Some((wallpaper_id, name, model.applied_rendition(i)?))
+                })
+                .collect();
+            (
+                model.wallpapers().to_vec(),
+                model.sources().to_vec(),
+                model.collections().to_vec(),
+                applied_renditions,
+            )
         };
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let collections = collections
+            .iter()
+            .map(|c| CollectionCache::new(c, &wallpapers_source, now))
+            .collect();
+
+        viewmodel.sources = sources;
+        viewmodel.collections = collections;
+
+        let mut tag_counts: HashMap<&str, usize> = HashMap::new();
+        for tag in wallpapers_source.iter().flat_map(|w| w.tags()) {
+            *tag_counts.entry(tag).or_default() += 1;
+        }
+        let mut tags: Vec<_> = tag_counts
+            .into_iter()
+            .map(|(tag, count)| (tag.to_string(), count))
+            .collect();
+        tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        viewmodel.tags = tags;
+
+        let ids: HashSet<_> = wallpapers_source.iter().map(|w| w.id()).collect();
+        viewmodel.frame_images.retain(|id, _| ids.contains(id));
+        for wallpaper in &wallpapers_source {
+            if wallpaper.kind() == WallpaperKind::Dynamic {
+                viewmodel
+                    .frame_images
+                    .entry(wallpaper.id())
+                    .or_insert_with(|| load_frame_images(wallpaper));
+            }
+        }
 
         viewmodel.wallpapers.clear();
-        for wallpaper in wallpapers_source {
-            let wv = WallpaperCache::new(&wallpaper);
+        for wallpaper in &wallpapers_source {
+            let mut wv =
+                WallpaperCache::new(wallpaper, &wallpapers_source, &viewmodel.frame_images);
+            wv.applied_renditions = applied_renditions
+                .iter()
+                .filter(|(id, _, _)| *id == wallpaper.id())
+                .map(|(_, name, rendition)| (name.clone(), *rendition))
+                .collect();
             viewmodel.wallpapers.push(wv);
         }
 
         viewmodel.notify(ApplicationViewModelEvent::WallpapersUpdated);
     }
+
+    /// Updates rotation status of each monitor.
+    /// Should be called as dedicated task.
+    pub fn update_rotations(this: Arc<Mutex<ApplicationViewModel>>) {
+        let mut viewmodel = this.lock();
+
+        let (
+            rotations,
+            rotation_queries,
+            rotation_collections,
+            scheduled_sources,
+            solar_events,
+            aspect_matching,
+        ) = {
+            let model = viewmodel.model.lock();
+            let now = Instant::now();
+            let (rotations, scheduled_sources) = (0..model.monitors().len())
+                .map(|i| {
+                    let source = model.scheduled_source(i).map(|s| match s {
+                        ScheduleSource::Rule(r) => model.schedules()[r].name.clone(),
+                        ScheduleSource::Solar(phase) => format!("Sun ({})", phase.label()),
+                    });
+                    (model.rotation_status(i, now), source)
+                })
+                .unzip();
+            let rotation_queries = (0..model.monitors().len())
+                .map(|i| model.rotation_query(i).cloned())
+                .collect();
+            let rotation_collections = (0..model.monitors().len())
+                .map(|i| model.rotation_collection(i).map(|c| c.id()))
+                .collect();
+
+            let events = model.solar_events();
+            let mut solar_events = format_crossing(events.sun, "Sunrise", "Sunset");
+            if let Some(twilight) = events.twilight {
+                solar_events.push_str(", ");
+                solar_events.push_str(&format_crossing(twilight, "Dawn", "Dusk"));
+            }
+            (
+                rotations,
+                rotation_queries,
+                rotation_collections,
+                scheduled_sources,
+                solar_events,
+                model.aspect_matching(),
+            )
+        };
+        viewmodel.rotations = rotations;
+        viewmodel.rotation_queries = rotation_queries;
+        viewmodel.rotation_collections = rotation_collections;
+        viewmodel.scheduled_sources = scheduled_sources;
+        viewmodel.solar_events = solar_events;
+        viewmodel.aspect_matching = aspect_matching;
+
+        viewmodel.notify(ApplicationViewModelEvent::RotationUpdated);
+    }
+
+    /// Updates schedule rules cache.
+    /// Should be called as dedicated task.
+    pub fn update_schedules(this: Arc<Mutex<ApplicationViewModel>>) {
+        let mut viewmodel = this.lock();
+
+        let (schedules, solar) = {
+            let model = viewmodel.model.lock();
+            (model.schedules().to_vec(), model.solar().clone())
+        };
+        viewmodel.schedules = schedules;
+        viewmodel.solar = solar;
+
+        viewmodel.notify(ApplicationViewModelEvent::SchedulesUpdated);
+    }
+
+    /// Updates text overlays cache.
+    /// Should be called as dedicated task.
+    pub fn update_overlays(this: Arc<Mutex<ApplicationViewModel>>) {
+        let mut viewmodel = this.lock();
+
+        let overlays = {
+            let model = viewmodel.model.lock();
+            model.overlays().to_vec()
+        };
+        viewmodel.overlays = overlays;
+
+        viewmodel.notify(ApplicationViewModelEvent::OverlaysUpdated);
+    }
 }
 
 impl ApplicationViewModel {
@@ -147,19 +340,81 @@ impl ApplicationViewModel {
 
         let selected = FileDialog::new()
             .add_filter("Supported Image Files", &["jpg", "jpeg", "png", "bmp"])
-            .show_open_single_file()
+            .add_filter("Dynamic Wallpaper Manifests", &["toml", "json"])
+            .show_open_multiple_file()
             .expect("Invalid file open dialog");
+
+        let model = viewmodel.model.clone();
+        drop(viewmodel);
+        add_paths(&model, &selected);
+
+        Ok(())
+    }
+
+    /// Adds files and folders dropped onto the window.
+    /// If dropped onto a monitor, the first added wallpaper is applied to it.
+    pub fn action_add_dropped(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        paths: Vec<PathBuf>,
+        monitor_index: Option<usize>,
+    ) {
+        info!("Adding {} dropped items: {monitor_index:?}", paths.len());
+        let model = this.lock().model.clone();
+        let added = add_paths(&model, &paths);
+
+        let (monitor_index, wallpaper_id) = match (monitor_index, added.first()) {
+            (Some(m), Some(&w)) => (m, w),
+            _ => return,
+        };
+        if let Err(e) = Application::apply_wallpaper_by_id(&model, monitor_index, wallpaper_id) {
+            error!("Failed to set wallpaper: {e}");
+        }
+    }
+
+    /// Opens folder selection dialog, and adds selected folder as a source.
+    pub fn action_add_folder(this: Arc<Mutex<ApplicationViewModel>>) -> Result<()> {
+        let viewmodel = this.lock();
+
+        let selected = FileDialog::new()
+            .show_open_single_dir()
+            .expect("Invalid folder open dialog");
         let path = match selected {
             Some(p) => p,
             None => return Ok(()),
         };
 
         let mut locked = viewmodel.model.lock();
-        locked.add_wallpaper(Wallpaper::new(path.to_string_lossy(), Fitting::Cover));
+        locked.add_source(&path, vec![], vec![])?;
 
         Ok(())
     }
 
+    /// Sets glob patterns of the folder source.
+    pub fn action_set_source_globs(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        index: usize,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    ) -> Result<()> {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_source_globs(index, include, exclude)
+    }
+
+    /// Removes the folder source and its wallpapers.
+    pub fn action_remove_source(this: Arc<Mutex<ApplicationViewModel>>, index: usize) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.remove_source(index);
+    }
+
+    /// Scans all folder sources again.
+    pub fn action_rescan_sources(this: Arc<Mutex<ApplicationViewModel>>) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.rescan_sources();
+    }
+
     /// Performs wallpapers list operation.
     pub fn action_perform_wallpaper(
         this: Arc<Mutex<ApplicationViewModel>>,
@@ -171,6 +426,131 @@ impl ApplicationViewModel {
         locked.update_wallpaper(index, op);
     }
 
+    /// Opens file selection dialog, and adds selected file as a variant of the wallpaper.
+    pub fn action_add_variant(this: Arc<Mutex<ApplicationViewModel>>, index: usize) -> Result<()> {
+        let viewmodel = this.lock();
+
+        let selected = FileDialog::new()
+            .add_filter("Supported Image Files", &["jpg", "jpeg", "png", "bmp"])
+            .show_open_single_file()
+            .expect("Invalid file open dialog");
+        let path = match selected {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        let mut locked = viewmodel.model.lock();
+        locked.add_variant_file(index, &path)?;
+
+        Ok(())
+    }
+
+    /// Adds new collage wallpaper.
+    pub fn action_add_collage(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        name: String,
+        collage: Collage,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.add_wallpaper(Wallpaper::new_collage(name, collage));
+    }
+
+    /// Sets new settings and display name of the collage wallpaper.
+    pub fn action_set_collage(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        index: usize,
+        name: String,
+        collage: Collage,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_collage(index, name, collage);
+    }
+
+    /// Records sizes of source images read by view.
+    pub fn action_cache_image_sizes(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        sizes: Vec<(Uuid, Vec2<u32>)>,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.cache_image_sizes(sizes);
+    }
+
+    /// Sets aspect ratio matching in rotation.
+    pub fn action_set_aspect_matching(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        aspect_matching: AspectMatching,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_aspect_matching(aspect_matching);
+    }
+
+    /// Stores detected focal points keyed by file content hash.
+    pub fn action_cache_focal_points(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        detected: Vec<(String, FocalPoint)>,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.cache_focal_points(detected);
+    }
+
+    /// Performs collections operation.
+    pub fn action_perform_collection(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        op: CollectionOperation,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.update_collection(op);
+    }
+
+    /// Renames the smart playlist and replaces its query.
+    pub fn action_set_smart_playlist(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        index: usize,
+        name: String,
+        query: SmartQuery,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.update_collection(CollectionOperation::Rename(index, name));
+        locked.update_collection(CollectionOperation::SetQuery(index, query));
+    }
+
+    /// Performs schedule rules operation.
+    pub fn action_perform_schedule(this: Arc<Mutex<ApplicationViewModel>>, op: ScheduleOperation) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.update_schedule(op);
+    }
+
+    /// Sets sun-driven switching settings.
+    pub fn action_set_solar(this: Arc<Mutex<ApplicationViewModel>>, solar: SolarSettings) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_solar(solar);
+    }
+
+    /// Sets text overlays.
+    pub fn action_set_overlays(this: Arc<Mutex<ApplicationViewModel>>, overlays: Vec<Overlay>) {
+        let model = this.lock().model.clone();
+        Application::set_overlays(&model, overlays);
+    }
+
+    /// Performs changes of a subcommand.
+    pub fn action_perform_command(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        command: &Command,
+    ) -> Result<()> {
+        info!("Performing command: {command:?}");
+        let model = this.lock().model.clone();
+        perform_command(&model, command)
+    }
+
     /// Sets selected wallpaper.
     pub fn action_set_wallpaper(
         this: Arc<Mutex<ApplicationViewModel>>,
@@ -178,15 +558,113 @@ impl ApplicationViewModel {
         wallpaper_index: usize,
     ) {
         info!("Changing wallpaper: Monitor #{monitor_index}: Wallpaper #{wallpaper_index}");
-        let viewmodel = this.lock();
-        let locked = viewmodel.model.lock();
-        match locked.apply_wallpaper_for_monitor(monitor_index, wallpaper_index) {
+        let model = this.lock().model.clone();
+        match Application::apply_wallpaper_for_monitor(&model, monitor_index, wallpaper_index) {
             Ok(()) => (),
             Err(e) => {
                 error!("Failed to set wallpaper: {e}");
             }
         }
     }
+
+    /// Sets rotation interval of the monitor. `None` disables rotation.
+    pub fn action_set_rotation_interval(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        monitor_index: usize,
+        interval: Option<Duration>,
+    ) {
+        info!("Changing rotation interval: Monitor #{monitor_index}: {interval:?}");
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_rotation_interval(monitor_index, interval);
+    }
+
+    /// Pauses or resumes rotation of the monitor.
+    pub fn action_pause_rotation(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        monitor_index: usize,
+        paused: bool,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_rotation_paused(monitor_index, paused);
+    }
+
+    /// Sets rotation order of the monitor.
+    pub fn action_set_rotation_order(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        monitor_index: usize,
+        order: RotationOrder,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_rotation_order(monitor_index, order);
+    }
+
+    /// Restricts wallpapers of the monitor in rotation to ones matching the query.
+    pub fn action_set_rotation_query(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        monitor_index: usize,
+        query: TagQuery,
+    ) {
+        info!("Changing rotation query: Monitor #{monitor_index}: {query}");
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_rotation_query(monitor_index, query);
+    }
+
+    /// Binds the monitor to the collection in rotation.
+    pub fn action_set_rotation_collection(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        monitor_index: usize,
+        collection: Option<Uuid>,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_rotation_collection(monitor_index, collection);
+    }
+
+    /// Adds and removes tags of multiple wallpapers.
+    pub fn action_edit_tags(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        indices: Vec<usize>,
+        added: Vec<String>,
+        removed: Vec<String>,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.edit_tags(&indices, &added, &removed);
+    }
+
+    /// Sets span group of the monitor.
+    pub fn action_set_span_group(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        monitor_index: usize,
+        group: Option<String>,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_span_group(monitor_index, group);
+    }
+
+    /// Sets bezel and physical size of the monitor.
+    pub fn action_set_monitor_layout(
+        this: Arc<Mutex<ApplicationViewModel>>,
+        monitor_index: usize,
+        layout: MonitorLayout,
+    ) {
+        let viewmodel = this.lock();
+        let mut locked = viewmodel.model.lock();
+        locked.set_monitor_layout(monitor_index, layout);
+    }
+
+    /// Changes to next wallpaper immediately.
+    pub fn action_skip_rotation(this: Arc<Mutex<ApplicationViewModel>>, monitor_index: usize) {
+        let model = this.lock().model.clone();
+        if let Err(e) = Application::apply_next_wallpaper(&model, monitor_index) {
+            error!("Failed to skip wallpaper: {e}");
+        }
+    }
 }
 
 impl Observable for ApplicationViewModel {
@@ -206,43 +684,72 @@ impl Observable for ApplicationViewModel {
 
 /// Represents an event in `ApplicationViewModel`.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum ApplicationViewModelEvent {
     WallpapersUpdated,
     MonitorsUpdated,
+    RotationUpdated,
+    SchedulesUpdated,
+    OverlaysUpdated,
+}
+
+/// Formats rising and setting time.
+fn format_crossing(crossing: Crossing, rise: &str, set: &str) -> String {
+    match crossing {
+        Crossing::Times(r, s) => format!(
+            "{rise} {:02}:{:02}, {set} {:02}:{:02}",
+            r.hour(),
+            r.minute(),
+            s.hour(),
+            s.minute()
+        ),
+        Crossing::AlwaysAbove => format!("No {set}"),
+        Crossing::AlwaysBelow => format!("No {rise}"),
+    }
 }
 
 /// Cache object for view about monitor.
 pub struct MonitorCache {
+    pub id: String,
     pub name: String,
     pub position: Vec2<i32>,
     pub size: Vec2<i32>,
+    pub span_group: Option<String>,
+    pub layout: MonitorLayout,
+
+    /// Normalized rect of whole monitor including bezel.
     pub preview_rect: Vec4<f32>,
+
+    /// Normalized rect of visible area.
+    pub preview_screen_rect: Vec4<f32>,
 }
 
 impl MonitorCache {
     /// Constructs from Monitor model.
     pub fn new(
         source: &Monitor,
+        span_group: Option<String>,
+        layout: MonitorLayout,
+        placement: Placement,
         whole_topleft: Vec2<f32>,
         whole_offset: Vec2<f32>,
         divider: f32,
     ) -> MonitorCache {
-        let raw_position = source.position().as_::<f32>();
-        let raw_size = source.size().as_::<f32>();
-
-        let normalized_position = (raw_position - whole_topleft + whole_offset) / divider;
-        let normalized_size = raw_size / divider;
+        let normalize = |rect: Aabr<f64>| {
+            let min = (rect.min.as_::<f32>() - whole_topleft + whole_offset) / divider;
+            let max = (rect.max.as_::<f32>() - whole_topleft + whole_offset) / divider;
+            Vec4::new(min.x, min.y, max.x, max.y)
+        };
 
         MonitorCache {
+            id: source.id().as_str().to_string(),
             name: source.name().to_string(),
             position: source.position(),
             size: source.size(),
-            preview_rect: Vec4::new(
-                normalized_position.x,
-                normalized_position.y,
-                normalized_position.x + normalized_size.x,
-                normalized_position.y + normalized_size.y,
-            ),
+            span_group,
+            layout,
+            preview_rect: normalize(placement.body),
+            preview_screen_rect: normalize(placement.screen),
         }
     }
 }
@@ -252,14 +759,191 @@ pub struct WallpaperCache {
     pub uuid: Uuid,
     pub filename: String,
     pub fitting: Fitting,
+    pub background: Background,
+
+    /// Manually set one.
+    pub focal_point: Option<FocalPoint>,
+    pub adjustments: Adjustments,
+    pub kind: WallpaperKind,
+
+    /// UUID of `FolderSource` which this is found in.
+    pub source: Option<Uuid>,
+    pub tags: Vec<String>,
+    pub rating: u8,
+
+    /// Of the oriented image, if its size is known.
+    pub aspect_ratio: Option<f32>,
+
+    /// Image files to show as thumbnails.
+    /// Dynamic wallpapers have all frames, and collages have none.
+    pub images: Vec<String>,
+
+    /// Composed into the thumbnail of collages.
+    pub collage: Option<CollagePreview>,
+
+    /// Renditions in other resolutions, as filename and size.
+    pub variants: Vec<(String, Vec2<u32>)>,
+
+    /// Monitor names showing this, with the index of rendition, 0 for the primary one.
+    pub applied_renditions: Vec<(String, usize)>,
+}
+
+/// Collage settings with its members resolved from the library.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollagePreview {
+    pub collage: Collage,
+    pub members: Vec<CollageMember>,
+}
+
+/// Member wallpaper of a collage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollageMember {
+    pub uuid: Uuid,
+
+    /// Image file, the first frame for dynamic wallpapers.
+    pub image: String,
+    pub focal_point: Option<FocalPoint>,
+    pub adjustments: Adjustments,
 }
 
 impl WallpaperCache {
-    pub fn new(source: &Wallpaper) -> WallpaperCache {
+    /// Constructs from the wallpaper, resolving collage members from `library`.
+    /// `frame_images` has frame images of dynamic wallpapers.
+    pub fn new(
+        source: &Wallpaper,
+        library: &[Wallpaper],
+        frame_images: &HashMap<Uuid, Vec<String>>,
+    ) -> WallpaperCache {
+        let images = wallpaper_images(source, frame_images);
+        let collage = source.collage().map(|c| CollagePreview {
+            collage: c.clone(),
+            members: c
+                .members
+                .iter()
+                .filter_map(|id| library.iter().find(|w| w.id() == *id))
+                .filter_map(|w| {
+                    Some(CollageMember {
+                        uuid: w.id(),
+                        image: wallpaper_images(w, frame_images).into_iter().next()?,
+                        focal_point: w.focal_point(),
+                        adjustments: w.adjustments(),
+                    })
+                })
+                .collect(),
+        });
+
         WallpaperCache {
             uuid: source.id(),
             filename: source.filename().to_string(),
             fitting: source.fitting(),
+            background: source.background(),
+            focal_point: source.focal_point(),
+            adjustments: source.adjustments(),
+            kind: source.kind(),
+            source: source.source(),
+            tags: source.tags().to_vec(),
+            rating: source.rating(),
+            aspect_ratio: source.aspect_ratio(),
+            variants: source
+                .variants()
+                .iter()
+                .map(|v| (v.filename.clone(), v.size()))
+                .collect(),
+            applied_renditions: vec![],
+            images,
+            collage,
+        }
+    }
+
+    /// Checks whether this matches the query.
+    pub fn matches(&self, query: &TagQuery) -> bool {
+        query.matches(&self.filename, &self.tags)
+    }
+}
+
+/// Cache object for view about collection.
+pub struct CollectionCache {
+    pub uuid: Uuid,
+    pub name: String,
+
+    /// UUIDs of member wallpapers in order, computed for smart playlists.
+    pub members: Vec<Uuid>,
+
+    /// Defines members of smart playlists.
+    pub query: Option<SmartQuery>,
+}
+
+impl CollectionCache {
+    /// Constructs from the collection, computing members of smart playlists.
+    pub fn new(source: &Collection, wallpapers: &[Wallpaper], now: i64) -> CollectionCache {
+        CollectionCache {
+            uuid: source.id(),
+            name: source.name.clone(),
+            members: source
+                .select_wallpapers(wallpapers, now)
+                .into_iter()
+                .map(|i| wallpapers[i].id())
+                .collect(),
+            query: source.query().cloned(),
+        }
+    }
+}
+
+/// Adds files as wallpapers and folders as sources, skipping invalid ones.
+/// Returns UUIDs of wallpapers from the files.
+fn add_paths(model: &Mutex<Application>, paths: &[PathBuf]) -> Vec<Uuid> {
+    let (folders, files): (Vec<_>, Vec<_>) = paths.iter().cloned().partition(|p| p.is_dir());
+    if !folders.is_empty() {
+        let mut locked = model.lock();
+        for folder in &folders {
+            if let Err(e) = locked.add_source(folder, vec![], vec![]) {
+                error!("Failed to add {}: {e}", folder.display());
+            }
+        }
+    }
+    if files.is_empty() {
+        return vec![];
+    }
+
+    let results = Application::add_wallpaper_files(model, &files, Fitting::Cover);
+    files
+        .iter()
+        .zip(results)
+        .filter_map(|(path, result)| match result {
+            Ok(i) => Some(i),
+            Err(e) => {
+                error!("Failed to add {}: {e}", path.display());
+                None
+            }
+        })
+        .collect()
+}
+
+/// Lists image files of the wallpaper. Dynamic ones are looked up in `frame_images`.
+fn wallpaper_images(
+    wallpaper: &Wallpaper,
+    frame_images: &HashMap<Uuid, Vec<String>>,
+) -> Vec<String> {
+    match wallpaper.kind() {
+        WallpaperKind::Image => vec![wallpaper.filename().to_string()],
+        WallpaperKind::Dynamic => frame_images
+            .get(&wallpaper.id())
+            .cloned()
+            .unwrap_or_default(),
+        WallpaperKind::Collage => vec![],
+    }
+}
+
+/// Reads the manifest of dynamic wallpaper, and lists its frame images.
+fn load_frame_images(wallpaper: &Wallpaper) -> Vec<String> {
+    let path = Path::new(wallpaper.filename());
+    match DynamicManifest::load(path) {
+        Ok(m) => (0..m.frames.len())
+            .map(|i| m.frame_path(path, i).to_string_lossy().to_string())
+            .collect(),
+        Err(e) => {
+            error!("Failed to load manifest {}: {e}", wallpaper.filename());
+            vec![]
         }
     }
 }
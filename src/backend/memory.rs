@@ -0,0 +1,45 @@
+use crate::backend::{Monitor, MonitorId, WallpaperBackend};
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+
+/// In-memory backend which only records applied wallpapers.
+/// Used on unsupported platforms and for tests.
+pub struct MemoryBackend {
+    monitors: Vec<Monitor>,
+    applied: Mutex<Vec<(MonitorId, PathBuf)>>,
+}
+
+impl MemoryBackend {
+    /// Constructs with fixed monitors.
+    pub fn new(monitors: Vec<Monitor>) -> MemoryBackend {
+        MemoryBackend {
+            monitors,
+            applied: Mutex::new(vec![]),
+        }
+    }
+
+    /// Gets all `set_wallpaper` calls in order.
+    #[cfg(test)]
+    pub fn applied(&self) -> Vec<(MonitorId, PathBuf)> {
+        self.applied.lock().clone()
+    }
+}
+
+impl WallpaperBackend for MemoryBackend {
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        Ok(self.monitors.clone())
+    }
+
+    fn set_wallpaper(&self, monitor_id: &MonitorId, path: &Path) -> Result<()> {
+        if !self.monitors.iter().any(|m| m.id() == monitor_id) {
+            bail!("Unknown monitor: {}", monitor_id.as_str());
+        }
+
+        let mut applied = self.applied.lock();
+        applied.push((monitor_id.clone(), path.to_path_buf()));
+        Ok(())
+    }
+}
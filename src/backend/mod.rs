@@ -0,0 +1,88 @@
+//! Abstracts platform-dependent wallpaper manipulation.
+
+mod memory;
+
+use std::path::Path;
+
+use anyhow::Result;
+use vek::Vec2;
+
+pub use self::memory::MemoryBackend;
+
+/// Identifies monitor.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MonitorId(String);
+
+impl MonitorId {
+    /// Constructs from backend-specific ID string.
+    pub fn new(id: impl Into<String>) -> MonitorId {
+        MonitorId(id.into())
+    }
+
+    /// Refers ID string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Represents a monitor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Monitor {
+    /// Monitor ID.
+    id: MonitorId,
+
+    /// Monitor name.
+    name: String,
+
+    /// Top-left monitor position.
+    position: Vec2<i32>,
+
+    /// Physical size of this monitor.
+    size: Vec2<i32>,
+}
+
+impl Monitor {
+    /// Constructs new monitor information.
+    pub fn new(
+        id: MonitorId,
+        name: impl Into<String>,
+        position: Vec2<i32>,
+        size: Vec2<i32>,
+    ) -> Monitor {
+        Monitor {
+            id,
+            name: name.into(),
+            position,
+            size,
+        }
+    }
+
+    /// Gets monitor ID.
+    pub fn id(&self) -> &MonitorId {
+        &self.id
+    }
+
+    /// Gets monitor name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets monitor position.
+    pub fn position(&self) -> Vec2<i32> {
+        self.position
+    }
+
+    /// Gets monitor size.
+    pub fn size(&self) -> Vec2<i32> {
+        self.size
+    }
+}
+
+/// Provides monitor enumeration and wallpaper setting of the platform.
+pub trait WallpaperBackend: Send + Sync {
+    /// Fetches connected monitors information.
+    fn monitors(&self) -> Result<Vec<Monitor>>;
+
+    /// Sets wallpaper image file for monitor.
+    fn set_wallpaper(&self, monitor_id: &MonitorId, path: &Path) -> Result<()>;
+}
@@ -1,16 +1,38 @@
-use crate::{application::Application, windows::WallpaperInterface};
+use crate::{
+    application::{Application, ApplicationViewModel, ApplicationWindowEvent},
+    egui::EventProxy,
+    ipc::{serve, IpcRequest, IpcResponse, IpcServer},
+};
 
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use parking_lot::Mutex;
+use time::{OffsetDateTime, UtcOffset};
+use tokio::{
+    task::spawn_blocking,
+    time::{interval, MissedTickBehavior},
+};
+use winit::window::WindowId;
+
+/// Restores persisted wallpaper library.
+pub async fn load_library(application: Arc<Mutex<Application>>) -> Result<()> {
+    let mut locked = application.lock();
+    locked.restore_library()?;
+
+    Ok(())
+}
 
 /// Fetches monitor information and sets them to application model.
 pub async fn load_monitor_info(application: Arc<Mutex<Application>>) -> Result<()> {
-    let monitors = {
-        let wallpaper = WallpaperInterface::new()?;
-        wallpaper.monitors()?
+    let backend = {
+        let locked = application.lock();
+        locked.backend()
     };
+    let monitors = backend.monitors()?;
 
     {
         let mut locked = application.lock();
@@ -19,3 +41,62 @@ pub async fn load_monitor_info(application: Arc<Mutex<Application>>) -> Result<(
 
     Ok(())
 }
+
+/// Checks schedules and rotation timers every second.
+/// `local_offset` is used when the local time cannot be determined.
+pub async fn rotate_wallpapers(application: Arc<Mutex<Application>>, local_offset: UtcOffset) {
+    let mut ticker = interval(Duration::from_secs(1));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let application = application.clone();
+        let ticked = spawn_blocking(move || {
+            let local = OffsetDateTime::now_local()
+                .unwrap_or_else(|_| OffsetDateTime::now_utc().to_offset(local_offset));
+            Application::tick_rotation(&application, Instant::now(), local);
+        });
+        if ticked.await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Scans folder sources which are added or changed every second.
+pub async fn sync_sources(application: Arc<Mutex<Application>>) {
+    let mut ticker = interval(Duration::from_secs(1));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let application = application.clone();
+        let ticked = spawn_blocking(move || Application::tick_sources(&application));
+        if ticked.await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Processes requests from other instances.
+pub async fn serve_requests(
+    server: IpcServer,
+    viewmodel: Arc<Mutex<ApplicationViewModel>>,
+    event_proxy: Arc<EventProxy<ApplicationWindowEvent>>,
+    window_id: WindowId,
+) {
+    serve(server, move |request| {
+        let result = match request {
+            IpcRequest::Show => {
+                event_proxy.request_show(window_id);
+                Ok(())
+            }
+            IpcRequest::Command(command) => {
+                ApplicationViewModel::action_perform_command(viewmodel.clone(), &command)
+            }
+        };
+
+        match result {
+            Ok(()) => IpcResponse::Done,
+            Err(e) => IpcResponse::Failed(e.to_string()),
+        }
+    })
+    .await;
+}
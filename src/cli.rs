@@ -0,0 +1,426 @@
+//! Provides command-line interface for scripting.
+
+use crate::{
+    application::{
+        Adjustments, Application, Background, Fitting, WallpaperKind, WallpaperListOperation,
+    },
+    background::{load_library, load_monitor_info},
+    ipc::{Endpoint, IpcClient, IpcRequest, IpcResponse},
+};
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+use log::info;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Advanced Wallpaper Changer.
+/// Opens the window when no command is specified.
+#[derive(Debug, Parser)]
+#[clap(version)]
+pub struct Arguments {
+    /// Prints results as JSON.
+    #[clap(long, global = true)]
+    pub json: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Represents a subcommand.
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand, Serialize, Deserialize)]
+pub enum Command {
+    /// Lists connected monitors.
+    Monitors,
+
+    /// Lists wallpapers in the library.
+    List,
+
+    /// Adds images to the library.
+    Add {
+        /// Image files or dynamic wallpaper manifests.
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Fitting for added images.
+        #[clap(long, default_value = "cover")]
+        fitting: Fitting,
+    },
+
+    /// Sets an image as wallpaper of a monitor, adding it to the library if needed.
+    Set {
+        /// Index of monitor.
+        #[clap(long)]
+        monitor: usize,
+
+        /// Image file or dynamic wallpaper manifest.
+        file: PathBuf,
+
+        /// Fitting for this image.
+        #[clap(long)]
+        fitting: Option<Fitting>,
+    },
+
+    /// Changes wallpapers to the next ones in the library.
+    Next {
+        /// Index of monitor. All monitors are changed if omitted.
+        #[clap(long)]
+        monitor: Option<usize>,
+    },
+}
+
+impl Command {
+    /// Checks whether this command changes the library or wallpapers.
+    pub fn is_mutating(&self) -> bool {
+        !matches!(self, Command::Monitors | Command::List)
+    }
+}
+
+/// Monitor information for output.
+#[derive(Debug, Serialize)]
+struct MonitorEntry {
+    index: usize,
+    id: String,
+    name: String,
+    position: [i32; 2],
+    size: [i32; 2],
+    wallpaper: Option<Uuid>,
+}
+
+/// Wallpaper information for output.
+#[derive(Debug, Serialize)]
+struct WallpaperEntry {
+    index: usize,
+    id: Uuid,
+    filename: String,
+    fitting: Fitting,
+    kind: WallpaperKind,
+    background: Background,
+    adjustments: Adjustments,
+    monitors: Vec<usize>,
+}
+
+/// Assignment result for output.
+#[derive(Debug, Serialize)]
+struct AssignmentEntry {
+    monitor: usize,
+    wallpaper: Option<Uuid>,
+}
+
+/// Runs a subcommand against persisted library.
+/// Changes are forwarded to the running instance if exists.
+pub async fn run_command(
+    application: Arc<Mutex<Application>>,
+    command: Command,
+    json: bool,
+) -> Result<()> {
+    let forwarded = command.is_mutating() && forward_command(&command).await?;
+
+    load_library(application.clone()).await?;
+    load_monitor_info(application.clone()).await?;
+
+    if !forwarded {
+        perform_command(&application, &command)?;
+    }
+
+    let locked = application.lock();
+
+    match command {
+        Command::Monitors => print_monitors(&locked, json),
+        Command::List | Command::Add { .. } => print_wallpapers(&locked, json),
+        Command::Set { monitor, .. }
+        | Command::Next {
+            monitor: Some(monitor),
+        } => print_assignments(&locked, &[monitor], json),
+        Command::Next { monitor: None } => {
+            let monitors: Vec<_> = (0..locked.monitors().len()).collect();
+            print_assignments(&locked, &monitors, json)
+        }
+    }
+}
+
+/// Performs changes of the command.
+/// Wallpapers are rendered without holding the lock.
+pub fn perform_command(application: &Mutex<Application>, command: &Command) -> Result<()> {
+    match command {
+        Command::Monitors | Command::List => (),
+        Command::Add { files, fitting } => {
+            let mut locked = application.lock();
+            for file in files {
+                locked.add_wallpaper_file(file, *fitting)?;
+            }
+        }
+        Command::Set {
+            monitor,
+            file,
+            fitting,
+        } => {
+            let index = {
+                let mut locked = application.lock();
+                check_monitor(&locked, *monitor)?;
+                let index = locked.add_wallpaper_file(file, fitting.unwrap_or(Fitting::Cover))?;
+                if let Some(fitting) = fitting {
+                    locked.update_wallpaper(index, WallpaperListOperation::SetFitting(*fitting));
+                }
+                index
+            };
+            Application::apply_wallpaper_for_monitor(application, *monitor, index)?;
+        }
+        Command::Next { monitor } => {
+            let monitors: Vec<_> = {
+                let locked = application.lock();
+                match monitor {
+                    Some(m) => {
+                        check_monitor(&locked, *m)?;
+                        vec![*m]
+                    }
+                    None => (0..locked.monitors().len()).collect(),
+                }
+            };
+            for m in monitors {
+                Application::apply_next_wallpaper(application, m)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends the command to running instance.
+/// Returns `false` if there is no running instance.
+async fn forward_command(command: &Command) -> Result<bool> {
+    let mut client = match IpcClient::connect(&Endpoint::default()).await? {
+        Some(c) => c,
+        None => return Ok(false),
+    };
+
+    info!("Forwarding to running instance");
+    match client
+        .request(&IpcRequest::Command(command.clone()))
+        .await?
+    {
+        IpcResponse::Done => Ok(true),
+        IpcResponse::Failed(message) => bail!(message),
+    }
+}
+
+/// Requests the running instance to show its window.
+/// Returns `false` if there is no running instance.
+pub async fn show_running_instance() -> Result<bool> {
+    let mut client = match IpcClient::connect(&Endpoint::default()).await? {
+        Some(c) => c,
+        None => return Ok(false),
+    };
+
+    match client.request(&IpcRequest::Show).await? {
+        IpcResponse::Done => Ok(true),
+        IpcResponse::Failed(message) => bail!(message),
+    }
+}
+
+/// Ensures the monitor index is valid.
+fn check_monitor(application: &Application, monitor_index: usize) -> Result<()> {
+    let count = application.monitors().len();
+    if monitor_index >= count {
+        bail!("Monitor #{monitor_index} does not exist ({count} monitors connected)");
+    }
+    Ok(())
+}
+
+fn print_monitors(application: &Application, json: bool) -> Result<()> {
+    let entries: Vec<_> = application
+        .monitors()
+        .iter()
+        .enumerate()
+        .map(|(i, m)| MonitorEntry {
+            index: i,
+            id: m.id().as_str().to_string(),
+            name: m.name().to_string(),
+            position: m.position().into_array(),
+            size: m.size().into_array(),
+            wallpaper: application
+                .assigned_wallpaper(i)
+                .map(|w| application.wallpapers()[w].id()),
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    for entry in entries {
+        println!(
+            "#{}\t{}\t({}, {})\t{}x{}\t{}",
+            entry.index,
+            entry.name,
+            entry.position[0],
+            entry.position[1],
+            entry.size[0],
+            entry.size[1],
+            entry.id,
+        );
+    }
+    Ok(())
+}
+
+fn print_wallpapers(application: &Application, json: bool) -> Result<()> {
+    let entries: Vec<_> = application
+        .wallpapers()
+        .iter()
+        .enumerate()
+        .map(|(i, w)| WallpaperEntry {
+            index: i,
+            id: w.id(),
+            filename: w.filename().to_string(),
+            fitting: w.fitting(),
+            kind: w.kind(),
+            background: w.background(),
+            adjustments: w.adjustments(),
+            monitors: (0..application.monitors().len())
+                .filter(|&m| application.assigned_wallpaper(m) == Some(i))
+                .collect(),
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    for entry in entries {
+        let monitors: Vec<_> = entry.monitors.iter().map(|m| format!("#{m}")).collect();
+        println!(
+            "#{}\t{:?}\t{}\t{}",
+            entry.index,
+            entry.fitting,
+            entry.filename,
+            monitors.join(", "),
+        );
+    }
+    Ok(())
+}
+
+fn print_assignments(application: &Application, monitors: &[usize], json: bool) -> Result<()> {
+    let entries: Vec<_> = monitors
+        .iter()
+        .map(|&m| AssignmentEntry {
+            monitor: m,
+            wallpaper: application
+                .assigned_wallpaper(m)
+                .map(|w| application.wallpapers()[w].id()),
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    for entry in entries {
+        let filename = application
+            .assigned_wallpaper(entry.monitor)
+            .map(|w| application.wallpapers()[w].filename())
+            .unwrap_or("(none)");
+        println!("#{}\t{}", entry.monitor, filename);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{MemoryBackend, Monitor, MonitorId, WallpaperBackend};
+
+    use image::RgbaImage;
+    use tempfile::TempDir;
+    use time::UtcOffset;
+    use vek::Vec2;
+
+    #[test]
+    fn arguments_parse_subcommands() {
+        let arguments = Arguments::try_parse_from(["adwapach"]).expect("Should parse");
+        assert_eq!(arguments.command, None);
+
+        let arguments =
+            Arguments::try_parse_from(["adwapach", "set", "--monitor", "1", "a.png", "--json"])
+                .expect("Should parse");
+        assert!(arguments.json);
+        assert_eq!(
+            arguments.command,
+            Some(Command::Set {
+                monitor: 1,
+                file: PathBuf::from("a.png"),
+                fitting: None,
+            })
+        );
+
+        let arguments =
+            Arguments::try_parse_from(["adwapach", "add", "a.png", "b.png"]).expect("Should parse");
+        assert_eq!(
+            arguments.command,
+            Some(Command::Add {
+                files: vec![PathBuf::from("a.png"), PathBuf::from("b.png")],
+                fitting: Fitting::Cover,
+            })
+        );
+        assert!(!Command::List.is_mutating());
+
+        assert!(Arguments::try_parse_from(["adwapach", "add"]).is_err());
+        assert!(Arguments::try_parse_from(["adwapach", "add", "a.png", "--fitting", "x"]).is_err());
+    }
+
+    #[test]
+    fn perform_command_changes_wallpapers() {
+        let backend = Arc::new(MemoryBackend::new(vec![Monitor::new(
+            MonitorId::new("primary"),
+            "Primary",
+            Vec2::new(0, 0),
+            Vec2::new(32, 18),
+        )]));
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        let application = Application::new(
+            backend.clone(),
+            directory.path().join("rendered"),
+            UtcOffset::UTC,
+        );
+        application
+            .lock()
+            .set_monitors(backend.monitors().expect("Should succeed"));
+
+        let files: Vec<_> = ["a.png", "b.png"]
+            .iter()
+            .map(|name| {
+                let path = directory.path().join(name);
+                RgbaImage::new(16, 9).save(&path).expect("Failed to save");
+                path
+            })
+            .collect();
+        let add = Command::Add {
+            files: files.clone(),
+            fitting: Fitting::Contain,
+        };
+        perform_command(&application, &add).expect("Should add");
+        assert_eq!(application.lock().wallpapers().len(), 2);
+
+        // The same file in another form is not added twice
+        let set = Command::Set {
+            monitor: 0,
+            file: directory.path().join(".").join("b.png"),
+            fitting: Some(Fitting::Stretch),
+        };
+        perform_command(&application, &set).expect("Should set");
+        {
+            let locked = application.lock();
+            assert_eq!(locked.wallpapers().len(), 2);
+            assert_eq!(locked.assigned_wallpaper(0), Some(1));
+            assert_eq!(locked.wallpapers()[1].fitting(), Fitting::Stretch);
+        }
+
+        perform_command(&application, &Command::Next { monitor: None }).expect("Should change");
+        assert_eq!(application.lock().assigned_wallpaper(0), Some(0));
+
+        let next = Command::Next { monitor: Some(1) };
+        assert!(perform_command(&application, &next).is_err());
+    }
+}
@@ -3,7 +3,7 @@ use crate::egui::{EguiEvent, EventProxy, View};
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use egui::{ClippedMesh, Context as EguiContext, RawInput, TexturesDelta};
+use egui::{ClippedMesh, Context as EguiContext, Event, Pos2, RawInput, TexturesDelta};
 use egui_wgpu_backend::{RenderPass as EguiRenderPass, ScreenDescriptor};
 use egui_winit::State as EguiState;
 use epi::{
@@ -18,7 +18,6 @@ use winit::{
     dpi::LogicalSize,
     event::WindowEvent,
     event_loop::{ControlFlow, EventLoop},
-    platform::windows::WindowBuilderExtWindows,
     window::{Window, WindowBuilder, WindowId},
 };
 
@@ -39,6 +38,9 @@ pub struct EguiWindow<V: View<E>, E: EguiEvent> {
     egui_render_pass: EguiRenderPass,
     egui_base_frame: EpiFrame,
     view: Arc<Mutex<V>>,
+
+    /// Pointer position to tell egui, as dragging files does not move it.
+    file_drag_position: Option<Pos2>,
 }
 
 impl<V: View<E>, E: EguiEvent> EguiWindow<V, E> {
@@ -57,7 +59,6 @@ impl<V: View<E>, E: EguiEvent> EguiWindow<V, E> {
             .with_decorations(true)
             .with_resizable(true)
             .with_transparent(false)
-            .with_drag_and_drop(false)
             .with_inner_size(LogicalSize::new(640, 640))
             .with_window_icon(icon)
             .with_title(name)
@@ -93,8 +94,8 @@ impl<V: View<E>, E: EguiEvent> EguiWindow<V, E> {
         let surface_config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
-            width: size.width as u32,
-            height: size.height as u32,
+            width: size.width,
+            height: size.height,
             present_mode: wgpu::PresentMode::Fifo,
         };
 
@@ -134,6 +135,7 @@ impl<V: View<E>, E: EguiEvent> EguiWindow<V, E> {
             egui_render_pass,
             egui_base_frame,
             view,
+            file_drag_position: None,
         })
     }
 
@@ -154,9 +156,13 @@ impl<V: View<E>, E: EguiEvent> EguiWindow<V, E> {
     /// Updates UI with arrived event.
     pub fn update_with_event(&mut self, event: WindowEvent) {
         match event {
-            WindowEvent::CloseRequested => {
+            // Without notify icon, there is no way to show it again
+            WindowEvent::CloseRequested if cfg!(windows) => {
                 self.event_proxy.request_hide(self.window.id());
             }
+            WindowEvent::CloseRequested => {
+                self.event_proxy.exit();
+            }
             WindowEvent::Resized(new_size) => {
                 if new_size.width > 0 && new_size.height > 0 {
                     self.surface_config.width = new_size.width;
@@ -171,12 +177,33 @@ impl<V: View<E>, E: EguiEvent> EguiWindow<V, E> {
                 // Call it also for State, or we will have non-scaled lettebox.
                 self.egui_state.on_event(&self.egui_context, &event);
             }
+            WindowEvent::HoveredFile(_) | WindowEvent::DroppedFile(_) => {
+                #[cfg(windows)]
+                {
+                    self.file_drag_position = self.cursor_position();
+                }
+                self.egui_state.on_event(&self.egui_context, &event);
+            }
             event => {
                 self.egui_state.on_event(&self.egui_context, &event);
             }
         }
     }
 
+    /// Gets cursor position in points.
+    #[cfg(windows)]
+    fn cursor_position(&self) -> Option<Pos2> {
+        use windows::Win32::Foundation::HWND;
+        use winit::platform::windows::WindowExtWindows;
+
+        let (x, y) = crate::windows::cursor_position(HWND(self.window.hwnd() as _))?;
+        let pixels_per_point = self.egui_state.pixels_per_point();
+        Some(Pos2::new(
+            x as f32 / pixels_per_point,
+            y as f32 / pixels_per_point,
+        ))
+    }
+
     /// Redraws UI.
     pub fn redraw(&mut self) -> Result<ControlFlow> {
         let output_frame = match self.surface.get_current_texture() {
@@ -190,7 +217,10 @@ impl<V: View<E>, E: EguiEvent> EguiWindow<V, E> {
         let texture_view = output_frame.texture.create_view(&Default::default());
 
         // Update view
-        let input = self.egui_state.take_egui_input(&self.window);
+        let mut input = self.egui_state.take_egui_input(&self.window);
+        if let Some(position) = self.file_drag_position.take() {
+            input.events.push(Event::PointerMoved(position));
+        }
         let (commands, textures_delta, repainting) = self.draw_egui(input);
 
         let screen_descriptor = ScreenDescriptor {
@@ -0,0 +1,194 @@
+//! Provides communication with the running instance.
+
+#[cfg(windows)]
+mod pipe;
+#[cfg(unix)]
+mod socket;
+
+use crate::cli::Command;
+
+use std::sync::Arc;
+
+use anyhow::{bail, Result};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    task::spawn_blocking,
+};
+
+#[cfg(windows)]
+pub use self::pipe::{Endpoint, IpcServer};
+#[cfg(unix)]
+pub use self::socket::{Endpoint, IpcServer};
+
+#[cfg(windows)]
+use self::pipe::{connect_endpoint, Stream};
+#[cfg(unix)]
+use self::socket::{connect_endpoint, Stream};
+
+/// Request sent to the running instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpcRequest {
+    /// Shows the window.
+    Show,
+
+    /// Performs changes of subcommand.
+    Command(Command),
+}
+
+/// Response from the running instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpcResponse {
+    /// Request has been processed.
+    Done,
+
+    /// Request has failed with message.
+    Failed(String),
+}
+
+/// Connection to the running instance.
+pub struct IpcClient(BufReader<Stream>);
+
+impl IpcClient {
+    /// Connects to the running instance.
+    /// Returns `None` if no instance is listening.
+    pub async fn connect(endpoint: &Endpoint) -> Result<Option<IpcClient>> {
+        let stream = connect_endpoint(endpoint).await?;
+        Ok(stream.map(|s| IpcClient(BufReader::new(s))))
+    }
+
+    /// Sends a request and waits for its response.
+    pub async fn request(&mut self, request: &IpcRequest) -> Result<IpcResponse> {
+        write_message(self.0.get_mut(), request).await?;
+        match read_message(&mut self.0).await? {
+            Some(response) => Ok(response),
+            None => bail!("Connection closed without response"),
+        }
+    }
+}
+
+/// Accepts connections and processes requests by handler.
+/// Handler is called on blocking thread.
+pub async fn serve<F>(mut server: IpcServer, handler: F)
+where
+    F: Fn(IpcRequest) -> IpcResponse + Send + Sync + 'static,
+{
+    let handler = Arc::new(handler);
+    loop {
+        let stream = match server.accept().await {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Failed to accept IPC connection: {e}");
+                return;
+            }
+        };
+
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            if let Err(e) = process_connection(stream, handler).await {
+                warn!("IPC connection error: {e}");
+            }
+        });
+    }
+}
+
+/// Processes requests in a connection until it closes.
+async fn process_connection<S, F>(stream: S, handler: Arc<F>) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: Fn(IpcRequest) -> IpcResponse + Send + Sync + 'static,
+{
+    let mut reader = BufReader::new(stream);
+    while let Some(request) = read_message(&mut reader).await? {
+        let handler = handler.clone();
+        let response = spawn_blocking(move || handler(request)).await?;
+        write_message(reader.get_mut(), &response).await?;
+    }
+
+    Ok(())
+}
+
+/// Writes a message as a JSON line.
+async fn write_message<S, T>(stream: &mut S, message: &T) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// Reads a message from a JSON line. Returns `None` at EOF.
+async fn read_message<S, T>(reader: &mut BufReader<S>) -> Result<Option<T>>
+where
+    S: AsyncRead + Unpin,
+    T: for<'de> Deserialize<'de>,
+{
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+
+    use tempfile::TempDir;
+
+    fn handle(request: IpcRequest) -> IpcResponse {
+        match request {
+            IpcRequest::Show => IpcResponse::Done,
+            IpcRequest::Command(c) => IpcResponse::Failed(format!("{c:?}")),
+        }
+    }
+
+    #[tokio::test]
+    async fn request_roundtrip() {
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        let endpoint = Endpoint::new(directory.path().join("test.sock"));
+        let server = IpcServer::bind(&endpoint).expect("Should bind");
+        tokio::spawn(serve(server, handle));
+
+        let mut client = IpcClient::connect(&endpoint)
+            .await
+            .expect("Should connect")
+            .expect("Should be listened");
+        let response = client
+            .request(&IpcRequest::Show)
+            .await
+            .expect("Should respond");
+        assert_eq!(response, IpcResponse::Done);
+
+        let command = Command::Next { monitor: Some(1) };
+        let response = client
+            .request(&IpcRequest::Command(command.clone()))
+            .await
+            .expect("Should respond");
+        assert_eq!(response, IpcResponse::Failed(format!("{command:?}")));
+    }
+
+    #[tokio::test]
+    async fn single_instance() {
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        let endpoint = Endpoint::new(directory.path().join("test.sock"));
+
+        let client = IpcClient::connect(&endpoint).await.expect("Should succeed");
+        assert!(client.is_none());
+
+        let server = IpcServer::bind(&endpoint).expect("Should bind");
+        assert!(IpcServer::bind(&endpoint).is_err());
+        drop(server);
+
+        // Stale socket file
+        File::create(directory.path().join("test.sock")).expect("Should create");
+        IpcServer::bind(&endpoint).expect("Should replace stale socket");
+    }
+}
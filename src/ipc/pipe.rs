@@ -0,0 +1,97 @@
+//! Named pipe transport.
+
+use std::{env, ffi::OsStr, io::ErrorKind, mem::replace, os::windows::ffi::OsStrExt};
+
+use anyhow::{bail, Context, Result};
+use tokio::{
+    net::windows::named_pipe::{ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions},
+    task::spawn_blocking,
+};
+use windows::{
+    core::PCWSTR,
+    Win32::{Foundation::ERROR_PIPE_BUSY, System::Pipes::WaitNamedPipeW},
+};
+
+pub type Stream = NamedPipeClient;
+
+/// Number of attempts to connect while the server is busy with other clients.
+const BUSY_ATTEMPTS: usize = 5;
+
+/// Milliseconds to wait for the server to be available in each attempt.
+const BUSY_TIMEOUT: u32 = 1000;
+
+/// Pipe name of the instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint(String);
+
+impl Endpoint {
+    /// Uses specified pipe name.
+    #[cfg(test)]
+    pub fn new(name: impl Into<String>) -> Endpoint {
+        Endpoint(name.into())
+    }
+}
+
+impl Default for Endpoint {
+    /// Separates pipe for each user.
+    fn default() -> Endpoint {
+        let user = env::var("USERNAME").unwrap_or_default();
+        Endpoint(format!(r"\\.\pipe\adwapach-{user}"))
+    }
+}
+
+/// Listens requests from other instances.
+pub struct IpcServer {
+    name: String,
+    next: NamedPipeServer,
+}
+
+impl IpcServer {
+    /// Starts listening. Fails if another instance is already listening.
+    /// Must be called in tokio runtime.
+    pub fn bind(endpoint: &Endpoint) -> Result<IpcServer> {
+        let next = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&endpoint.0)
+            .context("Another instance may be listening")?;
+
+        Ok(IpcServer {
+            name: endpoint.0.clone(),
+            next,
+        })
+    }
+
+    /// Accepts a connection.
+    pub async fn accept(&mut self) -> Result<NamedPipeServer> {
+        self.next.connect().await?;
+        let following = ServerOptions::new().create(&self.name)?;
+        Ok(replace(&mut self.next, following))
+    }
+}
+
+/// Connects to endpoint if it is listened.
+/// Waits for a free pipe instance while the server is busy.
+pub async fn connect_endpoint(endpoint: &Endpoint) -> Result<Option<Stream>> {
+    for _ in 0..BUSY_ATTEMPTS {
+        match ClientOptions::new().open(&endpoint.0) {
+            Ok(c) => return Ok(Some(c)),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY.0 as i32) => {
+                let name = endpoint.0.clone();
+                spawn_blocking(move || wait_pipe(&name)).await?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    bail!("Running instance is busy")
+}
+
+/// Blocks until an instance of the pipe is available or timed out.
+fn wait_pipe(name: &str) {
+    let mut wide_name: Vec<u16> = OsStr::new(name).encode_wide().collect();
+    wide_name.push(0);
+    // Failure is found by the next attempt to open
+    unsafe {
+        WaitNamedPipeW(PCWSTR(wide_name.as_ptr()), BUSY_TIMEOUT);
+    }
+}
@@ -0,0 +1,86 @@
+//! Unix domain socket transport.
+
+use std::{
+    env,
+    fs::remove_file,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use tokio::net::{UnixListener, UnixStream};
+
+pub type Stream = UnixStream;
+
+/// Socket path of the instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint(PathBuf);
+
+impl Endpoint {
+    /// Uses specified socket path.
+    #[cfg(test)]
+    pub fn new(path: impl Into<PathBuf>) -> Endpoint {
+        Endpoint(path.into())
+    }
+}
+
+impl Default for Endpoint {
+    /// Places socket in runtime directory, or temporary directory.
+    fn default() -> Endpoint {
+        match env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) => Endpoint(Path::new(&dir).join("adwapach.sock")),
+            None => {
+                let user = env::var("USER").unwrap_or_default();
+                Endpoint(env::temp_dir().join(format!("adwapach-{user}.sock")))
+            }
+        }
+    }
+}
+
+/// Listens requests from other instances.
+pub struct IpcServer {
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl IpcServer {
+    /// Starts listening. Fails if another instance is already listening.
+    /// Must be called in tokio runtime.
+    pub fn bind(endpoint: &Endpoint) -> Result<IpcServer> {
+        let path = endpoint.0.clone();
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                bail!("Another instance is listening at {}", path.display());
+            }
+
+            // Left by crashed instance
+            remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        Ok(IpcServer { listener, path })
+    }
+
+    /// Accepts a connection.
+    pub async fn accept(&mut self) -> Result<Stream> {
+        let (stream, _) = self.listener.accept().await?;
+        Ok(stream)
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        remove_file(&self.path).ok();
+    }
+}
+
+/// Connects to endpoint if it is listened.
+pub async fn connect_endpoint(endpoint: &Endpoint) -> Result<Option<Stream>> {
+    match UnixStream::connect(&endpoint.0).await {
+        Ok(s) => Ok(Some(s)),
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,23 +1,36 @@
 #![cfg_attr(production, windows_subsystem = "windows")]
 
 mod application;
+mod backend;
 mod background;
+mod cli;
 mod egui;
+mod ipc;
 mod mvvm;
+mod render;
+#[cfg(windows)]
 mod windows;
 
 use crate::{
     application::{Application, ApplicationView, ApplicationViewModel},
-    background::load_monitor_info,
-    egui::{EguiEvent, EguiWindow},
-    windows::{initialize_com, terminate_com},
+    backend::WallpaperBackend,
+    background::{
+        load_library, load_monitor_info, rotate_wallpapers, serve_requests, sync_sources,
+    },
+    cli::{run_command, show_running_instance, Arguments},
+    egui::{EguiEvent, EguiWindow, EventProxy},
+    ipc::{Endpoint, IpcServer},
+    render::rendered_directory,
 };
 
 use std::sync::Arc;
 
 use anyhow::Result;
+use clap::Parser;
 use flexi_logger::Logger;
 use log::error;
+use parking_lot::Mutex;
+use time::UtcOffset;
 use tokio::runtime::{Builder, Runtime};
 use winit::{
     event::Event,
@@ -26,13 +39,51 @@ use winit::{
 
 fn main() -> Result<()> {
     Logger::try_with_env()?.start()?;
-    let event_loop = EventLoop::with_user_event();
+    let arguments = Arguments::parse();
+    // Must be determined before any other thread starts
+    let local_offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
     let runtime = build_runtime()?;
-    initialize_com(false)?;
+    #[cfg(windows)]
+    crate::windows::initialize_com(false)?;
 
-    let application = Application::new();
+    let application = Application::new(platform_backend(), rendered_directory()?, local_offset);
+    match arguments.command {
+        Some(command) => {
+            #[cfg(windows)]
+            crate::windows::attach_parent_console();
+            runtime.block_on(run_command(application, command, arguments.json))
+        }
+        None => {
+            let server = {
+                let _guard = runtime.enter();
+                IpcServer::bind(&Endpoint::default())
+            };
+            let server = match server {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    if runtime.block_on(show_running_instance())? {
+                        return Ok(());
+                    }
+                    error!("Failed to listen requests: {e}");
+                    None
+                }
+            };
+            run_window(runtime, application, server, local_offset)
+        }
+    }
+}
+
+/// Opens the window and runs event loop.
+fn run_window(
+    runtime: Arc<Runtime>,
+    application: Arc<Mutex<Application>>,
+    server: Option<IpcServer>,
+    local_offset: UtcOffset,
+) -> Result<()> {
+    let event_loop = EventLoop::with_user_event();
+    let event_proxy = EventProxy::new(&event_loop);
     let application_viewmodel = ApplicationViewModel::new(application.clone());
-    let application_view = ApplicationView::new(application_viewmodel)?;
+    let application_view = ApplicationView::new(application_viewmodel.clone())?;
     let mut application_window = runtime.block_on(EguiWindow::create(
         &event_loop,
         runtime.clone(),
@@ -40,24 +91,33 @@ fn main() -> Result<()> {
     ))?;
 
     // Run async tasks
-    runtime.spawn(load_monitor_info(application));
+    if let Err(e) = runtime.block_on(load_library(application.clone())) {
+        error!("Failed to restore library: {e}");
+    }
+    runtime.spawn(load_monitor_info(application.clone()));
+    runtime.spawn(sync_sources(application.clone()));
+    runtime.spawn(rotate_wallpapers(application, local_offset));
+    if let Some(server) = server {
+        runtime.spawn(serve_requests(
+            server,
+            application_viewmodel,
+            event_proxy,
+            application_window.window_id(),
+        ));
+    }
 
     // Run UI thread
     event_loop.run(move |event, _, control_flow| match event {
-        Event::WindowEvent { window_id, event } => {
-            if window_id == application_window.window_id() {
-                application_window.update_with_event(event);
-            }
+        Event::WindowEvent { window_id, event } if window_id == application_window.window_id() => {
+            application_window.update_with_event(event);
         }
-        Event::RedrawRequested(window_id) => {
-            if window_id == application_window.window_id() {
-                match application_window.redraw() {
-                    Ok(f) => {
-                        *control_flow = f;
-                    }
-                    Err(e) => {
-                        error!("Redraw error: {}", e);
-                    }
+        Event::RedrawRequested(window_id) if window_id == application_window.window_id() => {
+            match application_window.redraw() {
+                Ok(f) => {
+                    *control_flow = f;
+                }
+                Err(e) => {
+                    error!("Redraw error: {}", e);
                 }
             }
         }
@@ -82,17 +142,46 @@ fn main() -> Result<()> {
 }
 
 fn build_runtime() -> Result<Arc<Runtime>> {
-    let tokio_runtime = Builder::new_multi_thread()
-        .on_thread_start(|| match initialize_com(false) {
-            Ok(()) => (),
-            Err(e) => {
-                error!("Failed to initialize com for thread: {e}");
-            }
-        })
-        .on_thread_stop(|| {
-            terminate_com();
-        })
-        .build()?;
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+
+    #[cfg(windows)]
+    {
+        use crate::windows::{initialize_com, terminate_com};
+
+        builder
+            .on_thread_start(|| match initialize_com(false) {
+                Ok(()) => (),
+                Err(e) => {
+                    error!("Failed to initialize com for thread: {e}");
+                }
+            })
+            .on_thread_stop(|| {
+                terminate_com();
+            });
+    }
 
+    let tokio_runtime = builder.build()?;
     Ok(Arc::new(tokio_runtime))
 }
+
+#[cfg(windows)]
+fn platform_backend() -> Arc<dyn WallpaperBackend> {
+    Arc::new(crate::windows::WallpaperInterface::new())
+}
+
+/// No wallpaper support yet, so it pretends to have one Full HD monitor.
+#[cfg(not(windows))]
+fn platform_backend() -> Arc<dyn WallpaperBackend> {
+    use crate::backend::{MemoryBackend, Monitor, MonitorId};
+    use vek::Vec2;
+
+    log::warn!("Wallpaper is not supported on this platform");
+    let monitor = Monitor::new(
+        MonitorId::new("memory"),
+        "Virtual Monitor",
+        Vec2::new(0, 0),
+        Vec2::new(1920, 1080),
+    );
+    Arc::new(MemoryBackend::new(vec![monitor]))
+}
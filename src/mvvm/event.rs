@@ -1,7 +1,8 @@
 use std::sync::{Arc, Weak};
 
 /// Subscription held by Observable and Subscriber.
-pub struct Subscription<M>(Arc<dyn Fn(M) + Send + Sync + 'static>);
+/// Subscriber keeps this only to make the subscription alive.
+pub struct Subscription<M>(#[allow(dead_code)] Arc<dyn Fn(M) + Send + Sync + 'static>);
 
 /// Weak reference of subscription function. Internally used.
 pub struct WeakSubscription<M>(Weak<dyn Fn(M) + Send + Sync + 'static>);
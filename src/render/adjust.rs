@@ -0,0 +1,143 @@
+//! Provides non-destructive image adjustments.
+
+use crate::application::Adjustments;
+
+use image::{imageops, imageops::FilterType, DynamicImage, RgbaImage};
+use vek::Vec2;
+
+/// Largest blur radius applied in full size. Stronger blur is applied in reduced size.
+const MAX_BLUR_SIGMA: f32 = 4.0;
+
+/// Rotates and flips the source image.
+pub fn orient(source: &DynamicImage, adjustments: Adjustments) -> DynamicImage {
+    let mut oriented = match adjustments.rotation % 4 {
+        1 => source.rotate90(),
+        2 => source.rotate180(),
+        3 => source.rotate270(),
+        _ => source.clone(),
+    };
+    if adjustments.flip_horizontal {
+        oriented = oriented.fliph();
+    }
+    if adjustments.flip_vertical {
+        oriented = oriented.flipv();
+    }
+    oriented
+}
+
+/// Applies color adjustments and blur to the image.
+pub fn adjust_colors(image: &mut RgbaImage, adjustments: Adjustments) {
+    if adjustments.grayscale {
+        for pixel in image.pixels_mut() {
+            let [r, g, b, _] = pixel.0;
+            let luma = (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) as u8;
+            pixel.0[..3].fill(luma);
+        }
+    }
+    if adjustments.brightness != 0.0 {
+        imageops::colorops::brighten_in_place(image, (adjustments.brightness * 255.0) as i32);
+    }
+    if adjustments.contrast != 0.0 {
+        imageops::colorops::contrast_in_place(image, adjustments.contrast * 100.0);
+    }
+    if adjustments.dim > 0.0 {
+        let factor = 1.0 - adjustments.dim.clamp(0.0, 1.0);
+        for pixel in image.pixels_mut() {
+            for channel in &mut pixel.0[..3] {
+                *channel = (*channel as f32 * factor) as u8;
+            }
+        }
+    }
+    if adjustments.blur > 0.0 {
+        let shorter = image.width().min(image.height()) as f32;
+        *image = blur(image, adjustments.blur / 100.0 * shorter);
+    }
+}
+
+/// Blurs the image. Strong blur is applied to a reduced copy, as details are lost anyway.
+fn blur(image: &RgbaImage, sigma: f32) -> RgbaImage {
+    if sigma <= MAX_BLUR_SIGMA {
+        return imageops::blur(image, sigma);
+    }
+
+    let scale = MAX_BLUR_SIGMA / sigma;
+    let (width, height) = image.dimensions();
+    let reduced_size = (Vec2::new(width, height).as_::<f32>() * scale)
+        .round()
+        .as_::<u32>()
+        .map(|x| x.max(1));
+    let reduced = imageops::resize(image, reduced_size.x, reduced_size.y, FilterType::Triangle);
+    let blurred = imageops::blur(&reduced, MAX_BLUR_SIGMA);
+    imageops::resize(&blurred, width, height, FilterType::Triangle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::FocalPoint;
+
+    use image::Rgba;
+
+    #[test]
+    fn orientation_matches_point() {
+        let mut source = RgbaImage::new(40, 20);
+        source.put_pixel(10, 5, Rgba([255, 0, 0, 255]));
+        let source = DynamicImage::ImageRgba8(source);
+
+        for rotation in 0..4 {
+            let adjustments = Adjustments {
+                rotation,
+                flip_horizontal: rotation % 2 == 1,
+                flip_vertical: rotation == 2,
+                ..Default::default()
+            };
+            let oriented = orient(&source, adjustments).to_rgba8();
+            let point = adjustments.orient_point(FocalPoint {
+                x: 10.5 / 40.0,
+                y: 5.5 / 20.0,
+            });
+            let x = (point.x * oriented.width() as f32) as u32;
+            let y = (point.y * oriented.height() as f32) as u32;
+            assert_eq!(oriented.get_pixel(x, y).0, [255, 0, 0, 255], "{rotation}");
+        }
+    }
+
+    #[test]
+    fn dim_and_grayscale() {
+        let mut image = RgbaImage::from_pixel(4, 4, Rgba([200, 100, 0, 255]));
+        adjust_colors(
+            &mut image,
+            Adjustments {
+                dim: 0.5,
+                grayscale: true,
+                ..Default::default()
+            },
+        );
+
+        let [r, g, b, a] = image.get_pixel(0, 0).0;
+        assert_eq!((r, a), (57, 255));
+        assert!(r == g && g == b);
+    }
+
+    #[test]
+    fn strong_blur_in_reduced_size() {
+        let mut image = RgbaImage::from_fn(400, 200, |x, _| match x < 200 {
+            true => Rgba([255, 255, 255, 255]),
+            false => Rgba([0, 0, 0, 255]),
+        });
+        adjust_colors(
+            &mut image,
+            Adjustments {
+                blur: 5.0,
+                ..Default::default()
+            },
+        );
+
+        // Kept in size, and the edge spreads smoothly
+        assert_eq!(image.dimensions(), (400, 200));
+        let red = |x| image.get_pixel(x, 100).0[0];
+        assert!(red(0) > 240 && red(399) < 15);
+        assert!((96..160).contains(&red(200)));
+        assert!(red(190) > red(200) && red(200) > red(210));
+    }
+}
@@ -0,0 +1,175 @@
+//! Provides composition of collage wallpapers.
+
+use crate::{
+    application::{Adjustments, Background, Collage, CollageLayout, Fitting, FocalPoint},
+    render::{adjust_colors, compose},
+};
+
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use vek::{Aabr, Vec2};
+
+/// Image placed in a cell of collage.
+pub struct CollageCell {
+    /// Oriented source image.
+    pub image: DynamicImage,
+    pub focus: FocalPoint,
+
+    /// Colors are applied to the cell.
+    pub adjustments: Adjustments,
+}
+
+/// Composes cells into a canvas according to the layout of the collage.
+/// Each cell is cover-fitted keeping its focal point visible.
+pub fn compose_collage(
+    cells: &[CollageCell],
+    canvas_size: Vec2<u32>,
+    collage: &Collage,
+) -> RgbaImage {
+    let [r, g, b] = collage.background;
+    let mut canvas = RgbaImage::from_pixel(canvas_size.x, canvas_size.y, Rgba([r, g, b, 255]));
+
+    let aspects: Vec<_> = cells
+        .iter()
+        .map(|c| c.image.width() as f32 / c.image.height().max(1) as f32)
+        .collect();
+    let gutter = collage.gutter.max(0.0) / 100.0 * canvas_size.y as f32;
+    let rects = layout_cells(&aspects, canvas_size.as_(), gutter, collage.layout);
+
+    for (cell, rect) in cells.iter().zip(rects) {
+        let min = rect.min.round().as_::<u32>();
+        let size = rect.max.round().as_::<u32>() - min;
+        if size.x == 0 || size.y == 0 {
+            continue;
+        }
+        let mut rendered = compose(
+            &cell.image,
+            size,
+            Fitting::Cover,
+            Background::default(),
+            cell.focus,
+        );
+        adjust_colors(&mut rendered, cell.adjustments);
+        imageops::overlay(&mut canvas, &rendered, min.x as i64, min.y as i64);
+    }
+    canvas
+}
+
+/// Calculates rectangles of cells for images with the aspect ratios.
+fn layout_cells(
+    aspects: &[f32],
+    canvas_size: Vec2<f32>,
+    gutter: f32,
+    layout: CollageLayout,
+) -> Vec<Aabr<f32>> {
+    if aspects.is_empty() {
+        return vec![];
+    }
+    let count = aspects.len();
+    let mean_aspect = aspects.iter().sum::<f32>() / count as f32;
+    let columns = column_count(count, canvas_size, gutter, mean_aspect);
+    let span = |length: f32, n: usize| ((length - gutter * (n + 1) as f32) / n as f32).max(0.0);
+    let offset = |length: f32, i: usize| gutter + (length + gutter) * i as f32;
+
+    match layout {
+        CollageLayout::Grid => {
+            let rows = count.div_ceil(columns);
+            let height = span(canvas_size.y, rows);
+            (0..count)
+                .map(|i| {
+                    let (row, column) = (i / columns, i % columns);
+                    let width = span(canvas_size.x, (count - row * columns).min(columns));
+                    let min = Vec2::new(offset(width, column), offset(height, row));
+                    Aabr {
+                        min,
+                        max: min + Vec2::new(width, height),
+                    }
+                })
+                .collect()
+        }
+        CollageLayout::Masonry => {
+            // Each image goes to the shortest column, then columns are scaled to fill
+            let width = span(canvas_size.x, columns);
+            let natural_heights: Vec<_> = aspects.iter().map(|a| 1.0 / a.max(0.01)).collect();
+            let mut stacks = vec![vec![]; columns];
+            let mut stack_heights = vec![0.0f32; columns];
+            for (i, height) in natural_heights.iter().enumerate() {
+                let column = (0..columns)
+                    .min_by(|&a, &b| stack_heights[a].total_cmp(&stack_heights[b]))
+                    .expect("Should have columns");
+                stacks[column].push(i);
+                stack_heights[column] += height;
+            }
+
+            let mut rects = vec![Aabr::default(); count];
+            for (column, stack) in stacks.iter().enumerate() {
+                let available = span(canvas_size.y, stack.len()) * stack.len() as f32;
+                let left = offset(width, column);
+                let mut top = gutter;
+                for &i in stack {
+                    let height = available * natural_heights[i] / stack_heights[column];
+                    rects[i] = Aabr {
+                        min: Vec2::new(left, top),
+                        max: Vec2::new(left + width, top + height),
+                    };
+                    top += height + gutter;
+                }
+            }
+            rects
+        }
+    }
+}
+
+/// Chooses the number of columns whose cells are the closest to the average aspect ratio.
+fn column_count(count: usize, canvas_size: Vec2<f32>, gutter: f32, mean_aspect: f32) -> usize {
+    let deviation = |columns: usize| {
+        let rows = count.div_ceil(columns);
+        let width = canvas_size.x - gutter * (columns + 1) as f32;
+        let height = canvas_size.y - gutter * (rows + 1) as f32;
+        let aspect = (width / columns as f32) / (height / rows as f32);
+        (aspect / mean_aspect).ln().abs()
+    };
+    (1..=count)
+        .min_by(|&a, &b| deviation(a).total_cmp(&deviation(b)))
+        .expect("Should have images")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_stretches_last_row() {
+        let rects = layout_cells(
+            &[16.0 / 9.0; 5],
+            Vec2::new(1920.0, 1080.0),
+            10.0,
+            CollageLayout::Grid,
+        );
+
+        assert_eq!(rects.len(), 5);
+        for rect in &rects[..3] {
+            assert_eq!(rect.size().h, 525.0);
+            assert!((rect.size().w - 1880.0 / 3.0).abs() < 0.01);
+        }
+        assert_eq!(rects[3].min, Vec2::new(10.0, 545.0));
+        assert_eq!(rects[4].max, Vec2::new(1910.0, 1070.0));
+        assert_eq!(rects[4].size().w, 945.0);
+    }
+
+    #[test]
+    fn masonry_fills_columns() {
+        let rects = layout_cells(
+            &[1.0, 0.5, 2.0, 1.0],
+            Vec2::new(1000.0, 1000.0),
+            0.0,
+            CollageLayout::Masonry,
+        );
+
+        // Tall one takes a column, others share another by their heights
+        assert_eq!(rects[1].min, Vec2::new(500.0, 0.0));
+        assert_eq!(rects[1].max, Vec2::new(1000.0, 1000.0));
+        assert_eq!(rects[0].size().h, 400.0);
+        assert_eq!(rects[2].size().h, 200.0);
+        assert_eq!(rects[3].max.y, 1000.0);
+    }
+}
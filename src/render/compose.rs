@@ -0,0 +1,453 @@
+//! Provides composition of a wallpaper into monitor-sized canvas.
+
+use crate::application::{Background, Fitting, FocalPoint};
+
+use std::collections::BTreeMap;
+
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, Rgba, RgbaImage,
+};
+use vek::{Aabr, Vec2};
+
+/// Filter used for scaling source images.
+const SCALING_FILTER: FilterType = FilterType::CatmullRom;
+
+/// Color of the area which is not covered by image.
+const LETTERBOX_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Size which images are reduced into for color analysis and blurring.
+const ANALYSIS_SIZE: u32 = 64;
+
+/// Standard deviation of blurred background, in reduced pixels.
+const BLUR_SIGMA: f32 = 3.0;
+
+/// Brightness multiplier of blurred background.
+const BLUR_BRIGHTNESS: f32 = 0.6;
+
+/// Renders source image into a canvas of specified size according to `Fitting`.
+/// `background` fills letterboxed area of `Center` and `Contain`,
+/// and `focus` is kept visible as possible in `Cover`.
+pub fn compose(
+    source: &DynamicImage,
+    canvas_size: Vec2<u32>,
+    fitting: Fitting,
+    background: Background,
+    focus: FocalPoint,
+) -> RgbaImage {
+    let source = source.to_rgba8();
+    let source_size = Vec2::new(source.width(), source.height()).as_::<f32>();
+    let scale = canvas_size.as_::<f32>() / source_size;
+
+    let mut canvas = match fitting {
+        Fitting::Center | Fitting::Contain => fill_background(&source, canvas_size, background),
+        _ => RgbaImage::from_pixel(canvas_size.x, canvas_size.y, LETTERBOX_COLOR),
+    };
+    match fitting {
+        Fitting::Center => place_centered(&mut canvas, &source),
+        Fitting::Tile => imageops::tile(&mut canvas, &source),
+        Fitting::Stretch => {
+            let scaled = imageops::resize(&source, canvas_size.x, canvas_size.y, SCALING_FILTER);
+            imageops::overlay(&mut canvas, &scaled, 0, 0);
+        }
+        Fitting::Contain => {
+            let scaled = scale_uniformly(&source, scale.x.min(scale.y));
+            place_centered(&mut canvas, &scaled);
+        }
+        Fitting::Cover | Fitting::Span => {
+            let scaled = scale_uniformly(&source, scale.x.max(scale.y));
+            let scaled_size = Vec2::new(scaled.width(), scaled.height()).as_();
+            // Truncated as well as `place_centered` does
+            let offset = focused_offset(scaled_size, canvas_size.as_(), focus).map(f32::trunc);
+            imageops::overlay(&mut canvas, &scaled, offset.x as i64, offset.y as i64);
+        }
+    }
+
+    canvas
+}
+
+/// Renders a slice of source image spanned across monitors.
+/// The source covers `desktop_size`, and the area at `slice_position` of `slice_size` is cut out
+/// and scaled into `canvas_size`. Desktop coordinates may be in any unit.
+pub fn compose_span(
+    source: &DynamicImage,
+    desktop_size: Vec2<f32>,
+    slice_position: Vec2<f32>,
+    slice_size: Vec2<f32>,
+    canvas_size: Vec2<u32>,
+    focus: FocalPoint,
+) -> RgbaImage {
+    let source = source.to_rgba8();
+    let source_size = Vec2::new(source.width(), source.height()).as_::<f32>();
+    let cover = desktop_size / source_size;
+    let cover = cover.x.max(cover.y);
+
+    // Slice area in source coordinates, with margin for the scaling filter
+    let desktop_origin = -focused_offset(source_size * cover, desktop_size, focus);
+    let slice_min = (desktop_origin + slice_position) / cover;
+    let slice_max = slice_min + slice_size / cover;
+    let scale = canvas_size.as_::<f32>() / (slice_max - slice_min);
+    let margin = scale.map(|s| (2.0 / s.min(1.0)).ceil() + 1.0);
+    let region_min = (slice_min - margin)
+        .floor()
+        .map2(source_size, |v, s| v.clamp(0.0, s));
+    let region_max = (slice_max + margin)
+        .ceil()
+        .map2(source_size, |v, s| v.clamp(0.0, s));
+    let region_size = (region_max - region_min).map(|v| v.max(1.0));
+
+    let region = imageops::crop_imm(
+        &source,
+        region_min.x as u32,
+        region_min.y as u32,
+        region_size.x as u32,
+        region_size.y as u32,
+    )
+    .to_image();
+    let region_scaled_size = (region_size * scale).round().as_::<u32>().map(|x| x.max(1));
+    let scaled = imageops::resize(
+        &region,
+        region_scaled_size.x,
+        region_scaled_size.y,
+        SCALING_FILTER,
+    );
+
+    let mut canvas = RgbaImage::from_pixel(canvas_size.x, canvas_size.y, LETTERBOX_COLOR);
+    let offset = ((region_min - slice_min) * scale).round();
+    imageops::overlay(&mut canvas, &scaled, offset.x as i64, offset.y as i64);
+    canvas
+}
+
+/// Calculates the area of source visible with `Fitting::Cover`.
+/// The area is normalized into 0.0 to 1.0 from top-left of the source.
+pub fn cover_area(source_size: Vec2<u32>, canvas_size: Vec2<u32>, focus: FocalPoint) -> Aabr<f32> {
+    let source_size = source_size.as_::<f32>().map(|x| x.max(1.0));
+    let canvas_size = canvas_size.as_::<f32>();
+    let scale = canvas_size / source_size;
+    let scaled_size = source_size * scale.x.max(scale.y);
+
+    let offset = focused_offset(scaled_size, canvas_size, focus);
+    Aabr {
+        min: -offset / scaled_size,
+        max: (canvas_size - offset) / scaled_size,
+    }
+}
+
+/// Calculates top-left position of scaled image on canvas,
+/// so that the focal point comes to the center without uncovered area.
+fn focused_offset(scaled_size: Vec2<f32>, canvas_size: Vec2<f32>, focus: FocalPoint) -> Vec2<f32> {
+    let offset = canvas_size / 2.0 - Vec2::new(focus.x, focus.y) * scaled_size;
+    let limit = canvas_size - scaled_size;
+    offset.map2(limit, |o, l| o.clamp(l.min(0.0), l.max(0.0)))
+}
+
+/// Makes a canvas filled with the background for the source.
+fn fill_background(
+    source: &RgbaImage,
+    canvas_size: Vec2<u32>,
+    background: Background,
+) -> RgbaImage {
+    let color = match background {
+        Background::Solid([r, g, b]) => Rgba([r, g, b, 255]),
+        Background::Dominant => dominant_color(source),
+        Background::Edge => edge_color(source),
+        Background::Blurred => return blurred_fill(source, canvas_size),
+    };
+    RgbaImage::from_pixel(canvas_size.x, canvas_size.y, color)
+}
+
+/// Finds the most frequent color, quantized into 4 bits for each channel.
+fn dominant_color(source: &RgbaImage) -> Rgba<u8> {
+    let reduced = imageops::thumbnail(source, ANALYSIS_SIZE, ANALYSIS_SIZE);
+    let mut bins: BTreeMap<[u8; 3], (u32, [u32; 3])> = BTreeMap::new();
+    for pixel in reduced.pixels().filter(|p| p.0[3] > 0) {
+        let [r, g, b, _] = pixel.0;
+        let (count, sum) = bins.entry([r >> 4, g >> 4, b >> 4]).or_default();
+        *count += 1;
+        for (s, c) in sum.iter_mut().zip([r, g, b]) {
+            *s += c as u32;
+        }
+    }
+
+    // Averaged in the bin, or the color will be posterized
+    bins.into_values()
+        .max_by_key(|(count, _)| *count)
+        .map(|(count, [r, g, b])| {
+            Rgba([(r / count) as u8, (g / count) as u8, (b / count) as u8, 255])
+        })
+        .unwrap_or(LETTERBOX_COLOR)
+}
+
+/// Calculates average color of the outermost pixels.
+fn edge_color(source: &RgbaImage) -> Rgba<u8> {
+    let (width, height) = source.dimensions();
+    let mut count = 0;
+    let mut sum = [0u64; 3];
+    for (_, _, pixel) in source
+        .enumerate_pixels()
+        .filter(|(x, y, _)| *x == 0 || *y == 0 || *x + 1 == width || *y + 1 == height)
+    {
+        count += 1;
+        for (s, c) in sum.iter_mut().zip(pixel.0) {
+            *s += c as u64;
+        }
+    }
+
+    match count {
+        0 => LETTERBOX_COLOR,
+        _ => Rgba([
+            (sum[0] / count) as u8,
+            (sum[1] / count) as u8,
+            (sum[2] / count) as u8,
+            255,
+        ]),
+    }
+}
+
+/// Makes blurred and darkened copy of the source stretched into canvas size.
+fn blurred_fill(source: &RgbaImage, canvas_size: Vec2<u32>) -> RgbaImage {
+    // Blurred in reduced size, as details are lost anyway
+    let reduced_size = (canvas_size.as_::<f32>() * ANALYSIS_SIZE as f32
+        / canvas_size.reduce_max().max(1) as f32)
+        .round()
+        .as_::<u32>()
+        .map(|x| x.max(1));
+    let reduced = imageops::resize(source, reduced_size.x, reduced_size.y, FilterType::Triangle);
+    let blurred = imageops::blur(&reduced, BLUR_SIGMA);
+
+    let mut filled = imageops::resize(&blurred, canvas_size.x, canvas_size.y, FilterType::Triangle);
+    for pixel in filled.pixels_mut() {
+        for c in &mut pixel.0[..3] {
+            *c = (*c as f32 * BLUR_BRIGHTNESS) as u8;
+        }
+        pixel.0[3] = 255;
+    }
+    filled
+}
+
+/// Scales image keeping its aspect ratio.
+fn scale_uniformly(source: &RgbaImage, scale: f32) -> RgbaImage {
+    let scaled_size = (Vec2::new(source.width(), source.height()).as_::<f32>() * scale)
+        .round()
+        .as_::<u32>()
+        .map(|x| x.max(1));
+    imageops::resize(source, scaled_size.x, scaled_size.y, SCALING_FILTER)
+}
+
+/// Places image at the center of canvas. Overflowed area will be cropped.
+fn place_centered(canvas: &mut RgbaImage, image: &RgbaImage) {
+    let x = (canvas.width() as i64 - image.width() as i64) / 2;
+    let y = (canvas.height() as i64 - image.height() as i64) / 2;
+    imageops::overlay(canvas, image, x, y);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, path::PathBuf};
+
+    /// Set this to regenerate golden images instead of comparing.
+    const UPDATE_GOLDEN_ENV: &str = "ADWAPACH_UPDATE_GOLDEN";
+
+    /// Makes 20x12 source image with 4 colored quadrants and a white frame.
+    fn source_image() -> DynamicImage {
+        let image = RgbaImage::from_fn(20, 12, |x, y| {
+            if x == 0 || y == 0 || x == 19 || y == 11 {
+                return Rgba([255, 255, 255, 255]);
+            }
+            match (x < 10, y < 6) {
+                (true, true) => Rgba([255, 0, 0, 255]),
+                (false, true) => Rgba([0, 255, 0, 255]),
+                (true, false) => Rgba([0, 0, 255, 255]),
+                (false, false) => Rgba([255, 255, 0, 255]),
+            }
+        });
+        DynamicImage::ImageRgba8(image)
+    }
+
+    fn assert_golden(fitting: Fitting, name: &str) {
+        let rendered = compose(
+            &source_image(),
+            Vec2::new(48, 32),
+            fitting,
+            Background::default(),
+            FocalPoint::default(),
+        );
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("resources/golden")
+            .join(format!("{name}.png"));
+
+        if env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            rendered.save(&path).expect("Failed to write golden image");
+            return;
+        }
+
+        let golden = image::open(&path)
+            .expect("Golden image not found")
+            .to_rgba8();
+        assert_eq!(rendered.dimensions(), golden.dimensions());
+        for (x, y, expected) in golden.enumerate_pixels() {
+            let actual = rendered.get_pixel(x, y);
+            let matched = actual
+                .0
+                .iter()
+                .zip(expected.0.iter())
+                .all(|(a, e)| a.abs_diff(*e) <= 1);
+            assert!(
+                matched,
+                "{name}: ({x}, {y}) is {actual:?}, expected {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fitting_center() {
+        assert_golden(Fitting::Center, "center");
+    }
+
+    #[test]
+    fn fitting_tile() {
+        assert_golden(Fitting::Tile, "tile");
+    }
+
+    #[test]
+    fn fitting_stretch() {
+        assert_golden(Fitting::Stretch, "stretch");
+    }
+
+    #[test]
+    fn fitting_contain() {
+        assert_golden(Fitting::Contain, "contain");
+    }
+
+    #[test]
+    fn fitting_cover() {
+        assert_golden(Fitting::Cover, "cover");
+    }
+
+    #[test]
+    fn cover_keeps_focus() {
+        let focus = FocalPoint { x: 0.9, y: 0.5 };
+        let area = cover_area(Vec2::new(100, 50), Vec2::new(50, 50), focus);
+        assert_eq!(area.min, Vec2::new(0.5, 0.0));
+        assert_eq!(area.max, Vec2::new(1.0, 1.0));
+        let area = cover_area(
+            Vec2::new(100, 50),
+            Vec2::new(50, 50),
+            FocalPoint { x: 0.6, y: 0.1 },
+        );
+        assert!((area.min.x - 0.35).abs() < 1e-6);
+
+        // Right quadrants are green and yellow
+        let rendered = compose(
+            &source_image(),
+            Vec2::new(12, 24),
+            Fitting::Cover,
+            Background::default(),
+            focus,
+        );
+        assert_eq!(*rendered.get_pixel(4, 4), Rgba([0, 255, 0, 255]));
+        assert_eq!(*rendered.get_pixel(4, 20), Rgba([255, 255, 0, 255]));
+    }
+
+    #[test]
+    fn letterbox_backgrounds() {
+        let render = |background| {
+            compose(
+                &source_image(),
+                Vec2::new(48, 32),
+                Fitting::Contain,
+                background,
+                FocalPoint::default(),
+            )
+        };
+        assert_eq!(
+            *render(Background::Solid([10, 20, 30])).get_pixel(0, 0),
+            Rgba([10, 20, 30, 255])
+        );
+        assert_eq!(
+            *render(Background::Edge).get_pixel(0, 0),
+            Rgba([255, 255, 255, 255])
+        );
+
+        let blurred = render(Background::Blurred);
+        let corner = blurred.get_pixel(0, 0);
+        assert_ne!(*corner, LETTERBOX_COLOR);
+        assert!(corner.0[..3].iter().all(|c| *c <= 154));
+    }
+
+    #[test]
+    fn dominant_color_is_most_frequent() {
+        let image = RgbaImage::from_fn(100, 100, |x, _| {
+            if x < 70 {
+                Rgba([20, 40, 200, 255])
+            } else {
+                Rgba([250, 0, 0, 255])
+            }
+        });
+        assert_eq!(dominant_color(&image), Rgba([20, 40, 200, 255]));
+    }
+
+    #[test]
+    fn span_slices_continue() {
+        let source = DynamicImage::ImageRgba8(RgbaImage::from_fn(300, 100, |x, y| {
+            Rgba([(x * 255 / 299) as u8, (y * 255 / 99) as u8, 128, 255])
+        }));
+        let whole = compose(
+            &source,
+            Vec2::new(160, 40),
+            Fitting::Cover,
+            Background::default(),
+            FocalPoint::default(),
+        );
+        let desktop_size = Vec2::new(160.0, 40.0);
+        let left = compose_span(
+            &source,
+            desktop_size,
+            Vec2::zero(),
+            Vec2::new(64.0, 40.0),
+            Vec2::new(64, 40),
+            FocalPoint::default(),
+        );
+        let right = compose_span(
+            &source,
+            desktop_size,
+            Vec2::new(64.0, 10.0),
+            Vec2::new(96.0, 30.0),
+            Vec2::new(96, 30),
+            FocalPoint::default(),
+        );
+
+        assert_eq!(left.dimensions(), (64, 40));
+        assert_eq!(right.dimensions(), (96, 30));
+        for (slice, position) in [(left, Vec2::new(0, 0)), (right, Vec2::new(64, 10))] {
+            for (x, y, actual) in slice.enumerate_pixels() {
+                let expected = whole.get_pixel(x + position.x, y + position.y);
+                let matched = actual
+                    .0
+                    .iter()
+                    .zip(expected.0.iter())
+                    .all(|(a, e)| a.abs_diff(*e) <= 4);
+                assert!(matched, "({x}, {y}) is {actual:?}, expected {expected:?}");
+            }
+        }
+
+        // Denser monitor gets the same area in more pixels
+        let dense = compose_span(
+            &source,
+            desktop_size,
+            Vec2::new(64.0, 10.0),
+            Vec2::new(96.0, 30.0),
+            Vec2::new(192, 60),
+            FocalPoint::default(),
+        );
+        assert_eq!(dense.dimensions(), (192, 60));
+        let (expected, actual) = (whole.get_pixel(100, 20), dense.get_pixel(72, 20));
+        assert!(actual
+            .0
+            .iter()
+            .zip(expected.0.iter())
+            .all(|(a, e)| a.abs_diff(*e) <= 4));
+    }
+}
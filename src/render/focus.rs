@@ -0,0 +1,126 @@
+//! Provides automatic detection of focal point.
+
+use crate::application::FocalPoint;
+
+use std::{fs::File, io::copy, path::Path};
+
+use anyhow::Result;
+use image::{DynamicImage, GrayImage};
+use sha2::{Digest, Sha256};
+
+/// Size which images are reduced into for detection.
+const DETECTION_SIZE: u32 = 128;
+
+/// Size of square cells scored for detection, in reduced pixels.
+const CELL_SIZE: u32 = 8;
+
+/// Number of luminance bins for entropy.
+const ENTROPY_BINS: usize = 16;
+
+/// Detects the most salient point of the image.
+/// Each cell is scored by its edge density and luminance entropy,
+/// and the point is the centroid of cells scoring above average.
+pub fn detect_focal_point(image: &DynamicImage) -> FocalPoint {
+    let luma = image.thumbnail(DETECTION_SIZE, DETECTION_SIZE).to_luma8();
+    let (width, height) = luma.dimensions();
+    let columns = width.div_ceil(CELL_SIZE);
+    let rows = height.div_ceil(CELL_SIZE);
+
+    let mut cells = vec![];
+    for row in 0..rows {
+        for column in 0..columns {
+            let (left, top) = (column * CELL_SIZE, row * CELL_SIZE);
+            let right = (left + CELL_SIZE).min(width);
+            let bottom = (top + CELL_SIZE).min(height);
+            let score = score_cell(&luma, left, top, right, bottom);
+            cells.push((
+                (left + right) as f32 / 2.0,
+                (top + bottom) as f32 / 2.0,
+                score,
+            ));
+        }
+    }
+
+    let average = cells.iter().map(|c| c.2).sum::<f32>() / cells.len().max(1) as f32;
+    let (mut sum_x, mut sum_y, mut sum_weight) = (0.0, 0.0, 0.0);
+    for (x, y, score) in cells {
+        let weight = (score - average).max(0.0);
+        sum_x += x * weight;
+        sum_y += y * weight;
+        sum_weight += weight;
+    }
+    if sum_weight <= f32::EPSILON {
+        return FocalPoint::default();
+    }
+
+    FocalPoint {
+        x: (sum_x / sum_weight / width as f32).clamp(0.0, 1.0),
+        y: (sum_y / sum_weight / height as f32).clamp(0.0, 1.0),
+    }
+}
+
+/// Calculates SHA-256 of the file content as hex string, for caching detected points.
+pub fn content_hash(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    copy(&mut File::open(path)?, &mut hasher)?;
+    let digest = hasher.finalize();
+    Ok(digest.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Scores a cell by mean gradient and entropy, both normalized into 0.0 to 1.0.
+fn score_cell(luma: &GrayImage, left: u32, top: u32, right: u32, bottom: u32) -> f32 {
+    let (width, height) = luma.dimensions();
+    let at = |x: u32, y: u32| luma.get_pixel(x.min(width - 1), y.min(height - 1)).0[0] as f32;
+
+    let mut gradient = 0.0;
+    let mut histogram = [0u32; ENTROPY_BINS];
+    for y in top..bottom {
+        for x in left..right {
+            let dx = at(x + 1, y) - at(x.saturating_sub(1), y);
+            let dy = at(x, y + 1) - at(x, y.saturating_sub(1));
+            gradient += dx.abs() + dy.abs();
+            histogram[at(x, y) as usize * ENTROPY_BINS / 256] += 1;
+        }
+    }
+
+    let count = ((right - left) * (bottom - top)).max(1) as f32;
+    let entropy: f32 = histogram
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f32 / count;
+            -p * p.log2()
+        })
+        .sum();
+
+    gradient / count / (255.0 * 4.0) + entropy / (ENTROPY_BINS as f32).log2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn detects_textured_region() {
+        let mut image = RgbaImage::from_pixel(400, 200, Rgba([90, 120, 150, 255]));
+        for y in 20..80 {
+            for x in 300..360 {
+                let value = if (x / 3 + y / 3) % 2 == 0 { 255 } else { 0 };
+                image.put_pixel(x, y, Rgba([value, value, value, 255]));
+            }
+        }
+
+        let point = detect_focal_point(&DynamicImage::ImageRgba8(image));
+        assert!((point.x - 0.825).abs() < 0.05, "{point:?}");
+        assert!((point.y - 0.25).abs() < 0.05, "{point:?}");
+    }
+
+    #[test]
+    fn flat_image_is_centered() {
+        let image = RgbaImage::from_pixel(300, 200, Rgba([10, 20, 30, 255]));
+        let point = detect_focal_point(&DynamicImage::ImageRgba8(image));
+        assert_eq!(point, FocalPoint::default());
+    }
+}
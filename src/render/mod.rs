@@ -0,0 +1,84 @@
+mod adjust;
+mod collage;
+mod compose;
+mod focus;
+mod overlay;
+
+use std::{
+    fs::{create_dir_all, read_dir, remove_file},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use image::RgbaImage;
+use log::warn;
+use sha2::{Digest, Sha256};
+
+pub use self::{
+    adjust::{adjust_colors, orient},
+    collage::{compose_collage, CollageCell},
+    compose::{compose, compose_span, cover_area},
+    focus::{content_hash, detect_focal_point},
+    overlay::draw_overlays,
+};
+
+/// Gets the directory where rendered wallpapers are written.
+pub fn rendered_directory() -> Result<PathBuf> {
+    let project = ProjectDirs::from("rs", "", "adwapach").context("No home directory")?;
+    Ok(project.cache_dir().join("rendered"))
+}
+
+/// Saves rendered wallpaper for a monitor into the directory and returns its path.
+/// Each call yields a new path, and previous ones for the same monitor are removed.
+pub fn save_rendered(directory: &Path, monitor_key: &str, image: &RgbaImage) -> Result<PathBuf> {
+    create_dir_all(directory)?;
+
+    // Stable across builds, so that files written by previous versions are removed too
+    let digest = Sha256::digest(monitor_key.as_bytes());
+    let prefix: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+    let prefix = format!("{prefix}-");
+    for entry in read_dir(directory)? {
+        let path = entry?.path();
+        let is_stale = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.starts_with(&prefix))
+            .unwrap_or(false);
+        if is_stale {
+            if let Err(e) = remove_file(&path) {
+                warn!("Failed to remove {}: {e}", path.display());
+            }
+        }
+    }
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let path = directory.join(format!("{prefix}{timestamp}.png"));
+    image.save(&path)?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn save_rendered_replaces_previous() {
+        let directory = TempDir::new().expect("Failed to create temporary directory");
+        let image = RgbaImage::new(4, 4);
+        save_rendered(directory.path(), "primary", &image).expect("Should save");
+        save_rendered(directory.path(), "secondary", &image).expect("Should save");
+        let path = save_rendered(directory.path(), "primary", &image).expect("Should save");
+
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .expect("Should have name");
+        assert!(name.starts_with("986a1b7135f49861-"));
+        assert_eq!(read_dir(directory.path()).expect("Should read").count(), 2);
+    }
+}
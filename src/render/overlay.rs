@@ -0,0 +1,285 @@
+//! Provides text rasterization of overlays.
+
+use crate::application::{Caption, Overlay, OverlayContent};
+
+use std::{borrow::Cow, fs::read};
+
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use anyhow::Result;
+use egui::FontDefinitions;
+use image::RgbaImage;
+use log::error;
+use time::{Date, Duration, OffsetDateTime};
+
+/// Name of bundled font used by default.
+const DEFAULT_FONT_NAME: &str = "Ubuntu-Light";
+
+/// Margin from monitor edges, in percent of the monitor height.
+const MARGIN: f32 = 3.0;
+
+/// Gap between calendar columns, relative to text size.
+const CALENDAR_GAP: f32 = 0.5;
+
+/// Opacity of shadows.
+const SHADOW_ALPHA: f32 = 0.6;
+
+/// Draws overlays onto the rendered wallpaper.
+/// `local` is the time shown by timed overlays.
+pub fn draw_overlays(
+    image: &mut RgbaImage,
+    overlays: &[Overlay],
+    caption: &Caption,
+    local: OffsetDateTime,
+) {
+    for overlay in overlays {
+        let rows = overlay_rows(overlay, caption, local);
+        if rows.is_empty() {
+            continue;
+        }
+        let font = match load_font(&overlay.font) {
+            Ok(f) => f,
+            Err(e) => {
+                error!("Failed to load font {}: {e}", overlay.font);
+                default_font()
+            }
+        };
+        draw_block(image, &font, overlay, &rows);
+    }
+}
+
+/// Makes text of the overlay, as rows of cells.
+fn overlay_rows(overlay: &Overlay, caption: &Caption, local: OffsetDateTime) -> Vec<Vec<String>> {
+    let text = match overlay.content {
+        OverlayContent::Filename => caption.filename.clone(),
+        OverlayContent::TakenAt => caption.taken_at.clone().unwrap_or_default(),
+        OverlayContent::CustomText => overlay.text.clone(),
+        OverlayContent::Date => format!(
+            "{}, {} {} {}",
+            local.weekday(),
+            local.day(),
+            local.month(),
+            local.year()
+        ),
+        OverlayContent::Clock => format!("{:02}:{:02}", local.hour(), local.minute()),
+        OverlayContent::Calendar => return calendar_rows(local.date()),
+    };
+    text.lines()
+        .filter(|l| !l.is_empty())
+        .map(|l| vec![l.to_string()])
+        .collect()
+}
+
+/// Makes calendar of the month, weeks starting from Monday.
+fn calendar_rows(today: Date) -> Vec<Vec<String>> {
+    let mut rows = vec![
+        vec![format!("{} {}", today.month(), today.year())],
+        ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"]
+            .iter()
+            .map(|d| d.to_string())
+            .collect(),
+    ];
+
+    let first = today - Duration::days(today.day() as i64 - 1);
+    let mut week = vec![String::new(); first.weekday().number_days_from_monday() as usize];
+    let mut day = first;
+    while day.month() == today.month() {
+        week.push(day.day().to_string());
+        if week.len() == 7 {
+            rows.push(week);
+            week = vec![];
+        }
+        day += Duration::days(1);
+    }
+    if !week.is_empty() {
+        week.resize(7, String::new());
+        rows.push(week);
+    }
+    rows
+}
+
+/// Loads the font file, or bundled one if the path is empty.
+fn load_font(path: &str) -> Result<FontArc> {
+    if path.is_empty() {
+        return Ok(default_font());
+    }
+    Ok(FontArc::try_from_vec(read(path)?)?)
+}
+
+/// Gets bundled font.
+fn default_font() -> FontArc {
+    let mut definitions = FontDefinitions::default();
+    let data = definitions
+        .font_data
+        .remove(DEFAULT_FONT_NAME)
+        .expect("Should be bundled");
+    match data.font {
+        Cow::Borrowed(b) => FontArc::try_from_slice(b),
+        Cow::Owned(v) => FontArc::try_from_vec(v),
+    }
+    .expect("Should be valid font")
+}
+
+/// Draws rows of text aligned to the anchor of the overlay.
+/// Rows of multiple cells are laid out in columns with right-aligned cells.
+fn draw_block(image: &mut RgbaImage, font: &FontArc, overlay: &Overlay, rows: &[Vec<String>]) {
+    let height = image.height() as f32;
+    let scale = PxScale::from((overlay.size / 100.0 * height).max(1.0));
+    let scaled = font.as_scaled(scale);
+    let line_height = scaled.height() + scaled.line_gap();
+    let measure = |text: &str| text_width(font, scale, text);
+
+    let column_width = rows
+        .iter()
+        .filter(|r| r.len() > 1)
+        .flatten()
+        .map(|c| measure(c))
+        .fold(0.0, f32::max)
+        + scale.y * CALENDAR_GAP;
+    let row_width = |row: &Vec<String>| match row.len() {
+        1 => measure(&row[0]),
+        n => column_width * n as f32 - scale.y * CALENDAR_GAP,
+    };
+    let block_width = rows.iter().map(row_width).fold(0.0, f32::max);
+    let block_height = line_height * rows.len() as f32;
+
+    let margin = MARGIN / 100.0 * height;
+    let (align_x, align_y) = overlay.anchor.alignment();
+    let area = (image.width() as f32 - margin * 2.0, height - margin * 2.0);
+    let left = margin + (area.0 - block_width) * align_x;
+    let top = margin + (area.1 - block_height) * align_y;
+
+    let [r, g, b] = overlay.color;
+    let shadow_offset = (scale.y / 16.0).max(1.0);
+    for (i, row) in rows.iter().enumerate() {
+        let baseline = top + line_height * i as f32 + scaled.ascent();
+        let cells: Vec<_> = match row.len() {
+            1 => vec![(left + (block_width - measure(&row[0])) * align_x, &row[0])],
+            _ => row
+                .iter()
+                .enumerate()
+                .map(|(j, c)| {
+                    let right = left + column_width * (j + 1) as f32 - scale.y * CALENDAR_GAP;
+                    (right - measure(c), c)
+                })
+                .collect(),
+        };
+
+        for (x, text) in cells {
+            if overlay.shadow {
+                let position = (x + shadow_offset, baseline + shadow_offset);
+                draw_text(image, font, scale, position, text, [0, 0, 0], SHADOW_ALPHA);
+            }
+            draw_text(image, font, scale, (x, baseline), text, [r, g, b], 1.0);
+        }
+    }
+}
+
+/// Measures advance width of the text.
+fn text_width(font: &FontArc, scale: PxScale, text: &str) -> f32 {
+    let scaled = font.as_scaled(scale);
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(p) = previous {
+            width += scaled.kern(p, id);
+        }
+        width += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Draws a line of text with its baseline starting at `position`.
+fn draw_text(
+    image: &mut RgbaImage,
+    font: &FontArc,
+    scale: PxScale,
+    position: (f32, f32),
+    text: &str,
+    color: [u8; 3],
+    alpha: f32,
+) {
+    let scaled = font.as_scaled(scale);
+    let (mut x, y) = position;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(p) = previous {
+            x += scaled.kern(p, id);
+        }
+        previous = Some(id);
+
+        let glyph = id.with_scale_and_position(scale, point(x, y));
+        x += scaled.h_advance(id);
+        let outlined = match font.outline_glyph(glyph) {
+            Some(o) => o,
+            None => continue,
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px < 0 || py < 0 || px >= image.width() as i64 || py >= image.height() as i64 {
+                return;
+            }
+            let a = coverage.clamp(0.0, 1.0) * alpha;
+            let pixel = image.get_pixel_mut(px as u32, py as u32);
+            for (dst, src) in pixel.0.iter_mut().zip(color) {
+                *dst = (*dst as f32 * (1.0 - a) + src as f32 * a).round() as u8;
+            }
+            pixel.0[3] = 255;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::OverlayAnchor;
+
+    use std::ops::Range;
+
+    use image::Rgba;
+    use time::Month;
+
+    /// Checks whether any pixel in the area is not black.
+    fn is_drawn(image: &RgbaImage, x: Range<u32>, y: Range<u32>) -> bool {
+        y.flat_map(|y| x.clone().map(move |x| (x, y)))
+            .any(|(x, y)| *image.get_pixel(x, y) != Rgba([0, 0, 0, 255]))
+    }
+
+    #[test]
+    fn clock_at_anchor() {
+        let mut image = RgbaImage::from_pixel(400, 200, Rgba([0, 0, 0, 255]));
+        let overlay = Overlay {
+            content: OverlayContent::Clock,
+            size: 20.0,
+            anchor: OverlayAnchor::TopLeft,
+            shadow: false,
+            ..Default::default()
+        };
+        draw_overlays(
+            &mut image,
+            &[overlay],
+            &Caption::default(),
+            Date::from_calendar_date(2026, Month::October, 16)
+                .and_then(|d| d.with_hms(12, 34, 0))
+                .expect("Should be valid")
+                .assume_utc(),
+        );
+
+        assert!(is_drawn(&image, 0..200, 0..100));
+        assert!(!is_drawn(&image, 200..400, 0..200));
+        assert!(!is_drawn(&image, 0..400, 100..200));
+    }
+
+    #[test]
+    fn calendar_starts_on_weekday() {
+        let today = Date::from_calendar_date(2026, Month::October, 16).expect("Should be valid");
+        let rows = calendar_rows(today);
+        assert_eq!(rows[0], vec!["October 2026".to_string()]);
+        assert_eq!(rows[2], vec!["", "", "", "1", "2", "3", "4"]);
+        assert_eq!(rows.last().expect("Should have weeks")[5], "31");
+    }
+}
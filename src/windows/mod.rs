@@ -6,17 +6,19 @@ use std::ptr::null;
 
 use anyhow::Result;
 use windows::Win32::{
-    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    Foundation::{HWND, LPARAM, LRESULT, POINT, WPARAM},
+    Graphics::Gdi::ScreenToClient,
     System::Com::{
         CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED, COINIT_DISABLE_OLE1DDE,
         COINIT_MULTITHREADED,
     },
-    UI::Shell::DefSubclassProc,
+    System::Console::{AttachConsole, ATTACH_PARENT_PROCESS},
+    UI::{Shell::DefSubclassProc, WindowsAndMessaging::GetCursorPos},
 };
 
 pub use self::notify_icon::NotifyIcon;
 pub use self::popup_menu::{MenuItem, PopupMenu};
-pub use self::wallpaper::{Monitor, WallpaperInterface};
+pub use self::wallpaper::WallpaperInterface;
 
 /// Initializes COM.
 pub fn initialize_com(multi_threaded: bool) -> Result<()> {
@@ -37,6 +39,23 @@ pub fn terminate_com() {
     }
 }
 
+/// Attaches to the console of parent process, so that output of commands is visible
+/// even in GUI subsystem builds. Returns `false` if there is no such console.
+pub fn attach_parent_console() -> bool {
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS).as_bool() }
+}
+
+/// Gets cursor position relative to the client area of the window, in physical pixels.
+pub fn cursor_position(hwnd: HWND) -> Option<(i32, i32)> {
+    let mut point = POINT::default();
+    unsafe {
+        if !GetCursorPos(&mut point).as_bool() || !ScreenToClient(hwnd, &mut point).as_bool() {
+            return None;
+        }
+    }
+    Some((point.x, point.y))
+}
+
 /// Proxies subclass window procedure to Rust objects.
 pub struct SubclassProxy(Box<dyn Fn(HWND, u32, WPARAM, LPARAM) -> bool + Send + Sync + 'static>);
 
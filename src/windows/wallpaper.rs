@@ -1,10 +1,13 @@
 //! Provides desktop wallpaper manipulation.
 
+use crate::backend::{Monitor, MonitorId, WallpaperBackend};
+
 use std::{
     collections::HashMap,
     ffi::OsString,
     mem::size_of,
     os::windows::prelude::{OsStrExt, OsStringExt},
+    path::Path,
     ptr::null,
     slice::from_raw_parts,
 };
@@ -18,141 +21,35 @@ use windows::{
         Graphics::Gdi::{EnumDisplayDevicesW, DISPLAY_DEVICEW},
         System::Com::{CoCreateInstance, CLSCTX_ALL},
         UI::{
-            Shell::{DesktopWallpaper, IDesktopWallpaper},
+            Shell::{DesktopWallpaper, IDesktopWallpaper, DWPOS_FILL},
             WindowsAndMessaging::EDD_GET_DEVICE_INTERFACE_NAME,
         },
     },
 };
 
-/// Identifies monitor.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct MonitorId(Box<[u16]>);
-
-impl MonitorId {
-    /// Gets PCWSTR pointer for COM interface.
-    /// Valid until the object will be destroyed.
-    pub fn as_pcwstr(&self) -> PCWSTR {
-        PCWSTR(self.0.as_ptr())
-    }
-
-    /// Converts into lossy `String`.
-    pub fn to_string_lossy<'a>(&'a self) -> String {
-        OsString::from_wide(&self.0).to_string_lossy().to_string()
-    }
-}
-
-/// Represents a monitor.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Monitor {
-    /// Monitor ID WSTR, which contains NUL word.
-    id: MonitorId,
-
-    /// Monitor name.
-    name: String,
-
-    /// Top-left monitor position.
-    position: Vec2<i32>,
-
-    /// Physical size of this monitor.
-    size: Vec2<i32>,
-}
-
-impl Monitor {
-    /// Gets monitor ID as `String`.
-    pub fn id(&self) -> &MonitorId {
-        &self.id
-    }
-
-    /// Gets monitor name.
-    pub fn name(&self) -> &str {
-        &self.name
-    }
-
-    /// Gets monitor position.
-    pub fn position(&self) -> Vec2<i32> {
-        self.position
-    }
+/// Provides wallpaper manipulations through `IDesktopWallpaper`.
+/// COM must be initialized for each calling thread.
+#[derive(Debug, Default)]
+pub struct WallpaperInterface;
 
-    /// Gets monitor size.
-    pub fn size(&self) -> Vec2<i32> {
-        self.size
+impl WallpaperInterface {
+    /// Constructs new interface.
+    pub fn new() -> WallpaperInterface {
+        WallpaperInterface
     }
-}
 
-/// Provides wallpaper manipulations.
-#[derive(Debug)]
-pub struct WallpaperInterface {
-    interface: IDesktopWallpaper,
-}
-
-impl WallpaperInterface {
-    /// Initializes `IDesktopWallpaper` internally.
-    pub fn new() -> Result<WallpaperInterface> {
+    /// Initializes `IDesktopWallpaper` for current thread.
+    fn interface(&self) -> Result<IDesktopWallpaper> {
         let interface: IDesktopWallpaper = unsafe {
             CoCreateInstance(&DesktopWallpaper, None, CLSCTX_ALL)
                 .context("Failed to initialize IDesktopWallper")?
         };
 
-        Ok(WallpaperInterface { interface })
-    }
-
-    /// Fetches connected monitors information.
-    pub fn monitors(&self) -> Result<Vec<Monitor>> {
-        let monitor_names = self.list_monitor_names();
-
-        let monitor_count = unsafe { self.interface.GetMonitorDevicePathCount()? } as usize;
-
-        let mut monitors = Vec::with_capacity(monitor_count);
-        for i in 0..monitor_count {
-            let id = unsafe {
-                let monitor_id_ptr = self.interface.GetMonitorDevicePathAt(i as u32)?.0;
-                let monitor_id_length = (0..std::isize::MAX)
-                    .position(|i| *monitor_id_ptr.offset(i) == 0)
-                    .context("Unterminated text")?;
-
-                // Contain NUL word
-                let id = from_raw_parts(monitor_id_ptr, monitor_id_length + 1)
-                    .to_vec()
-                    .into_boxed_slice();
-                MonitorId(id)
-            };
-
-            let rect = unsafe { self.interface.GetMonitorRECT(PCWSTR(id.0.as_ptr()))? };
-            let position = Vec2::new(rect.left, rect.top);
-            let size = Vec2::new(rect.right - rect.left, rect.bottom - rect.top);
-            let name = monitor_names
-                .get(&id)
-                .cloned()
-                .unwrap_or_else(|| format!("Monitor #{i}"));
-
-            monitors.push(Monitor {
-                id,
-                name,
-                position,
-                size,
-            })
-        }
-
-        Ok(monitors)
-    }
-
-    /// Sets wallpaper for monitor.
-    pub fn set_wallpaper(
-        &self,
-        monitor_id: &MonitorId,
-        wallpaper: impl Into<OsString>,
-    ) -> Result<()> {
-        let mut path: Vec<u16> = wallpaper.into().encode_wide().collect();
-        path.push(0);
-        unsafe {
-            self.interface
-                .SetWallpaper(monitor_id.as_pcwstr(), PCWSTR(path.as_ptr()))?;
-        }
-        Ok(())
+        Ok(interface)
     }
 
     /// Lists available monitor Ids.
-    fn list_monitor_names(&self) -> HashMap<MonitorId, String> {
+    fn list_monitor_names(&self) -> HashMap<String, String> {
         let mut display_device = DISPLAY_DEVICEW {
             cb: size_of::<DISPLAY_DEVICEW>() as u32,
             ..Default::default()
@@ -187,7 +84,9 @@ impl WallpaperInterface {
                 .iter()
                 .position(|&x| x == 0)
                 .expect("Unterminated text");
-            let id: Box<[u16]> = display_device.DeviceID[..(id_length + 1)].into();
+            let id = OsString::from_wide(&display_device.DeviceID[..id_length])
+                .to_string_lossy()
+                .to_string();
 
             let string_length = display_device
                 .DeviceString
@@ -198,10 +97,64 @@ impl WallpaperInterface {
                 .to_string_lossy()
                 .to_string();
 
-            name_pairs.insert(MonitorId(id), name);
+            name_pairs.insert(id, name);
             index += 1;
         }
 
         name_pairs
     }
 }
+
+impl WallpaperBackend for WallpaperInterface {
+    fn monitors(&self) -> Result<Vec<Monitor>> {
+        let interface = self.interface()?;
+        let monitor_names = self.list_monitor_names();
+
+        let monitor_count = unsafe { interface.GetMonitorDevicePathCount()? } as usize;
+
+        let mut monitors = Vec::with_capacity(monitor_count);
+        for i in 0..monitor_count {
+            // Contains NUL word
+            let wide_id = unsafe {
+                let monitor_id_ptr = interface.GetMonitorDevicePathAt(i as u32)?.0;
+                let monitor_id_length = (0..std::isize::MAX)
+                    .position(|i| *monitor_id_ptr.offset(i) == 0)
+                    .context("Unterminated text")?;
+
+                from_raw_parts(monitor_id_ptr, monitor_id_length + 1).to_vec()
+            };
+            let id = OsString::from_wide(&wide_id[..(wide_id.len() - 1)])
+                .to_string_lossy()
+                .to_string();
+
+            let rect = unsafe { interface.GetMonitorRECT(PCWSTR(wide_id.as_ptr()))? };
+            let position = Vec2::new(rect.left, rect.top);
+            let size = Vec2::new(rect.right - rect.left, rect.bottom - rect.top);
+            let name = monitor_names
+                .get(&id)
+                .cloned()
+                .unwrap_or_else(|| format!("Monitor #{i}"));
+
+            monitors.push(Monitor::new(MonitorId::new(id), name, position, size));
+        }
+
+        Ok(monitors)
+    }
+
+    /// Since images are rendered into exact monitor size, position is forced to fill,
+    /// especially to prevent spanning from discarding per-monitor images.
+    fn set_wallpaper(&self, monitor_id: &MonitorId, path: &Path) -> Result<()> {
+        let interface = self.interface()?;
+
+        let mut wide_id: Vec<u16> = OsString::from(monitor_id.as_str()).encode_wide().collect();
+        wide_id.push(0);
+        let mut wide_path: Vec<u16> = path.as_os_str().encode_wide().collect();
+        wide_path.push(0);
+
+        unsafe {
+            interface.SetPosition(DWPOS_FILL)?;
+            interface.SetWallpaper(PCWSTR(wide_id.as_ptr()), PCWSTR(wide_path.as_ptr()))?;
+        }
+        Ok(())
+    }
+}